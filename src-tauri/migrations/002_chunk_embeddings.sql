@@ -0,0 +1,2 @@
+-- 为文档块增加向量列（小端序 f32 数组）
+ALTER TABLE chunks ADD COLUMN embedding BLOB;
@@ -0,0 +1,12 @@
+-- 文档块全文索引（关键词检索）
+-- rowid 与 chunks.rowid 对应（chunks 没有整数主键，执行 VACUUM 后需重建索引）
+-- terms 为 Rust 端预先分词后以空格连接的文本
+CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+    terms,
+    tokenize = "unicode61 tokenchars '._-'"
+);
+
+-- 删除文档块（包括级联删除）时同步清理索引
+CREATE TRIGGER IF NOT EXISTS chunks_fts_delete AFTER DELETE ON chunks BEGIN
+    DELETE FROM chunks_fts WHERE rowid = old.rowid;
+END;
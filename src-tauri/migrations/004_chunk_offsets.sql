@@ -0,0 +1,3 @@
+-- 文档块在原文中的字符范围 [start_offset, end_offset)，用于引用定位
+ALTER TABLE chunks ADD COLUMN start_offset INTEGER;
+ALTER TABLE chunks ADD COLUMN end_offset INTEGER;
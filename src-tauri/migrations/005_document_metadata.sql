@@ -0,0 +1,2 @@
+-- 文档级元数据（JSON），例如网页标题和 canonical 链接
+ALTER TABLE documents ADD COLUMN metadata TEXT;
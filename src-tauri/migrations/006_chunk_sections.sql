@@ -0,0 +1,2 @@
+-- 块所属的章节（例如电子书的章标题）
+ALTER TABLE chunks ADD COLUMN section TEXT;
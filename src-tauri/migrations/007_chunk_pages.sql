@@ -0,0 +1,3 @@
+-- 块所在的起止页码（仅 PDF 等分页文档）
+ALTER TABLE chunks ADD COLUMN page_start INTEGER;
+ALTER TABLE chunks ADD COLUMN page_end INTEGER;
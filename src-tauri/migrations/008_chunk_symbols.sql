@@ -0,0 +1,2 @@
+-- 代码块所属的符号（函数、类型、impl 等）
+ALTER TABLE chunks ADD COLUMN symbol TEXT;
@@ -0,0 +1,19 @@
+-- 后台导入任务：记录来源、当前阶段和进度，支持取消、重试和重启后恢复
+-- document_id 在创建任务时生成，文档在分块完成后才写入 documents 表
+CREATE TABLE IF NOT EXISTS ingestion_jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    document_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    source TEXT NOT NULL, -- JSON：上传的文本内容或文件路径
+    chunk_strategy TEXT,
+    status TEXT NOT NULL CHECK(status IN ('queued', 'running', 'completed', 'failed', 'cancelled')),
+    stage TEXT NOT NULL DEFAULT 'extracting', -- extracting / embedding / indexing
+    progress_done INTEGER NOT NULL DEFAULT 0,
+    progress_total INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingestion_jobs_status ON ingestion_jobs(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_ingestion_jobs_document_id ON ingestion_jobs(document_id);
@@ -0,0 +1,18 @@
+-- 导入任务的暂存区：提取后的文档和分块结果（含已生成的向量）先写入这里，
+-- 任务完成时在同一事务中写入 documents / chunks，失败或取消时不会留下残缺的文档
+ALTER TABLE ingestion_jobs ADD COLUMN document TEXT; -- JSON：提取后的内容、文件类型和元数据
+
+CREATE TABLE IF NOT EXISTS ingestion_chunks (
+    job_id TEXT NOT NULL,
+    chunk_index INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    start_offset INTEGER,
+    end_offset INTEGER,
+    section TEXT,
+    page_start INTEGER,
+    page_end INTEGER,
+    symbol TEXT,
+    embedding BLOB,
+    PRIMARY KEY (job_id, chunk_index),
+    FOREIGN KEY (job_id) REFERENCES ingestion_jobs(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+-- 文档和文档块的内容哈希（SHA-256），用于重复检测和增量重建索引
+-- 旧数据的哈希在启动时补全
+ALTER TABLE documents ADD COLUMN content_hash TEXT;
+ALTER TABLE chunks ADD COLUMN content_hash TEXT;
+ALTER TABLE ingestion_chunks ADD COLUMN content_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_documents_content_hash ON documents(content_hash);
+CREATE INDEX IF NOT EXISTS idx_chunks_content_hash ON chunks(document_id, content_hash);
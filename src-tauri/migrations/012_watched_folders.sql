@@ -0,0 +1,10 @@
+-- 监听目录：目录中的文件变化后自动重新导入，删除的文件同步删除对应文档
+CREATE TABLE IF NOT EXISTS watched_folders (
+    id TEXT PRIMARY KEY NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    include TEXT NOT NULL DEFAULT '[]', -- JSON：只导入匹配的文件
+    exclude TEXT NOT NULL DEFAULT '[]', -- JSON：排除的文件或目录
+    chunk_strategy TEXT,
+    created_at INTEGER NOT NULL,
+    last_synced_at INTEGER
+);
@@ -0,0 +1,19 @@
+-- 知识库：每个知识库有独立的文档、向量索引以及分块和 Embedding 设置
+-- 升级前的数据全部归入默认知识库（不可删除）
+CREATE TABLE IF NOT EXISTS knowledge_bases (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    settings TEXT NOT NULL DEFAULT '{}', -- JSON：覆盖全局 RAG 配置的分块和 Embedding 设置
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO knowledge_bases (id, name, created_at, updated_at)
+VALUES ('default', '默认知识库', strftime('%s', 'now'), strftime('%s', 'now'));
+
+ALTER TABLE documents ADD COLUMN knowledge_base_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE ingestion_jobs ADD COLUMN knowledge_base_id TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE watched_folders ADD COLUMN knowledge_base_id TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_documents_knowledge_base_id ON documents(knowledge_base_id);
@@ -0,0 +1,16 @@
+-- 关键词索引改为按 chunks.id 关联：chunks 没有整数主键，VACUUM 会重新编号 rowid，
+-- 按 rowid 关联时检索结果会对应到错误的文档块
+-- 重建后的索引为空，启动时由 sync_keyword_index 补建
+DROP TRIGGER IF EXISTS chunks_fts_delete;
+DROP TABLE IF EXISTS chunks_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+    chunk_id UNINDEXED,
+    terms,
+    tokenize = "unicode61 tokenchars '._-'"
+);
+
+-- 删除文档块（包括级联删除）时同步清理索引
+CREATE TRIGGER IF NOT EXISTS chunks_fts_delete AFTER DELETE ON chunks BEGIN
+    DELETE FROM chunks_fts WHERE chunk_id = old.id;
+END;
@@ -0,0 +1,3 @@
+-- 生成文档向量的 Embedding 服务标识（提供方、地址和模型）
+-- 重新导入时只复用同一服务生成的向量，旧数据为空（不复用）
+ALTER TABLE documents ADD COLUMN embedding_model TEXT;
@@ -0,0 +1,22 @@
+-- 关键词索引改为通过映射表按整数键关联文档块：
+-- 014 中的 chunk_id 列不建索引，删除每个文档块时触发器都要扫描整个索引
+-- 映射表的 fts_rowid 即 chunks_fts 的 rowid，两者都不会因 VACUUM 重新编号
+-- 重建后的索引为空，启动时由 sync_keyword_index 补建
+DROP TRIGGER IF EXISTS chunks_fts_delete;
+DROP TABLE IF EXISTS chunks_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+    terms,
+    tokenize = "unicode61 tokenchars '._-'"
+);
+
+CREATE TABLE IF NOT EXISTS chunk_fts_map (
+    fts_rowid INTEGER PRIMARY KEY,
+    chunk_id TEXT NOT NULL UNIQUE
+);
+
+-- 删除文档块（包括级联删除）时按 rowid 同步清理索引
+CREATE TRIGGER IF NOT EXISTS chunks_fts_delete AFTER DELETE ON chunks BEGIN
+    DELETE FROM chunks_fts WHERE rowid = (SELECT fts_rowid FROM chunk_fts_map WHERE chunk_id = old.id);
+    DELETE FROM chunk_fts_map WHERE chunk_id = old.id;
+END;
@@ -0,0 +1,19 @@
+-- 同一目录可以被不同的知识库监听：唯一约束改为 (knowledge_base_id, path)
+-- SQLite 不能修改约束，重建表后复制数据
+CREATE TABLE watched_folders_new (
+    id TEXT PRIMARY KEY NOT NULL,
+    path TEXT NOT NULL,
+    include TEXT NOT NULL DEFAULT '[]', -- JSON：只导入匹配的文件
+    exclude TEXT NOT NULL DEFAULT '[]', -- JSON：排除的文件或目录
+    chunk_strategy TEXT,
+    created_at INTEGER NOT NULL,
+    last_synced_at INTEGER,
+    knowledge_base_id TEXT NOT NULL DEFAULT 'default',
+    UNIQUE (knowledge_base_id, path)
+);
+
+INSERT INTO watched_folders_new (id, path, include, exclude, chunk_strategy, created_at, last_synced_at, knowledge_base_id)
+SELECT id, path, include, exclude, chunk_strategy, created_at, last_synced_at, knowledge_base_id FROM watched_folders;
+
+DROP TABLE watched_folders;
+ALTER TABLE watched_folders_new RENAME TO watched_folders;
@@ -1,44 +1,417 @@
 use crate::db::Database;
-use crate::rag::{embedding::EmbeddingService, llm::LLMService, vector_store::VectorStore, RAGConfig};
+use crate::hash::content_hash;
+use crate::rag::{
+    embedding::EmbeddingService,
+    keyword,
+    knowledge_base::{KnowledgeBaseSettings, DEFAULT_KNOWLEDGE_BASE_ID},
+    llm::LLMService,
+    rerank::RerankService,
+    vector_store::{decode_embedding, write_index_file, VectorDocument, VectorStore},
+    RAGConfig,
+};
+use notify::RecommendedWatcher;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
 use anyhow::Result;
 
+/// 读取已生成向量的块及其所属文档信息
+const INDEXED_CHUNKS_QUERY: &str =
+    "SELECT c.id, c.document_id, c.content, c.chunk_index, d.name AS document_name, d.metadata AS document_metadata,
+            d.knowledge_base_id,
+            c.embedding, c.start_offset, c.end_offset, c.section, c.page_start, c.page_end, c.symbol
+     FROM chunks c
+     JOIN documents d ON d.id = c.document_id
+     WHERE c.embedding IS NOT NULL";
+
+/// 加载向量存储时读取的块及其所属文档信息
+#[derive(sqlx::FromRow)]
+struct IndexedChunk {
+    id: String,
+    document_id: String,
+    content: String,
+    chunk_index: i64,
+    document_name: String,
+    document_metadata: Option<String>,
+    knowledge_base_id: String,
+    embedding: Vec<u8>,
+    start_offset: Option<i64>,
+    end_offset: Option<i64>,
+    section: Option<String>,
+    page_start: Option<i64>,
+    page_end: Option<i64>,
+    symbol: Option<String>,
+}
+
+/// 将数据库中的块转换为向量存储中的文档（携带引用来源所需的元数据）
+fn to_vector_document(row: IndexedChunk) -> VectorDocument {
+    let document_metadata = row.document_metadata
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+        .unwrap_or_default();
+    let document_field = |key: &str| document_metadata.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    
+    VectorDocument {
+        id: row.id,
+        content: row.content,
+        embedding: decode_embedding(&row.embedding),
+        metadata: serde_json::json!({
+            "document_id": row.document_id,
+            "knowledge_base_id": row.knowledge_base_id,
+            "chunk_index": row.chunk_index,
+            "document_name": row.document_name,
+            "document_title": document_field("title"),
+            "start_offset": row.start_offset,
+            "end_offset": row.end_offset,
+            "section": row.section,
+            "page_start": row.page_start,
+            "page_end": row.page_end,
+            "symbol": row.symbol,
+            "language": document_field("language"),
+            "source_path": document_field("source_path"),
+        }),
+    }
+}
+
+/// 读取单个文档已生成向量的块（可在事务中调用，用于导入和修复时写入向量存储）
+pub async fn document_vectors(conn: &mut SqliteConnection, document_id: &str) -> Result<Vec<VectorDocument>> {
+    let rows = sqlx::query_as::<_, IndexedChunk>(&format!(
+        "{} AND c.document_id = ? ORDER BY c.chunk_index",
+        INDEXED_CHUNKS_QUERY
+    ))
+    .bind(document_id)
+    .fetch_all(conn)
+    .await?;
+    
+    Ok(rows.into_iter().map(to_vector_document).collect())
+}
+
 /// 应用全局状态
 pub struct AppState {
     pub db: Database,
-    pub vector_store: Arc<Mutex<VectorStore>>,
-    pub embedding_service: Arc<Mutex<Option<EmbeddingService>>>,
-    pub llm_service: Arc<Mutex<Option<LLMService>>>,
+    /// 各知识库的向量存储（按知识库 ID），不同知识库的向量维度可能不同
+    pub vector_stores: Arc<Mutex<HashMap<String, Arc<VectorStore>>>>,
+    pub embedding_service: Arc<Mutex<Option<Arc<EmbeddingService>>>>,
+    /// 单独设置了 Embedding 模型的知识库使用的服务（按知识库 ID 缓存）
+    knowledge_base_embeddings: Arc<Mutex<HashMap<String, Arc<EmbeddingService>>>>,
+    /// 初始化 RAG 服务时使用的 API Key，用于创建知识库单独的 Embedding 服务
+    api_key: Arc<Mutex<Option<String>>>,
+    pub llm_service: Arc<Mutex<Option<Arc<LLMService>>>>,
+    /// 重排序服务（未启用时为 None）
+    pub rerank_service: Arc<Mutex<Option<Arc<RerankService>>>>,
     pub rag_config: Arc<Mutex<RAGConfig>>,
+    /// 唤醒后台导入任务
+    pub ingestion_notify: Arc<Notify>,
+    /// 监听目录的文件监听器（按目录 ID），移除后停止监听
+    pub folder_watchers: Arc<Mutex<HashMap<String, RecommendedWatcher>>>,
+    /// HNSW 索引文件所在目录
+    data_dir: PathBuf,
+    /// 串行化索引写盘，保证最后写入的是最新快照
+    index_save_lock: Arc<Mutex<()>>,
 }
 
 impl AppState {
-    pub async fn new(db: Database) -> Result<Self> {
-        Ok(Self {
+    pub async fn new(db: Database, data_dir: &Path) -> Result<Self> {
+        let state = Self {
             db,
-            vector_store: Arc::new(Mutex::new(VectorStore::new())),
+            vector_stores: Arc::new(Mutex::new(HashMap::new())),
             embedding_service: Arc::new(Mutex::new(None)),
+            knowledge_base_embeddings: Arc::new(Mutex::new(HashMap::new())),
+            api_key: Arc::new(Mutex::new(None)),
             llm_service: Arc::new(Mutex::new(None)),
+            rerank_service: Arc::new(Mutex::new(None)),
             rag_config: Arc::new(Mutex::new(RAGConfig::default())),
-        })
+            ingestion_notify: Arc::new(Notify::new()),
+            folder_watchers: Arc::new(Mutex::new(HashMap::new())),
+            data_dir: data_dir.to_path_buf(),
+            index_save_lock: Arc::new(Mutex::new(())),
+        };
+        
+        // 加载持久化的 RAG 配置
+        state.load_rag_config().await?;
+        
+        // 从数据库恢复向量索引，保证重启后知识库可用
+        state.load_vector_store().await?;
+        
+        // 为升级前已有的文档块补建关键词索引
+        state.sync_keyword_index().await?;
+        
+        // 为升级前已有的文档和文档块补全内容哈希
+        state.sync_content_hashes().await?;
+        
+        Ok(state)
+    }
+    
+    /// 从数据库加载 RAG 配置（未保存过则使用默认值）
+    pub async fn load_rag_config(&self) -> Result<()> {
+        let saved = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM settings WHERE key = ?"
+        )
+        .bind("rag_config")
+        .fetch_optional(self.db.pool())
+        .await?;
+        
+        if let Some(json) = saved {
+            match serde_json::from_str::<RAGConfig>(&json) {
+                Ok(config) => *self.rag_config.lock().unwrap() = config,
+                Err(e) => println!("⚠️ RAG 配置解析失败，使用默认配置: {}", e),
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 从数据库加载已持久化的向量到各知识库的内存向量存储
+    pub async fn load_vector_store(&self) -> Result<usize> {
+        let rows = sqlx::query_as::<_, IndexedChunk>(&format!(
+            "{} ORDER BY c.document_id, c.chunk_index",
+            INDEXED_CHUNKS_QUERY
+        ))
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        let mut grouped: HashMap<String, Vec<VectorDocument>> = HashMap::new();
+        for row in rows {
+            grouped.entry(row.knowledge_base_id.clone()).or_default().push(to_vector_document(row));
+        }
+        
+        let mut count = 0;
+        for (knowledge_base_id, vector_docs) in grouped {
+            count += vector_docs.len();
+            let reused = self.vector_store(&knowledge_base_id)
+                .restore(vector_docs, Some(&self.index_path(&knowledge_base_id)));
+            
+            // 首次构建或索引文件失效时，保存新建的索引图
+            if !reused {
+                self.persist_vector_index(&knowledge_base_id);
+            }
+        }
+        
+        // 旧版本写入的块没有向量，无法参与检索
+        let missing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chunks WHERE embedding IS NULL")
+            .fetch_one(self.db.pool())
+            .await?;
+        
+        if missing > 0 {
+            println!("⚠️ 有 {} 个文档块缺少向量，可在知识库中执行修复", missing);
+        }
+        
+        println!("📚 已从数据库加载 {} 个向量", count);
+        Ok(count)
+    }
+    
+    /// 为缺少关键词索引的文档块建立 FTS5 索引并清理已删除文档块的索引，返回补建数量
+    pub async fn sync_keyword_index(&self) -> Result<usize> {
+        // 清理两侧已不对应的记录（不相关子查询只执行一次，映射表两列都有索引）
+        let mut stale = 0;
+        for sql in [
+            "DELETE FROM chunk_fts_map WHERE chunk_id NOT IN (SELECT id FROM chunks)",
+            "DELETE FROM chunk_fts_map WHERE fts_rowid NOT IN (SELECT rowid FROM chunks_fts)",
+            "DELETE FROM chunks_fts WHERE rowid NOT IN (SELECT fts_rowid FROM chunk_fts_map)",
+        ] {
+            stale += sqlx::query(sql).execute(self.db.pool()).await?.rows_affected();
+        }
+        if stale > 0 {
+            println!("🧹 已清理 {} 条失效的关键词索引", stale);
+        }
+        
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, content FROM chunks WHERE id NOT IN (SELECT chunk_id FROM chunk_fts_map)"
+        )
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        
+        let mut tx = self.db.pool().begin().await?;
+        for (chunk_id, content) in &rows {
+            keyword::index_chunk(&mut tx, chunk_id, content).await?;
+        }
+        tx.commit().await?;
+        
+        println!("🔤 已为 {} 个文档块补建关键词索引", rows.len());
+        Ok(rows.len())
+    }
+    
+    /// 为缺少内容哈希的文档和文档块计算哈希，返回补全数量
+    pub async fn sync_content_hashes(&self) -> Result<usize> {
+        let documents = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, content FROM documents WHERE content_hash IS NULL"
+        )
+        .fetch_all(self.db.pool())
+        .await?;
+        let chunks = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, content FROM chunks WHERE content_hash IS NULL"
+        )
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        let count = documents.len() + chunks.len();
+        if count == 0 {
+            return Ok(0);
+        }
+        
+        let mut tx = self.db.pool().begin().await?;
+        for (id, content) in &documents {
+            sqlx::query("UPDATE documents SET content_hash = ? WHERE id = ?")
+                .bind(content_hash(content))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for (id, content) in &chunks {
+            sqlx::query("UPDATE chunks SET content_hash = ? WHERE id = ?")
+                .bind(content_hash(content))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        
+        println!("#️⃣ 已为 {} 个文档和 {} 个文档块补全内容哈希", documents.len(), chunks.len());
+        Ok(count)
+    }
+    
+    /// 获取知识库的向量存储（不存在时创建空的存储）
+    pub fn vector_store(&self, knowledge_base_id: &str) -> Arc<VectorStore> {
+        self.vector_stores
+            .lock()
+            .unwrap()
+            .entry(knowledge_base_id.to_string())
+            .or_insert_with(|| Arc::new(VectorStore::new()))
+            .clone()
+    }
+    
+    /// 知识库 HNSW 索引文件路径（默认知识库沿用升级前的文件名）
+    fn index_path(&self, knowledge_base_id: &str) -> PathBuf {
+        if knowledge_base_id == DEFAULT_KNOWLEDGE_BASE_ID {
+            self.data_dir.join("vector_index.bin")
+        } else {
+            self.data_dir.join(format!("vector_index_{}.bin", knowledge_base_id))
+        }
+    }
+    
+    /// 移除知识库的向量存储并删除其索引文件
+    pub fn remove_vector_store(&self, knowledge_base_id: &str) {
+        self.vector_stores.lock().unwrap().remove(knowledge_base_id);
+        self.knowledge_base_embeddings.lock().unwrap().remove(knowledge_base_id);
+        
+        let _guard = self.index_save_lock.lock().unwrap();
+        let path = self.index_path(knowledge_base_id);
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                println!("⚠️ 删除向量索引文件失败: {}", e);
+            }
+        }
+    }
+    
+    /// 保存所有知识库的向量索引
+    pub fn persist_all_vector_indexes(&self) {
+        let knowledge_base_ids: Vec<String> = self.vector_stores.lock().unwrap().keys().cloned().collect();
+        for knowledge_base_id in knowledge_base_ids {
+            self.persist_vector_index(&knowledge_base_id);
+        }
+    }
+    
+    /// 在后台线程将知识库的 HNSW 索引图写入应用数据目录
+    pub fn persist_vector_index(&self, knowledge_base_id: &str) {
+        let vector_store = self.vector_store(knowledge_base_id);
+        let save_lock = self.index_save_lock.clone();
+        let path = self.index_path(knowledge_base_id);
+        
+        tokio::task::spawn_blocking(move || {
+            let _guard = save_lock.lock().unwrap();
+            let snapshot = vector_store.snapshot_index();
+            
+            match snapshot.and_then(|bytes| write_index_file(&path, &bytes)) {
+                Ok(()) => println!("💾 向量索引已保存"),
+                Err(e) => println!("⚠️ 向量索引保存失败: {}", e),
+            }
+        });
     }
     
     /// 初始化 RAG 服务
-    pub fn init_rag_services(&self, api_key: String) {
-        let config = self.rag_config.lock().unwrap();
+    pub fn init_rag_services(&self, api_key: String) -> Result<()> {
+        let config = self.rag_config.lock().unwrap().clone();
+        
+        let embedding_service = EmbeddingService::from_config(&config, &api_key)?;
         
-        let embedding_service = EmbeddingService::new(
-            api_key.clone(),
-            config.embedding_model.clone(),
-        );
+        let llm_service = Arc::new(LLMService::from_config(&config, &api_key)?);
         
-        let llm_service = LLMService::new(
-            api_key,
-            config.llm_model.clone(),
-        );
+        let rerank_service = RerankService::from_config(&config, &api_key, llm_service.clone())?;
         
-        *self.embedding_service.lock().unwrap() = Some(embedding_service);
+        *self.embedding_service.lock().unwrap() = Some(Arc::new(embedding_service));
         *self.llm_service.lock().unwrap() = Some(llm_service);
+        *self.rerank_service.lock().unwrap() = rerank_service.map(Arc::new);
+        *self.api_key.lock().unwrap() = Some(api_key);
+        self.knowledge_base_embeddings.lock().unwrap().clear();
+        
+        Ok(())
+    }
+    
+    /// 读取知识库设置（知识库不存在时返回错误）
+    pub async fn knowledge_base_settings(&self, knowledge_base_id: &str) -> Result<KnowledgeBaseSettings, String> {
+        let settings = sqlx::query_scalar::<_, String>("SELECT settings FROM knowledge_bases WHERE id = ?")
+            .bind(knowledge_base_id)
+            .fetch_optional(self.db.pool())
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("知识库不存在: {}", knowledge_base_id))?;
+        
+        Ok(serde_json::from_str(&settings).unwrap_or_default())
+    }
+    
+    /// 获取知识库生效的 RAG 配置和 Embedding 服务
+    pub async fn knowledge_base_services(&self, knowledge_base_id: &str) -> Result<(RAGConfig, Arc<EmbeddingService>), String> {
+        let settings = self.knowledge_base_settings(knowledge_base_id).await?;
+        let config = settings.apply(&self.rag_config.lock().unwrap());
+        
+        if !settings.has_embedding_override() {
+            return Ok((config, self.embedding_service()?));
+        }
+        
+        if let Some(service) = self.knowledge_base_embeddings.lock().unwrap().get(knowledge_base_id) {
+            return Ok((config, service.clone()));
+        }
+        
+        let api_key = self.api_key.lock().unwrap().clone()
+            .ok_or_else(|| "Embedding 服务未初始化".to_string())?;
+        let service = Arc::new(EmbeddingService::from_config(&config, &api_key).map_err(|e| e.to_string())?);
+        self.knowledge_base_embeddings.lock().unwrap()
+            .insert(knowledge_base_id.to_string(), service.clone());
+        
+        Ok((config, service))
+    }
+    
+    /// 知识库设置修改后丢弃缓存的 Embedding 服务
+    pub fn invalidate_knowledge_base(&self, knowledge_base_id: &str) {
+        self.knowledge_base_embeddings.lock().unwrap().remove(knowledge_base_id);
+    }
+    
+    /// 获取 Embedding 服务（克隆 Arc，避免跨 await 持有锁）
+    pub fn embedding_service(&self) -> Result<Arc<EmbeddingService>, String> {
+        self.embedding_service
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "Embedding 服务未初始化".to_string())
+    }
+    
+    /// 获取 LLM 服务（克隆 Arc，避免跨 await 持有锁）
+    pub fn llm_service(&self) -> Result<Arc<LLMService>, String> {
+        self.llm_service
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "LLM 服务未初始化".to_string())
+    }
+    
+    /// 获取重排序服务（未启用重排序时返回 None）
+    pub fn rerank_service(&self) -> Option<Arc<RerankService>> {
+        self.rerank_service.lock().unwrap().clone()
     }
     
     /// 检查 RAG 服务是否已初始化
@@ -48,3 +421,11 @@ impl AppState {
     }
 }
 
+/// 测试用：在临时数据目录中创建 AppState，目录随返回的 TempDataDir 一起删除
+#[cfg(test)]
+pub(crate) async fn test_state() -> (AppState, crate::db::TempDataDir) {
+    let data_dir = crate::db::TempDataDir::new();
+    let db = Database::new(&data_dir).await.unwrap();
+    let state = AppState::new(db, &data_dir).await.unwrap();
+    (state, data_dir)
+}
@@ -1,21 +1,229 @@
 use crate::app_state::AppState;
 use crate::db::models::{Conversation, Message};
-use tauri::State;
+use crate::rag::citation::{self, ContextPassage};
+use crate::rag::embedding::EmbeddingService;
+use crate::rag::hybrid::{reciprocal_rank_fusion, DEFAULT_RRF_K};
+use crate::rag::keyword;
+use crate::rag::llm::ChatMessage;
+use crate::rag::rerank::RerankService;
+use crate::rag::vector_store::SearchResult;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
 use uuid::Uuid;
-use reqwest::Client;
+
+/// 历史消息带入提示词时的最大字符数，避免长回答挤占上下文
+const MAX_HISTORY_MESSAGE_CHARS: usize = 1000;
+
+/// 流式问答事件：增量文本
+pub const EVENT_CHAT_DELTA: &str = "chat://delta";
+/// 流式问答事件：回答完成（携带来源）
+pub const EVENT_CHAT_DONE: &str = "chat://done";
+/// 流式问答事件：出错
+pub const EVENT_CHAT_ERROR: &str = "chat://error";
 
 #[derive(serde::Deserialize)]
 pub struct AskQuestionRequest {
     question: String,
     conversation_id: Option<String>,
+    /// 检索的知识库，为空时检索全部知识库
+    #[serde(default)]
+    knowledge_base_ids: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
 pub struct AskQuestionResponse {
     success: bool,
     answer: String,
-    sources: Vec<String>,
+    sources: Vec<MessageSource>,
+    conversation_id: String,
+}
+
+/// 流式问答开始后立即返回，前端据此匹配后续事件
+#[derive(serde::Serialize)]
+pub struct AskQuestionStreamStarted {
+    conversation_id: String,
+    message_id: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ChatDeltaEvent {
+    conversation_id: String,
+    message_id: String,
+    delta: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ChatDoneEvent {
+    conversation_id: String,
+    message_id: String,
+    answer: String,
+    sources: Vec<MessageSource>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ChatErrorEvent {
     conversation_id: String,
+    message_id: String,
+    error: String,
+}
+
+/// 来源摘要的最大字符数
+const SOURCE_SNIPPET_CHARS: usize = 120;
+
+/// 回答引用的来源文档块（以 JSON 数组保存在 messages.sources 中）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MessageSource {
+    document_id: String,
+    document_name: String,
+    chunk_id: String,
+    #[serde(default)]
+    chunk_index: i64,
+    /// 块在原文中的字符范围 [start_offset, end_offset)，旧数据为空
+    #[serde(default)]
+    start_offset: Option<i64>,
+    #[serde(default)]
+    end_offset: Option<i64>,
+    /// 块所属章节（电子书章标题等）
+    #[serde(default)]
+    section: Option<String>,
+    /// 块所在的起止页码（仅 PDF 等分页文档）
+    #[serde(default)]
+    page_start: Option<i64>,
+    #[serde(default)]
+    page_end: Option<i64>,
+    /// 代码块所属的符号（函数、类型等）及编程语言
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    /// 展示用的来源名称，例如「书名 › 章节」「file.pdf p.12」
+    #[serde(default)]
+    label: String,
+    /// 与问题的向量相似度
+    similarity: f32,
+    /// 重排序得分（未启用重排序时为空）
+    rerank_score: Option<f32>,
+    /// 块内容开头的摘要
+    #[serde(default)]
+    snippet: String,
+    /// 提示词中的片段编号，对应回答里的 [n] 标记
+    #[serde(default)]
+    citation: usize,
+    /// 回答中是否引用了该片段
+    #[serde(default)]
+    cited: bool,
+}
+
+impl MessageSource {
+    fn from_result(result: &SearchResult, citation: usize, rerank_score: Option<f32>) -> Self {
+        let metadata = &result.document.metadata;
+        let metadata_str = |key: &str| {
+            metadata.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let metadata_i64 = |key: &str| metadata.get(key).and_then(|v| v.as_i64());
+        Self {
+            document_id: metadata_str("document_id"),
+            document_name: metadata_str("document_name"),
+            chunk_id: result.document.id.clone(),
+            chunk_index: metadata_i64("chunk_index").unwrap_or_default(),
+            start_offset: metadata_i64("start_offset"),
+            end_offset: metadata_i64("end_offset"),
+            section: metadata.get("section").and_then(|v| v.as_str()).map(str::to_string),
+            page_start: metadata_i64("page_start"),
+            page_end: metadata_i64("page_end"),
+            symbol: metadata.get("symbol").and_then(|v| v.as_str()).map(str::to_string),
+            language: metadata.get("language").and_then(|v| v.as_str()).map(str::to_string),
+            label: source_label(metadata),
+            similarity: result.similarity,
+            rerank_score,
+            snippet: snippet(&result.document.content),
+            citation,
+            cited: false,
+        }
+    }
+}
+
+/// 来源名称：有章节时为「文档标题 › 章节」（无标题时使用文件名），代码块为「文件名 › 符号」，
+/// 否则为文件名；分页文档在末尾加上页码，例如「file.pdf p.12」「file.pdf p.12-13」
+fn source_label(metadata: &serde_json::Value) -> String {
+    let metadata_str = |key: &str| metadata.get(key).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+    let document_name = metadata_str("document_name").unwrap_or_default();
+    
+    let mut label = match (metadata_str("section"), metadata_str("symbol")) {
+        (Some(section), _) => {
+            let title = metadata_str("document_title").unwrap_or(document_name);
+            format!("{} › {}", title, section)
+        }
+        (None, Some(symbol)) => format!("{} › {}", document_name, symbol),
+        (None, None) => document_name.to_string(),
+    };
+    
+    let page_start = metadata.get("page_start").and_then(|v| v.as_i64());
+    let page_end = metadata.get("page_end").and_then(|v| v.as_i64());
+    match (page_start, page_end) {
+        (Some(start), Some(end)) if end > start => label.push_str(&format!(" p.{}-{}", start, end)),
+        (Some(start), _) => label.push_str(&format!(" p.{}", start)),
+        _ => {}
+    }
+    
+    label
+}
+
+/// 构建提示词中的参考片段：代码块标注文件路径、符号和语言，并放入代码围栏
+fn context_passage(result: &SearchResult, source: &MessageSource) -> ContextPassage {
+    let content = &result.document.content;
+    
+    match &source.language {
+        Some(language) => {
+            let path = result.document.metadata.get("source_path")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&source.document_name);
+            let title = match &source.symbol {
+                Some(symbol) => format!("{} › {}（{}）", path, symbol, language),
+                None => format!("{}（{}）", path, language),
+            };
+            ContextPassage {
+                title,
+                content: format!("```{}\n{}\n```", language, content),
+            }
+        }
+        None => ContextPassage {
+            title: source.label.clone(),
+            content: content.clone(),
+        },
+    }
+}
+
+/// 截取块内容开头作为摘要（合并空白，超长时加省略号）
+fn snippet(content: &str) -> String {
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SOURCE_SNIPPET_CHARS {
+        return text;
+    }
+    
+    let mut snippet: String = text.chars().take(SOURCE_SNIPPET_CHARS).collect();
+    snippet.push('…');
+    snippet
+}
+
+/// 消息引用的文档块及其完整内容
+#[derive(serde::Serialize)]
+pub struct CitedChunk {
+    #[serde(flatten)]
+    source: MessageSource,
+    /// 块的完整内容（文档已删除时为空）
+    content: Option<String>,
+}
+
+/// 检索得到的上下文
+struct RetrievedContext {
+    /// 按编号顺序排列的参考片段
+    passages: Vec<ContextPassage>,
+    sources: Vec<MessageSource>,
 }
 
 /// RAG 问答
@@ -29,197 +237,454 @@ pub async fn ask_question(
         return Err("请先配置 API Key".to_string());
     }
     
-    // 1. 将问题向量化
-    let question_embedding = {
-        let service_arc = state.embedding_service.clone();
-        let question_clone = request.question.clone();
-        
-        // 在独立的代码块中获取服务和调用方法
-        let (api_key, model) = {
-            let guard = service_arc.lock().unwrap();
-            let service = guard.as_ref().ok_or("Embedding 服务未初始化")?;
-            (service.api_key().clone(), service.model().clone())
-        }; // guard 在这里释放
-        
-        // 调用 API
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
+    let conversation_id = request.conversation_id
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    
+    let history = load_history(&state, &conversation_id).await?;
+    let mut retrieved = retrieve_context(&state, &request.question, &history, &request.knowledge_base_ids).await?;
+    
+    // 调用 LLM 生成答案
+    let answer = state.llm_service()?
+        .answer_with_history(&request.question, &retrieved.passages, &history)
+        .await
+        .map_err(|e| format!("生成回答失败: {}", e))?;
+    let answer = apply_citations(&answer, &mut retrieved.sources);
+    
+    // 保存对话历史
+    let ai_msg_id = Uuid::new_v4().to_string();
+    
+    save_exchange(&state, &conversation_id, &request.question, &ai_msg_id, &answer, &retrieved.sources).await?;
+    
+    Ok(AskQuestionResponse {
+        success: true,
+        answer,
+        sources: retrieved.sources,
+        conversation_id,
+    })
+}
+
+/// 流式 RAG 问答
+///
+/// 立即返回对话和消息 ID，回答通过 `chat://delta` 事件逐段推送，
+/// 结束时发送携带来源的 `chat://done` 事件，出错时发送 `chat://error` 事件。
+#[tauri::command]
+pub async fn ask_question_stream(
+    request: AskQuestionRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AskQuestionStreamStarted, String> {
+    if !state.is_rag_initialized() {
+        return Err("请先配置 API Key".to_string());
+    }
+    
+    let conversation_id = request.conversation_id
+        .clone()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let message_id = Uuid::new_v4().to_string();
+    
+    let started = AskQuestionStreamStarted {
+        conversation_id: conversation_id.clone(),
+        message_id: message_id.clone(),
+    };
+    
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
         
-        let request_body = serde_json::json!({
-            "model": model,
-            "input": {
-                "texts": [question_clone]
-            }
+        if let Err(error) = stream_answer(
+            &app,
+            &state,
+            &request.question,
+            &request.knowledge_base_ids,
+            &conversation_id,
+            &message_id,
+        ).await {
+            println!("❌ 流式问答失败: {}", error);
+            let _ = app.emit(EVENT_CHAT_ERROR, ChatErrorEvent {
+                conversation_id,
+                message_id,
+                error,
+            });
+        }
+    });
+    
+    Ok(started)
+}
+
+/// 生成流式回答，完成后保存消息并发送完成事件
+async fn stream_answer(
+    app: &AppHandle,
+    state: &AppState,
+    question: &str,
+    knowledge_base_ids: &[String],
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<(), String> {
+    let history = load_history(state, conversation_id).await?;
+    let mut retrieved = retrieve_context(state, question, &history, knowledge_base_ids).await?;
+    
+    let on_delta = |delta: &str| {
+        let _ = app.emit(EVENT_CHAT_DELTA, ChatDeltaEvent {
+            conversation_id: conversation_id.to_string(),
+            message_id: message_id.to_string(),
+            delta: delta.to_string(),
         });
-        
-        let response = client
-            .post("https://dashscope.aliyuncs.com/api/v1/services/embeddings/text-embedding/text-embedding")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
+    };
+    
+    let answer = state.llm_service()?
+        .answer_with_history_stream(question, &retrieved.passages, &history, &on_delta)
+        .await
+        .map_err(|e| format!("生成回答失败: {}", e))?;
+    
+    // 完成事件中的回答已校验引用标记，前端以此替换增量拼接的文本
+    let answer = apply_citations(&answer, &mut retrieved.sources);
+    
+    save_exchange(state, conversation_id, question, message_id, &answer, &retrieved.sources).await?;
+    
+    app.emit(EVENT_CHAT_DONE, ChatDoneEvent {
+        conversation_id: conversation_id.to_string(),
+        message_id: message_id.to_string(),
+        answer,
+        sources: retrieved.sources,
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// 加载对话最近的若干轮消息（按时间正序），新对话返回空列表
+async fn load_history(
+    state: &AppState,
+    conversation_id: &str,
+) -> Result<Vec<ChatMessage>, String> {
+    let history_turns = state.rag_config.lock().unwrap().history_turns;
+    if history_turns == 0 {
+        return Ok(Vec::new());
+    }
+    
+    // 同一轮问答的时间戳相同，用 rowid 保证插入顺序
+    let rows = sqlx::query_as::<_, (String, String, Option<String>)>(
+        "SELECT role, content, sources FROM messages
+         WHERE conversation_id = ?
+         ORDER BY created_at DESC, rowid DESC
+         LIMIT ?"
+    )
+    .bind(conversation_id)
+    .bind((history_turns * 2) as i64)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    
+    Ok(rows
+        .into_iter()
+        .rev()
+        .map(|(role, content, sources)| {
+            // 历史回答中的引用编号指向上一轮的参考片段，带入提示词前移除
+            let content = if role == "assistant" {
+                let passage_count = sources
+                    .and_then(|s| serde_json::from_str::<Vec<serde_json::Value>>(&s).ok())
+                    .map_or(0, |sources| sources.len());
+                citation::strip_citations(&content, passage_count)
+            } else {
+                content
+            };
+            let content = if content.chars().count() > MAX_HISTORY_MESSAGE_CHARS {
+                format!("{}...", content.chars().take(MAX_HISTORY_MESSAGE_CHARS).collect::<String>())
+            } else {
+                content
+            };
+            ChatMessage { role, content }
+        })
+        .collect())
+}
+
+/// 将追问改写为独立问题；改写失败时回退到原问题，不影响问答
+async fn condense_search_query(
+    state: &AppState,
+    question: &str,
+    history: &[ChatMessage],
+) -> String {
+    let enabled = state.rag_config.lock().unwrap().condense_question;
+    if !enabled || history.is_empty() {
+        return question.to_string();
+    }
+    
+    let llm_service = match state.llm_service() {
+        Ok(service) => service,
+        Err(_) => return question.to_string(),
+    };
+    
+    match llm_service.condense_question(history, question).await {
+        Ok(condensed) => {
+            println!("🔁 追问改写: {} -> {}", question, condensed);
+            condensed
+        }
+        Err(e) => {
+            println!("⚠️ 追问改写失败，使用原问题检索: {}", e);
+            question.to_string()
+        }
+    }
+}
+
+/// 结合对话历史改写问题，向量化并在指定知识库（为空时为全部知识库）中检索相关文档块
+async fn retrieve_context(
+    state: &AppState,
+    question: &str,
+    history: &[ChatMessage],
+    knowledge_base_ids: &[String],
+) -> Result<RetrievedContext, String> {
+    let search_query = condense_search_query(state, question, history).await;
+    
+    let knowledge_base_ids = if knowledge_base_ids.is_empty() {
+        sqlx::query_scalar::<_, String>("SELECT id FROM knowledge_bases ORDER BY created_at")
+            .fetch_all(state.db.pool())
             .await
-            .map_err(|e| format!("Embedding API网络请求失败: {}. 请检查网络连接", e))?;
-        
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.map_err(|e| e.to_string())?;
-            return Err(format!("Embedding API 请求失败: {} - {}", status, text));
+            .map_err(|e| e.to_string())?
+    } else {
+        knowledge_base_ids.to_vec()
+    };
+    
+    // 召回候选文档块：启用重排序时多取一些候选，再由重排序模型挑选
+    let (top_k, candidate_k, hybrid_search, max_context_tokens) = {
+        let config = state.rag_config.lock().unwrap();
+        (config.top_k, config.candidate_k.max(config.top_k), config.hybrid_search, config.max_context_tokens)
+    }; // config 的 MutexGuard 在这里释放
+    
+    let rerank_service = state.rerank_service();
+    let retrieve_k = if rerank_service.is_some() { candidate_k } else { top_k };
+    
+    // 1. 在每个知识库中分别检索：问题用该知识库的 Embedding 模型向量化，模型相同的知识库共用一次向量化
+    let mut embeddings: Vec<(Arc<EmbeddingService>, Vec<f32>)> = Vec::new();
+    let mut rankings: Vec<Vec<SearchResult>> = Vec::new();
+    for knowledge_base_id in &knowledge_base_ids {
+        let (_, embedding_service) = state.knowledge_base_services(knowledge_base_id).await?;
+        if state.vector_store(knowledge_base_id).is_empty() {
+            continue;
         }
         
-        let result: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
-        
-        if let Some(embeddings) = result["output"]["embeddings"].as_array() {
-            if let Some(first) = embeddings.first() {
-                if let Some(embedding) = first["embedding"].as_array() {
-                    embedding.iter()
-                        .filter_map(|v| v.as_f64().map(|f| f as f32))
-                        .collect::<Vec<f32>>()
-                } else {
-                    return Err("Embedding API 返回空向量".to_string());
-                }
-            } else {
-                return Err("Embedding API 返回空向量".to_string());
+        let cached = embeddings.iter()
+            .find(|(service, _)| Arc::ptr_eq(service, &embedding_service))
+            .map(|(_, embedding)| embedding.clone());
+        let question_embedding = match cached {
+            Some(embedding) => embedding,
+            None => {
+                let embedding = embedding_service.embed(&search_query)
+                    .await
+                    .map_err(|e| format!("问题向量化失败: {}", e))?;
+                embeddings.push((embedding_service, embedding.clone()));
+                embedding
             }
+        };
+        
+        let hits = if hybrid_search {
+            hybrid_retrieve(state, knowledge_base_id, &search_query, &question_embedding, candidate_k, retrieve_k).await
         } else {
-            return Err("Embedding API 返回空向量".to_string());
-        }
+            state.vector_store(knowledge_base_id)
+                .search(&question_embedding, retrieve_k)
+        };
+        rankings.push(hits);
+    }
+    
+    // 2. 不同知识库的相似度可能来自不同的模型，无法直接比较，按倒数排名融合
+    let candidates = if rankings.len() <= 1 {
+        rankings.pop().unwrap_or_default()
+    } else {
+        merge_rankings(rankings, retrieve_k)
     };
     
-    // 2. 检索相关文档
-    let top_k = {
-        let config = state.rag_config.lock().unwrap();
-        config.top_k
-    }; // config 的 MutexGuard 在这里释放
+    // 3. 重排序并保留前 top_k
+    let ranked = match rerank_service {
+        Some(service) => rerank_candidates(&service, &search_query, candidates, top_k).await,
+        None => candidates.into_iter().map(|r| (r, None)).collect(),
+    };
     
-    let search_results = state.vector_store.lock().unwrap()
-        .search(&question_embedding, top_k);
+    // 4. 记录来源文档块及得分，并构建编号的参考片段
+    let mut sources: Vec<MessageSource> = ranked.iter()
+        .enumerate()
+        .map(|(i, (r, rerank_score))| MessageSource::from_result(r, i + 1, *rerank_score))
+        .collect();
     
-    // 3. 构建上下文
-    let context: Vec<String> = search_results.iter()
-        .map(|r| r.document.content.clone())
+    let mut passages: Vec<ContextPassage> = ranked.iter()
+        .zip(&sources)
+        .map(|((r, _), source)| context_passage(r, source))
         .collect();
     
-    let context_text = context.join("\n\n");
+    // 5. 参考片段总长度不超过上下文上限，超出的片段不提供给模型，也不作为来源
+    let total = passages.len();
+    let kept = citation::fit_context(&mut passages, max_context_tokens);
+    if kept < total {
+        println!("✂️ 参考片段超出上下文上限（{} tokens），保留 {}/{} 个", max_context_tokens, kept, total);
+    }
+    sources.truncate(kept);
     
-    // 4. 获取来源文档名称
-    let sources: Vec<String> = search_results.iter()
-        .filter_map(|r| {
-            r.document.metadata.get("document_name")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        })
+    Ok(RetrievedContext {
+        passages,
+        sources,
+    })
+}
+
+/// 校验回答中的引用标记（移除超出范围的编号），并标记被引用的来源
+fn apply_citations(answer: &str, sources: &mut [MessageSource]) -> String {
+    let citations = citation::extract_citations(answer, sources.len());
+    
+    if !citations.invalid.is_empty() {
+        println!("⚠️ 回答中包含超出范围的引用编号，按原文保留: {:?}", citations.invalid);
+    }
+    for n in &citations.cited {
+        sources[n - 1].cited = true;
+    }
+    
+    citations.answer
+}
+
+/// 用重排序模型为候选打分，返回前 top_k 条及其得分；失败时退回检索顺序
+async fn rerank_candidates(
+    service: &RerankService,
+    query: &str,
+    candidates: Vec<SearchResult>,
+    top_k: usize,
+) -> Vec<(SearchResult, Option<f32>)> {
+    let documents: Vec<String> = candidates.iter()
+        .map(|r| r.document.content.clone())
         .collect();
     
-    // 5. 调用 LLM 生成答案
-    let answer = {
-        let service_arc = state.llm_service.clone();
-        let question_clone = request.question.clone();
-        let context_clone = context_text.clone();
-        
-        // 在独立的代码块中获取服务和调用方法
-        let (api_key, model) = {
-            let guard = service_arc.lock().unwrap();
-            let service = guard.as_ref().ok_or("LLM 服务未初始化")?;
-            (service.api_key().clone(), service.model().clone())
-        }; // guard 在这里释放
-        
-        // 调用 API
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
-            .build()
-            .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
-        
-        let messages = vec![
-            serde_json::json!({
-                "role": "system",
-                "content": "你是一个专业的知识库助手。请基于提供的文档内容回答用户问题。如果文档中没有相关信息，请诚实告知。"
-            }),
-            serde_json::json!({
-                "role": "user",
-                "content": format!("参考文档：\n\n{}\n\n问题：{}", context_clone, question_clone)
-            })
-        ];
-        
-        let request_body = serde_json::json!({
-            "model": model,
-            "input": {
-                "messages": messages
-            },
-            "parameters": {
-                "temperature": 0.7,
-                "top_p": 0.9
-            }
-        });
-        
-        let response = client
-            .post("https://dashscope.aliyuncs.com/api/v1/services/aigc/text-generation/generation")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| format!("LLM API网络请求失败: {}. 请检查网络连接", e))?;
-        
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.map_err(|e| e.to_string())?;
-            return Err(format!("LLM API 请求失败: {} - {}", status, text));
+    match service.rerank(query, &documents, top_k).await {
+        Ok(results) => {
+            let mut candidates: Vec<Option<SearchResult>> = candidates.into_iter().map(Some).collect();
+            results.into_iter()
+                .filter_map(|r| candidates[r.index].take().map(|c| (c, Some(r.score))))
+                .collect()
         }
-        
-        let result: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
-        
-        // 处理不同的响应格式
-        if let Some(text) = result["output"]["text"].as_str() {
-            text.to_string()
-        } else if let Some(choices) = result["output"]["choices"].as_array() {
-            if let Some(first) = choices.first() {
-                if let Some(message) = first["message"].as_object() {
-                    if let Some(content) = message["content"].as_str() {
-                        content.to_string()
-                    } else {
-                        return Err("LLM API 返回空内容".to_string());
-                    }
-                } else {
-                    return Err("LLM API 返回空内容".to_string());
-                }
-            } else {
-                return Err("LLM API 返回空内容".to_string());
-            }
-        } else {
-            return Err("LLM API 返回空内容".to_string());
+        Err(e) => {
+            println!("⚠️ 重排序失败（{}），使用检索顺序: {}", service.provider().name(), e);
+            candidates.into_iter()
+                .take(top_k)
+                .map(|r| (r, None))
+                .collect()
         }
-    };
+    }
+}
+
+/// 按倒数排名融合多个知识库的检索结果，取前 limit 条
+fn merge_rankings(rankings: Vec<Vec<SearchResult>>, limit: usize) -> Vec<SearchResult> {
+    let ids: Vec<Vec<String>> = rankings.iter()
+        .map(|hits| hits.iter().map(|r| r.document.id.clone()).collect())
+        .collect();
+    let fused = reciprocal_rank_fusion(&ids, DEFAULT_RRF_K);
     
-    // 6. 保存对话历史
-    let conversation_id = request.conversation_id
-        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let mut by_id: HashMap<String, SearchResult> = rankings.into_iter()
+        .flatten()
+        .map(|r| (r.document.id.clone(), r))
+        .collect();
+    fused.iter()
+        .filter_map(|(id, _)| by_id.remove(id))
+        .take(limit)
+        .collect()
+}
+
+/// 混合检索：在知识库中向量检索与关键词检索各取 candidate_k 条候选，按倒数排名融合后取前 limit 条
+async fn hybrid_retrieve(
+    state: &AppState,
+    knowledge_base_id: &str,
+    search_query: &str,
+    question_embedding: &[f32],
+    candidate_k: usize,
+    limit: usize,
+) -> Vec<SearchResult> {
+    // 关键词检索失败（例如索引损坏）时退化为纯向量检索
+    let keyword_ids = keyword_search(state, knowledge_base_id, search_query, candidate_k)
+        .await
+        .unwrap_or_else(|e| {
+            println!("⚠️ 关键词检索失败，仅使用向量检索: {}", e);
+            Vec::new()
+        });
     
+    let store = state.vector_store(knowledge_base_id);
+    let vector_hits = store.search(question_embedding, candidate_k);
+    
+    let vector_ids: Vec<String> = vector_hits.iter()
+        .map(|r| r.document.id.clone())
+        .collect();
+    let fused = reciprocal_rank_fusion(&[vector_ids, keyword_ids], DEFAULT_RRF_K);
+    
+    // 向量命中的块直接复用，只被关键词命中的块从向量存储中取出并补算相似度
+    let mut by_id: HashMap<String, SearchResult> = vector_hits.into_iter()
+        .map(|r| (r.document.id.clone(), r))
+        .collect();
+    let keyword_only: Vec<String> = fused.iter()
+        .map(|(id, _)| id)
+        .filter(|id| !by_id.contains_key(*id))
+        .cloned()
+        .collect();
+    by_id.extend(
+        store.score_documents(&keyword_only, question_embedding)
+            .into_iter()
+            .map(|r| (r.document.id.clone(), r)),
+    );
+    
+    fused.iter()
+        .filter_map(|(id, _)| by_id.remove(id))
+        .take(limit)
+        .collect()
+}
+
+/// 在知识库中关键词检索（FTS5 + bm25），返回按相关度排序的文档块 ID
+async fn keyword_search(
+    state: &AppState,
+    knowledge_base_id: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let Some(match_expr) = keyword::match_query(query) else {
+        return Ok(Vec::new());
+    };
+    
+    sqlx::query_scalar::<_, String>(
+        "SELECT c.id FROM chunks_fts
+         JOIN chunk_fts_map m ON m.fts_rowid = chunks_fts.rowid
+         JOIN chunks c ON c.id = m.chunk_id
+         JOIN documents d ON d.id = c.document_id
+         WHERE chunks_fts MATCH ? AND d.knowledge_base_id = ?
+         ORDER BY bm25(chunks_fts)
+         LIMIT ?"
+    )
+    .bind(match_expr)
+    .bind(knowledge_base_id)
+    .bind(limit as i64)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 保存一轮问答（不存在的对话会自动创建）
+async fn save_exchange(
+    state: &AppState,
+    conversation_id: &str,
+    question: &str,
+    ai_msg_id: &str,
+    answer: &str,
+    sources: &[MessageSource],
+) -> Result<(), String> {
     let timestamp = chrono::Utc::now().timestamp();
     
     // 确保对话存在
     let conv_exists = sqlx::query_scalar::<_, i64>(
         "SELECT COUNT(*) FROM conversations WHERE id = ?"
     )
-    .bind(&conversation_id)
+    .bind(conversation_id)
     .fetch_one(state.db.pool())
     .await
     .map_err(|e| e.to_string())?;
     
     if conv_exists == 0 {
         // 创建新对话
-        let title = if request.question.chars().count() > 20 {
-            format!("{}...", request.question.chars().take(20).collect::<String>())
+        let title = if question.chars().count() > 20 {
+            format!("{}...", question.chars().take(20).collect::<String>())
         } else {
-            request.question.clone()
+            question.to_string()
         };
         
         sqlx::query(
             "INSERT INTO conversations (id, title, created_at, updated_at) VALUES (?, ?, ?, ?)"
         )
-        .bind(&conversation_id)
+        .bind(conversation_id)
         .bind(&title)
         .bind(timestamp)
         .bind(timestamp)
@@ -235,9 +700,9 @@ pub async fn ask_question(
          VALUES (?, ?, ?, ?, ?, ?)"
     )
     .bind(&user_msg_id)
-    .bind(&conversation_id)
+    .bind(conversation_id)
     .bind("user")
-    .bind(&request.question)
+    .bind(question)
     .bind::<Option<String>>(None)
     .bind(timestamp)
     .execute(state.db.pool())
@@ -245,29 +710,23 @@ pub async fn ask_question(
     .map_err(|e| e.to_string())?;
     
     // 保存 AI 回复
-    let ai_msg_id = Uuid::new_v4().to_string();
-    let sources_json = serde_json::to_string(&sources).ok();
+    let sources_json = serde_json::to_string(sources).ok();
     
     sqlx::query(
         "INSERT INTO messages (id, conversation_id, role, content, sources, created_at) 
          VALUES (?, ?, ?, ?, ?, ?)"
     )
-    .bind(&ai_msg_id)
-    .bind(&conversation_id)
+    .bind(ai_msg_id)
+    .bind(conversation_id)
     .bind("assistant")
-    .bind(&answer)
+    .bind(answer)
     .bind(&sources_json)
     .bind(timestamp)
     .execute(state.db.pool())
     .await
     .map_err(|e| e.to_string())?;
     
-    Ok(AskQuestionResponse {
-        success: true,
-        answer,
-        sources,
-        conversation_id,
-    })
+    Ok(())
 }
 
 /// 获取对话历史
@@ -302,6 +761,42 @@ pub async fn get_messages(
     Ok(messages)
 }
 
+/// 获取消息引用的文档块内容
+#[tauri::command]
+pub async fn get_message_sources(
+    message_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<CitedChunk>, String> {
+    let sources_json = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT sources FROM messages WHERE id = ?"
+    )
+    .bind(&message_id)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "消息不存在".to_string())?;
+    
+    // 早期版本只保存了文档名称数组，无法定位到具体文档块
+    let sources: Vec<MessageSource> = sources_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    
+    let mut cited = Vec::with_capacity(sources.len());
+    for source in sources {
+        let content = sqlx::query_scalar::<_, String>(
+            "SELECT content FROM chunks WHERE id = ?"
+        )
+        .bind(&source.chunk_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+        
+        cited.push(CitedChunk { source, content });
+    }
+    
+    Ok(cited)
+}
+
 /// 删除对话
 #[tauri::command]
 pub async fn delete_conversation(
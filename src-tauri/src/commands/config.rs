@@ -1,4 +1,6 @@
 use crate::app_state::AppState;
+use crate::rag::knowledge_base::KnowledgeBaseSettings;
+use crate::rag::RAGConfig;
 use tauri::State;
 
 #[derive(serde::Serialize)]
@@ -21,7 +23,12 @@ pub async fn set_api_key(
     }
     
     // 初始化 RAG 服务
-    state.init_rag_services(api_key.clone());
+    if let Err(e) = state.init_rag_services(api_key.clone()) {
+        return Ok(ConfigResponse {
+            success: false,
+            message: format!("RAG 服务初始化失败: {}", e),
+        });
+    }
     
     // 保存到数据库
     let timestamp = chrono::Utc::now().timestamp();
@@ -57,3 +64,98 @@ pub async fn get_api_key_status(
     Ok(result.is_some())
 }
 
+
+/// 获取 RAG 配置
+#[tauri::command]
+pub async fn get_rag_config(
+    state: State<'_, AppState>,
+) -> Result<RAGConfig, String> {
+    Ok(state.rag_config.lock().unwrap().clone())
+}
+
+/// 更新 RAG 配置（切换 Embedding / LLM 提供方等），并重新初始化 RAG 服务
+#[tauri::command]
+pub async fn set_rag_config(
+    config: RAGConfig,
+    state: State<'_, AppState>,
+) -> Result<ConfigResponse, String> {
+    let config_json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    
+    // 已有文档的向量由原来的 Embedding 服务生成，此时不能修改其使用的全局 Embedding 设置
+    let current = state.rag_config.lock().unwrap().clone();
+    if let Some(name) = knowledge_base_blocking_embedding_change(&state, &current, &config).await? {
+        return Ok(ConfigResponse {
+            success: false,
+            message: format!("知识库「{}」中已有文档，不能修改 Embedding 设置", name),
+        });
+    }
+    
+    let previous = std::mem::replace(&mut *state.rag_config.lock().unwrap(), config);
+    
+    // 使用已保存的 API Key 重新初始化服务，失败时恢复原配置
+    let api_key = sqlx::query_scalar::<_, String>(
+        "SELECT value FROM settings WHERE key = ?"
+    )
+    .bind("qwen_api_key")
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    
+    let requires_api_key = state.rag_config.lock().unwrap().requires_api_key();
+    let api_key = api_key.or_else(|| (!requires_api_key).then(String::new));
+    
+    if let Some(api_key) = api_key {
+        if let Err(e) = state.init_rag_services(api_key) {
+            *state.rag_config.lock().unwrap() = previous;
+            return Ok(ConfigResponse {
+                success: false,
+                message: format!("RAG 服务初始化失败: {}", e),
+            });
+        }
+    }
+    
+    let timestamp = chrono::Utc::now().timestamp();
+    sqlx::query(
+        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?, ?, ?)"
+    )
+    .bind("rag_config")
+    .bind(&config_json)
+    .bind(timestamp)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    
+    Ok(ConfigResponse {
+        success: true,
+        message: "RAG 配置已保存".to_string(),
+    })
+}
+
+/// 查找使用全局 Embedding 设置、已有文档且 Embedding 服务会因新配置而改变的知识库，返回其名称
+async fn knowledge_base_blocking_embedding_change(
+    state: &AppState,
+    current: &RAGConfig,
+    config: &RAGConfig,
+) -> Result<Option<String>, String> {
+    if current.embedding_signature() == config.embedding_signature() {
+        return Ok(None);
+    }
+    
+    let knowledge_bases = sqlx::query_as::<_, (String, String)>(
+        "SELECT k.name, k.settings FROM knowledge_bases k
+         WHERE EXISTS (SELECT 1 FROM documents d WHERE d.knowledge_base_id = k.id)
+         ORDER BY k.created_at"
+    )
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    
+    for (name, settings) in knowledge_bases {
+        let settings: KnowledgeBaseSettings = serde_json::from_str(&settings).unwrap_or_default();
+        if settings.apply(current).embedding_signature() != settings.apply(config).embedding_signature() {
+            return Ok(Some(name));
+        }
+    }
+    
+    Ok(None)
+}
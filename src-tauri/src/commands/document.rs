@@ -1,28 +1,59 @@
-use crate::app_state::AppState;
+use crate::app_state::{document_vectors, AppState};
 use crate::db::models::Document;
-use crate::rag::text_splitter::TextSplitter;
-use crate::rag::vector_store::VectorDocument;
-use crate::commands::file::read_file_content;
+use crate::rag::code_splitter::{language_by_name, language_for_extension, CodeSplitter};
+use crate::rag::markdown_splitter::MarkdownSplitter;
+use crate::rag::semantic_splitter::SemanticSplitter;
+use crate::rag::embedding::EmbeddingService;
+use crate::rag::knowledge_base::DEFAULT_KNOWLEDGE_BASE_ID;
+use crate::rag::{ChunkStrategy, RAGConfig};
+use crate::rag::text_splitter::{assign_pages, Section, TextChunk, TextSplitter};
+use crate::commands::file::extract_document;
+use crate::hash::{content_hash, file_hash};
+use crate::commands::ingestion::{self, JobSource, EMBEDDING_BATCH_SIZE};
+use crate::rag::vector_store::{encode_embedding, VectorStore};
 use tauri::State;
-use uuid::Uuid;
-use reqwest::Client;
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(serde::Deserialize)]
 pub struct UploadDocumentRequest {
-    name: String,
-    content: String,
-    file_type: Option<String>,
+    pub(crate) name: String,
+    pub(crate) content: String,
+    pub(crate) file_type: Option<String>,
+    /// 文档级元数据（网页标题、canonical 链接等）
+    #[serde(default)]
+    pub(crate) metadata: Option<serde_json::Value>,
+    /// 本文档使用的分块策略，为空时使用 RAG 配置中的默认策略
+    #[serde(default)]
+    pub(crate) chunk_strategy: Option<ChunkStrategy>,
+    /// 导入到的知识库，为空时导入默认知识库
+    #[serde(default)]
+    pub(crate) knowledge_base_id: Option<String>,
+    /// 章节划分（仅在后端提取文件时提供）
+    #[serde(skip)]
+    pub(crate) sections: Vec<Section>,
+    /// 每页的起始字符偏移（仅在后端提取 PDF 时提供）
+    #[serde(skip)]
+    pub(crate) page_starts: Vec<usize>,
 }
 
+/// 按 Markdown 结构分块的文件类型（提取器输出的 Office 文档和网页同样是 Markdown 结构）
+const MARKDOWN_EXTENSIONS: &[&str] = &[
+    "md", "markdown", "mdx", "docx", "pptx", "xlsx", "html", "htm", "xhtml", "mhtml", "mht",
+];
+
 #[derive(serde::Serialize)]
 pub struct UploadDocumentResponse {
     success: bool,
     message: String,
     document_id: Option<String>,
+    /// 后台导入任务 ID
+    job_id: Option<String>,
 }
 
 /// 上传文档
+///
+/// 文档加入后台导入队列后立即返回，处理进度通过 `ingestion://progress` 事件推送。
 #[tauri::command]
 pub async fn upload_document(
     request: UploadDocumentRequest,
@@ -34,201 +65,112 @@ pub async fn upload_document(
             success: false,
             message: "请先配置 API Key".to_string(),
             document_id: None,
+            job_id: None,
         });
     }
     
-    let document_id = Uuid::new_v4().to_string();
-    let timestamp = chrono::Utc::now().timestamp();
-    
-    // 1. 保存文档到数据库
-    let file_size = request.content.len() as i64;
+    let knowledge_base_id = request.knowledge_base_id.as_deref().unwrap_or(DEFAULT_KNOWLEDGE_BASE_ID);
     
-    sqlx::query(
-        "INSERT INTO documents (id, name, content, file_type, file_size, created_at, updated_at) 
-         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    // 内容与知识库中已有文档相同时不再重复导入
+    let duplicate = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, name FROM documents WHERE knowledge_base_id = ? AND content_hash = ? LIMIT 1"
     )
-    .bind(&document_id)
-    .bind(&request.name)
-    .bind(&request.content)
-    .bind(&request.file_type)
-    .bind(file_size)
-    .bind(timestamp)
-    .bind(timestamp)
-    .execute(state.db.pool())
+    .bind(knowledge_base_id)
+    .bind(content_hash(&request.content))
+    .fetch_optional(state.db.pool())
     .await
     .map_err(|e| e.to_string())?;
     
-    // 2. 文本分块
-    let chunks = {
-        let config = state.rag_config.lock().unwrap();
-        let splitter = TextSplitter::new(config.chunk_size, config.chunk_overlap);
-        splitter.split_smart(&request.content)
-    }; // config 的 MutexGuard 在这里自动释放
-    
-    let start_time = std::time::Instant::now();
-    println!("📦 文档分块完成: {} 个块", chunks.len());
-    
-    // 3. 批量生成向量（每批 25 个 - 通义千问 API 限制）
-    const BATCH_SIZE: usize = 25;
-    let total_chunks = chunks.len();
-    let mut all_embeddings = Vec::new();
-    
-    // 获取 API 配置
-    let (api_key, model) = {
-        let guard = state.embedding_service.lock().unwrap();
-        let service = guard.as_ref().ok_or("Embedding服务未初始化")?;
-        (service.api_key().clone(), service.model().clone())
-    };
-    
-    // 创建 HTTP 客户端
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-        .map_err(|e| format!("创建HTTP客户端失败: {}", e))?;
-    
-    // 并发处理多个批次（最多 5 个并发）
-    let mut batch_tasks = Vec::new();
-    
-    for (batch_idx, chunk_batch) in chunks.chunks(BATCH_SIZE).enumerate() {
-        let client_clone = client.clone();
-        let api_key_clone = api_key.clone();
-        let model_clone = model.clone();
-        let batch_texts: Vec<String> = chunk_batch.iter().map(|s| s.to_string()).collect();
-        let batch_num = batch_idx + 1;
-        let total_batches = (total_chunks + BATCH_SIZE - 1) / BATCH_SIZE;
-        
-        let task = tokio::spawn(async move {
-            println!("🚀 正在处理批次 {}/{} ({} 个块)...", batch_num, total_batches, batch_texts.len());
-            
-            let request_body = serde_json::json!({
-                "model": model_clone,
-                "input": {
-                    "texts": batch_texts
-                }
-            });
-            
-            let response = client_clone
-                .post("https://dashscope.aliyuncs.com/api/v1/services/embeddings/text-embedding/text-embedding")
-                .header("Authorization", format!("Bearer {}", api_key_clone))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-                .await
-                .map_err(|e| format!("批次 {} Embedding API网络请求失败: {}", batch_num, e))?;
-            
-            if !response.status().is_success() {
-                let status = response.status();
-                let text = response.text().await.map_err(|e| e.to_string())?;
-                return Err(format!("批次 {} Embedding API 请求失败: {} - {}", batch_num, status, text));
-            }
-            
-            let result: serde_json::Value = response.json().await
-                .map_err(|e| format!("批次 {} 解析响应失败: {}", batch_num, e))?;
-            
-            let embeddings_array = result["output"]["embeddings"].as_array()
-                .ok_or_else(|| format!("批次 {} API 返回格式错误", batch_num))?;
-            
-            let mut batch_embeddings = Vec::new();
-            for emb in embeddings_array {
-                let embedding = emb["embedding"].as_array()
-                    .ok_or_else(|| format!("批次 {} 向量数据格式错误", batch_num))?
-                    .iter()
-                    .filter_map(|v| v.as_f64().map(|f| f as f32))
-                    .collect::<Vec<f32>>();
-                
-                if embedding.is_empty() {
-                    return Err(format!("批次 {} 向量为空", batch_num));
-                }
-                
-                batch_embeddings.push(embedding);
-            }
-            
-            println!("✅ 批次 {}/{} 完成", batch_num, total_batches);
-            Ok::<Vec<Vec<f32>>, String>(batch_embeddings)
+    if let Some((document_id, name)) = duplicate {
+        return Ok(UploadDocumentResponse {
+            success: false,
+            message: format!("内容与已有文档「{}」相同", name),
+            document_id: Some(document_id),
+            job_id: None,
         });
-        
-        batch_tasks.push(task);
-        
-        // 控制并发数：每 10 个任务等待一批完成
-        if batch_tasks.len() >= 10 {
-            let completed = batch_tasks.remove(0);
-            let embeddings = completed.await
-                .map_err(|e| format!("任务执行失败: {}", e))?
-                .map_err(|e| e)?;
-            all_embeddings.extend(embeddings);
-        }
-    }
-    
-    // 等待剩余任务完成
-    for task in batch_tasks {
-        let embeddings = task.await
-            .map_err(|e| format!("任务执行失败: {}", e))?
-            .map_err(|e| e)?;
-        all_embeddings.extend(embeddings);
-    }
-    
-    let embedding_time = start_time.elapsed();
-    println!("🎉 所有向量生成完成: {} 个 (耗时: {:.2}秒)", all_embeddings.len(), embedding_time.as_secs_f64());
-    
-    // 4. 批量保存到数据库（单个事务）
-    println!("💾 开始批量保存到数据库...");
-    let mut tx = state.db.pool().begin().await
-        .map_err(|e| format!("开始事务失败: {}", e))?;
-    
-    for (index, (chunk_content, embedding)) in chunks.iter().zip(all_embeddings.iter()).enumerate() {
-        let chunk_id = Uuid::new_v4().to_string();
-        
-        // 保存 chunk 到数据库
-        sqlx::query(
-            "INSERT INTO chunks (id, document_id, content, chunk_index, created_at) 
-             VALUES (?, ?, ?, ?, ?)"
-        )
-        .bind(&chunk_id)
-        .bind(&document_id)
-        .bind(chunk_content)
-        .bind(index as i64)
-        .bind(timestamp)
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("插入块 {} 失败: {}", index, e))?;
-        
-        // 添加到向量存储
-        let vector_doc = VectorDocument {
-            id: chunk_id.clone(),
-            content: chunk_content.clone(),
-            embedding: embedding.clone(),
-            metadata: serde_json::json!({
-                "document_id": document_id,
-                "chunk_index": index,
-                "document_name": request.name,
-            }),
-        };
-        
-        state.vector_store.lock().unwrap().add_document(vector_doc);
     }
     
-    // 提交事务
-    tx.commit().await
-        .map_err(|e| format!("提交事务失败: {}", e))?;
-    
-    let total_time = start_time.elapsed();
-    println!("✅ 数据库保存完成 (总耗时: {:.2}秒)", total_time.as_secs_f64());
+    let source = JobSource::Text {
+        content: request.content,
+        file_type: request.file_type,
+        metadata: request.metadata,
+    };
+    let job = ingestion::enqueue_job(&state, knowledge_base_id, &request.name, &source, request.chunk_strategy).await?;
     
     Ok(UploadDocumentResponse {
         success: true,
-        message: format!("文档上传成功，共分为 {} 个块", chunks.len()),
-        document_id: Some(document_id),
+        message: "文档已加入导入队列".to_string(),
+        document_id: Some(job.document_id),
+        job_id: Some(job.id),
     })
 }
 
-/// 获取所有文档列表
+/// 文本分块：源代码按声明边界，Markdown 按标题结构，其余文本按分块策略
+///
+/// 识别出的编程语言会写入文档元数据。`config` 为文档所属知识库生效的配置。
+pub(crate) async fn split_document(
+    config: &RAGConfig,
+    embedding_service: &EmbeddingService,
+    request: &mut UploadDocumentRequest,
+) -> Result<Vec<TextChunk>, String> {
+    // 语言由元数据或文件扩展名判断
+    let extension = Path::new(&request.name).extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    let language = request.metadata.as_ref()
+        .and_then(|m| m.get("language"))
+        .and_then(|l| l.as_str())
+        .and_then(language_by_name)
+        .or_else(|| language_for_extension(&extension));
+    let is_markdown = MARKDOWN_EXTENSIONS.contains(&extension.as_str());
+    if let Some(language) = language {
+        let metadata = request.metadata.get_or_insert_with(|| serde_json::json!({}));
+        if let Some(metadata) = metadata.as_object_mut() {
+            metadata.insert("language".to_string(), language.name.into());
+        }
+    }
+    
+    // 普通文本按文档指定的策略，未指定时使用配置中的默认策略
+    let strategy = request.chunk_strategy.unwrap_or(config.chunk_strategy);
+    let splitter = TextSplitter::new(config.chunk_size, config.chunk_overlap).with_unit(config.chunk_unit);
+    let mut chunks = if let Some(language) = language {
+        CodeSplitter::new(config.chunk_size, language)
+            .with_unit(config.chunk_unit)
+            .split(&request.content)
+    } else if !request.sections.is_empty() {
+        splitter.split_sections(&request.content, &request.sections)
+    } else if is_markdown {
+        MarkdownSplitter::new(config.chunk_size)
+            .with_unit(config.chunk_unit)
+            .split(&request.content)
+    } else {
+        match strategy {
+            ChunkStrategy::Fixed => splitter.split_smart_chunks(&request.content),
+            ChunkStrategy::Recursive => splitter.split_recursive(&request.content),
+            ChunkStrategy::Semantic => SemanticSplitter::new(config.chunk_size, config.semantic_breakpoint_percentile)
+                .with_unit(config.chunk_unit)
+                .split(&request.content, embedding_service)
+                .await
+                .map_err(|e| format!("语义分块失败: {}", e))?,
+        }
+    };
+    assign_pages(&mut chunks, &request.page_starts);
+    
+    println!("📦 文档分块完成: {} 个块", chunks.len());
+    Ok(chunks)
+}
+
+/// 获取文档列表，指定知识库时只返回该知识库的文档
 #[tauri::command]
 pub async fn get_documents(
+    knowledge_base_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<Vec<Document>, String> {
     let documents = sqlx::query_as::<_, Document>(
-        "SELECT * FROM documents ORDER BY created_at DESC"
+        "SELECT * FROM documents WHERE ?1 IS NULL OR knowledge_base_id = ?1 ORDER BY created_at DESC"
     )
+    .bind(knowledge_base_id)
     .fetch_all(state.db.pool())
     .await
     .map_err(|e| e.to_string())?;
@@ -242,23 +184,197 @@ pub async fn delete_document(
     document_id: String,
     state: State<'_, AppState>,
 ) -> Result<bool, String> {
+    if let Some(knowledge_base_id) = remove_document(&state, &document_id).await? {
+        state.persist_vector_index(&knowledge_base_id);
+    }
+    
+    Ok(true)
+}
+
+/// 从数据库和向量存储中删除文档，返回文档所属的知识库（文档不存在时为 None）
+pub(crate) async fn remove_document(state: &AppState, document_id: &str) -> Result<Option<String>, String> {
+    let knowledge_base_id = sqlx::query_scalar::<_, String>("SELECT knowledge_base_id FROM documents WHERE id = ?")
+        .bind(document_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    
     // 从数据库删除（会级联删除chunks）
     sqlx::query("DELETE FROM documents WHERE id = ?")
-        .bind(&document_id)
+        .bind(document_id)
+        .execute(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    // 删除对应的导入任务（正在执行的任务会因此停止）
+    sqlx::query("DELETE FROM ingestion_jobs WHERE document_id = ?")
+        .bind(document_id)
         .execute(state.db.pool())
         .await
         .map_err(|e| e.to_string())?;
     
     // 从向量存储删除
-    state.vector_store.lock().unwrap().remove_by_document_id(&document_id);
+    if let Some(knowledge_base_id) = &knowledge_base_id {
+        state.vector_store(knowledge_base_id).remove_by_document_id(document_id);
+    }
     
-    Ok(true)
+    Ok(knowledge_base_id)
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct RepairReport {
+    /// 检查的文档数
+    checked: usize,
+    /// 没有任何文档块、已删除的文档名称
+    removed: Vec<String>,
+    /// 补全向量的文档块数
+    reembedded_chunks: usize,
+    /// 重新载入向量存储的文档数
+    reloaded: usize,
+    /// 从向量存储移除的、数据库中已不存在的文档数
+    orphaned: usize,
 }
 
-/// 从文件路径上传文档（支持 PDF 等格式）
+/// 修复知识库
+///
+/// 检查每个文档的块数、已生成向量的块数和向量存储中的块数：没有任何块的文档直接删除，
+/// 缺少向量的块重新生成向量，向量存储与数据库不一致的文档重新载入，最后补建关键词索引。
+#[tauri::command]
+pub async fn repair_documents(
+    state: State<'_, AppState>,
+) -> Result<RepairReport, String> {
+    let documents = sqlx::query_as::<_, (String, String, String, i64, i64)>(
+        "SELECT d.id, d.knowledge_base_id, d.name, COUNT(c.id), COUNT(c.embedding)
+         FROM documents d LEFT JOIN chunks c ON c.document_id = d.id
+         GROUP BY d.id"
+    )
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    
+    let mut report = RepairReport {
+        checked: documents.len(),
+        ..Default::default()
+    };
+    
+    for (document_id, knowledge_base_id, name, chunk_count, embedded_count) in documents {
+        if chunk_count == 0 {
+            println!("🧹 删除没有文档块的文档: {}", name);
+            remove_document(&state, &document_id).await?;
+            report.removed.push(name);
+            continue;
+        }
+        
+        let reembedded = if embedded_count < chunk_count {
+            embed_missing_chunks(&state, &knowledge_base_id, &document_id).await?
+        } else {
+            0
+        };
+        report.reembedded_chunks += reembedded;
+        
+        let vector_store = state.vector_store(&knowledge_base_id);
+        let stored = vector_store.document_len(&document_id);
+        if reembedded > 0 || stored as i64 != chunk_count {
+            let mut conn = state.db.pool().acquire().await
+                .map_err(|e| e.to_string())?;
+            let vector_docs = document_vectors(&mut conn, &document_id).await
+                .map_err(|e| e.to_string())?;
+            
+            vector_store.remove_by_document_id(&document_id);
+            vector_store.add_documents(vector_docs);
+            report.reloaded += 1;
+        }
+    }
+    
+    // 向量存储中残留的已删除文档（或不属于该知识库的文档）
+    let vector_stores: Vec<(String, Arc<VectorStore>)> = state.vector_stores.lock().unwrap()
+        .iter()
+        .map(|(id, store)| (id.clone(), store.clone()))
+        .collect();
+    for (knowledge_base_id, vector_store) in vector_stores {
+        for document_id in vector_store.document_ids() {
+            let exists = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM documents WHERE id = ? AND knowledge_base_id = ?"
+            )
+            .bind(&document_id)
+            .bind(&knowledge_base_id)
+            .fetch_one(state.db.pool())
+            .await
+            .map_err(|e| e.to_string())? > 0;
+            
+            if !exists {
+                vector_store.remove_by_document_id(&document_id);
+                report.orphaned += 1;
+            }
+        }
+    }
+    
+    state.sync_keyword_index().await
+        .map_err(|e| e.to_string())?;
+    if !report.removed.is_empty() || report.reloaded > 0 || report.orphaned > 0 {
+        state.persist_all_vector_indexes();
+    }
+    
+    println!(
+        "🔧 知识库修复完成: 检查 {} 个文档，删除 {}，补全向量 {} 个块，重新载入 {}，清理残留 {}",
+        report.checked,
+        report.removed.len(),
+        report.reembedded_chunks,
+        report.reloaded,
+        report.orphaned
+    );
+    Ok(report)
+}
+
+/// 为文档中缺少向量的块分批生成向量，返回补全的块数
+async fn embed_missing_chunks(state: &AppState, knowledge_base_id: &str, document_id: &str) -> Result<usize, String> {
+    let (_, embedding_service) = state.knowledge_base_services(knowledge_base_id).await?;
+    let mut embedded = 0;
+    
+    loop {
+        let batch = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, content FROM chunks WHERE document_id = ? AND embedding IS NULL ORDER BY chunk_index LIMIT ?"
+        )
+        .bind(document_id)
+        .bind(EMBEDDING_BATCH_SIZE)
+        .fetch_all(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+        
+        if batch.is_empty() {
+            return Ok(embedded);
+        }
+        
+        let texts: Vec<String> = batch.iter().map(|(_, content)| content.clone()).collect();
+        let embeddings = embedding_service.embed_batch(&texts).await
+            .map_err(|e| format!("向量生成失败: {}", e))?;
+        if embeddings.len() != batch.len() {
+            return Err(format!("向量数量不匹配: {} 个块，{} 个向量", batch.len(), embeddings.len()));
+        }
+        
+        let mut tx = state.db.pool().begin().await
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+        for ((chunk_id, _), embedding) in batch.iter().zip(&embeddings) {
+            sqlx::query("UPDATE chunks SET embedding = ? WHERE id = ?")
+                .bind(encode_embedding(embedding))
+                .bind(chunk_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("保存向量失败: {}", e))?;
+        }
+        tx.commit().await
+            .map_err(|e| format!("提交事务失败: {}", e))?;
+        
+        embedded += batch.len();
+    }
+}
+
+/// 从文件路径上传文档（支持 PDF 等格式），文件在后台导入任务中提取
 #[tauri::command]
 pub async fn upload_document_from_path(
     file_path: String,
+    chunk_strategy: Option<ChunkStrategy>,
+    knowledge_base_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<UploadDocumentResponse, String> {
     // 检查 RAG 服务是否已初始化
@@ -267,33 +383,95 @@ pub async fn upload_document_from_path(
             success: false,
             message: "请先配置 API Key".to_string(),
             document_id: None,
+            job_id: None,
         });
     }
     
-    // 获取文件名
-    let file_name = Path::new(&file_path)
+    let source = JobSource::File { path: file_path.clone() };
+    let knowledge_base_id = knowledge_base_id.as_deref().unwrap_or(DEFAULT_KNOWLEDGE_BASE_ID);
+    let job = ingestion::enqueue_job(&state, knowledge_base_id, &file_name(&file_path), &source, chunk_strategy).await?;
+    
+    Ok(UploadDocumentResponse {
+        success: true,
+        message: "文件已加入导入队列".to_string(),
+        document_id: Some(job.document_id),
+        job_id: Some(job.id),
+    })
+}
+
+/// 从原文件重新导入文档
+///
+/// 只为内容变化的块重新生成向量，未变化的块复用原有向量；文件内容未变化时不做任何修改。
+#[tauri::command]
+pub async fn reimport_document(
+    document_id: String,
+    state: State<'_, AppState>,
+) -> Result<UploadDocumentResponse, String> {
+    if !state.is_rag_initialized() {
+        return Ok(UploadDocumentResponse {
+            success: false,
+            message: "请先配置 API Key".to_string(),
+            document_id: None,
+            job_id: None,
+        });
+    }
+    
+    let (knowledge_base_id, source_path) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT knowledge_base_id, json_extract(metadata, '$.source_path') FROM documents WHERE id = ?"
+    )
+    .bind(&document_id)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "文档不存在".to_string())?;
+    let source_path = source_path
+        .ok_or_else(|| "该文档不是从文件导入的，无法重新导入".to_string())?;
+    
+    let source = JobSource::File { path: source_path.clone() };
+    let job = ingestion::enqueue_job(&state, &knowledge_base_id, &file_name(&source_path), &source, None).await?;
+    
+    Ok(UploadDocumentResponse {
+        success: true,
+        message: "文档已加入重新导入队列".to_string(),
+        document_id: Some(job.document_id),
+        job_id: Some(job.id),
+    })
+}
+
+/// 路径中的文件名
+pub(crate) fn file_name(file_path: &str) -> String {
+    Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("未知文件")
-        .to_string();
-    
+        .to_string()
+}
+
+/// 提取文件内容，并在元数据中记录文件路径和内容哈希
+pub(crate) async fn prepare_file(file_path: &str) -> Result<UploadDocumentRequest, String> {
     // 检测文件类型
-    let file_type = Path::new(&file_path)
+    let file_type = Path::new(file_path)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("unknown")
         .to_string();
     
-    // 读取文件内容（会自动处理 PDF）
-    let content = read_file_content(file_path).await?;
+    // 提取文件内容（自动处理 PDF、Office 文档、网页和电子书）
+    let extracted = extract_document(Path::new(file_path)).await?;
+    let hash = file_hash(Path::new(file_path)).await?;
     
-    // 调用原有的上传逻辑
-    let request = UploadDocumentRequest {
-        name: file_name,
-        content,
-        file_type: Some(file_type),
-    };
+    let mut metadata = extracted.metadata;
+    metadata.insert("source_path".to_string(), file_path.into());
+    metadata.insert("file_hash".to_string(), hash.into());
     
-    upload_document(request, state).await
+    Ok(UploadDocumentRequest {
+        name: file_name(file_path),
+        content: extracted.text,
+        file_type: Some(file_type),
+        metadata: Some(serde_json::Value::Object(metadata)),
+        chunk_strategy: None,
+        knowledge_base_id: None,
+        sections: extracted.sections,
+        page_starts: extracted.page_starts,
+    })
 }
-
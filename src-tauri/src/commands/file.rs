@@ -1,12 +1,29 @@
-use std::path::PathBuf;
+use crate::extract::{epub, html, ocr, office, pdf, ExtractedDocument};
+use crate::rag::code_splitter::language_for_extension;
+use std::path::{Path, PathBuf};
 
-/// 读取文件内容（支持 TXT、MD 等文本文件和 PDF）
+/// 可以导入的非代码文件扩展名（源代码按语言表判断）
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "mdx", "csv", "json",
+    "pdf", "docx", "pptx", "xlsx", "html", "htm", "xhtml", "mhtml", "mht", "epub",
+    "png", "jpg", "jpeg", "tif", "tiff", "bmp", "webp",
+];
+
+/// 扩展名（小写）是否为支持导入的文件类型
+pub(crate) fn is_supported_extension(extension: &str) -> bool {
+    SUPPORTED_EXTENSIONS.contains(&extension) || language_for_extension(extension).is_some()
+}
+
+/// 读取文件内容（支持 TXT、MD 等文本文件、PDF（含扫描版）、Word/PowerPoint/Excel、HTML/MHTML 网页、EPUB 电子书以及图片 OCR）
 #[tauri::command]
 pub async fn read_file_content(file_path: String) -> Result<String, String> {
+    Ok(extract_document(Path::new(&file_path)).await?.text)
+}
+
+/// 按扩展名提取文件正文和文档元数据
+pub(crate) async fn extract_document(path: &Path) -> Result<ExtractedDocument, String> {
     use tokio::fs;
     
-    let path = PathBuf::from(&file_path);
-    
     // 检查文件是否存在
     if !path.exists() {
         return Err("文件不存在".to_string());
@@ -20,25 +37,76 @@ pub async fn read_file_content(file_path: String) -> Result<String, String> {
     
     match extension.as_str() {
         "pdf" => {
-            // PDF 文件：使用 pdf-extract 解析
-            extract_pdf_text(&file_path)
+            // PDF 文件：逐页解析文本层，扫描页使用 OCR（耗时较长，放到阻塞线程中）
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || pdf::extract_pdf(&path))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| format!("PDF 解析失败: {:#}", e))
+        }
+        "png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "webp" => {
+            // 图片：本地 OCR 识别
+            let path = path.to_path_buf();
+            let page = tokio::task::spawn_blocking(move || ocr::ocr_image(&path))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| format!("图片文字识别失败: {}", e))?;
+            if page.text.trim().is_empty() {
+                return Err("图片中没有识别到文字".to_string());
+            }
+            
+            let mut extracted = ExtractedDocument::from(page.text);
+            extracted.metadata.insert("ocr".to_string(), serde_json::json!({
+                "engine": "tesseract",
+                "pages": [{ "page": page.page, "confidence": page.confidence }],
+            }));
+            Ok(extracted)
+        }
+        "docx" | "pptx" | "xlsx" => {
+            // Office 文档：解压并解析 XML（大文件耗时较长，放到阻塞线程中）
+            let path = path.to_path_buf();
+            let (extract, kind): (fn(&Path) -> anyhow::Result<String>, &str) = match extension.as_str() {
+                "docx" => (office::extract_docx, "Word"),
+                "pptx" => (office::extract_pptx, "PowerPoint"),
+                _ => (office::extract_xlsx, "Excel"),
+            };
+            tokio::task::spawn_blocking(move || extract(&path))
+                .await
+                .map_err(|e| e.to_string())?
+                .map(Into::into)
+                .map_err(|e| format!("{} 文档解析失败: {}", kind, e))
+        }
+        "doc" | "ppt" | "xls" => {
+            Err(format!("暂不支持旧版 .{} 格式，请另存为 .{}x 后再导入", extension, extension))
+        }
+        "html" | "htm" | "xhtml" => {
+            let bytes = fs::read(path).await
+                .map_err(|e| format!("读取文件失败: {}", e))?;
+            Ok(html::extract_html_bytes(&bytes))
+        }
+        "epub" => {
+            // 电子书：解压并解析各章节（放到阻塞线程中）
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || epub::extract_epub(&path))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| format!("电子书解析失败: {}", e))
+        }
+        "mhtml" | "mht" => {
+            let bytes = fs::read(path).await
+                .map_err(|e| format!("读取文件失败: {}", e))?;
+            html::extract_mhtml(&bytes)
+                .map_err(|e| format!("网页存档解析失败: {}", e))
         }
         _ => {
             // 其他文本文件：直接读取
-            fs::read_to_string(&path).await
+            fs::read_to_string(path).await
+                .map(Into::into)
                 .map_err(|e| format!("读取文件失败: {}", e))
         }
     }
 }
 
-/// 从 PDF 文件提取文本
-fn extract_pdf_text(file_path: &str) -> Result<String, String> {
-    use pdf_extract::extract_text;
-    
-    extract_text(file_path)
-        .map_err(|e| format!("PDF 解析失败: {}. 可能是扫描版 PDF 或加密文件", e))
-}
-
 /// 获取文件信息
 #[tauri::command]
 pub async fn get_file_info(file_path: String) -> Result<(String, usize), String> {
@@ -0,0 +1,399 @@
+use crate::app_state::AppState;
+use crate::commands::document::file_name;
+use crate::commands::file::is_supported_extension;
+use crate::commands::ingestion::{self, JobSource};
+use crate::hash::file_hash;
+use crate::rag::knowledge_base::DEFAULT_KNOWLEDGE_BASE_ID;
+use crate::rag::ChunkStrategy;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// 文件夹导入事件：每处理完一个文件发送一次
+pub const EVENT_IMPORT_PROGRESS: &str = "import://progress";
+
+/// 同时导入的文件数（单个文件的向量化本身已分批并发）
+const IMPORT_WORKERS: usize = 2;
+/// 待导入队列的容量，队列满时暂停检查后续文件
+const IMPORT_QUEUE_CAPACITY: usize = 8;
+
+#[derive(serde::Deserialize)]
+pub struct ImportFolderRequest {
+    path: String,
+    /// 只导入匹配的文件（相对导入目录的通配符，例如 `docs/**/*.md`），为空时导入全部
+    #[serde(default)]
+    include: Vec<String>,
+    /// 排除匹配的文件或目录，例如 `node_modules`、`**/*.log`
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// 本次导入使用的分块策略，为空时使用 RAG 配置中的默认策略
+    #[serde(default)]
+    chunk_strategy: Option<ChunkStrategy>,
+    /// 导入到的知识库，为空时导入默认知识库
+    #[serde(default)]
+    knowledge_base_id: Option<String>,
+    /// 前端生成的导入 ID，用于匹配进度事件
+    #[serde(default)]
+    import_id: Option<String>,
+}
+
+/// 单个文件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Imported,
+    /// 与已导入的文档或本次导入的其他文件内容相同
+    Duplicate,
+    Unsupported,
+    Failed,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ImportFileResult {
+    /// 相对导入目录的路径
+    path: String,
+    status: ImportStatus,
+    message: Option<String>,
+    document_id: Option<String>,
+}
+
+impl ImportFileResult {
+    fn new(path: String, status: ImportStatus, message: Option<String>) -> Self {
+        Self {
+            path,
+            status,
+            message,
+            document_id: None,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ImportProgressEvent {
+    import_id: String,
+    /// 已处理的文件数（含跳过的文件）
+    processed: usize,
+    total: usize,
+    file: ImportFileResult,
+}
+
+/// 导入汇总报告
+#[derive(serde::Serialize)]
+pub struct ImportSummary {
+    import_id: String,
+    total: usize,
+    imported: usize,
+    duplicates: usize,
+    unsupported: usize,
+    failed: usize,
+    elapsed_ms: u64,
+    files: Vec<ImportFileResult>,
+}
+
+/// 进度计数，并向前端发送进度事件
+struct Progress {
+    app: AppHandle,
+    import_id: String,
+    total: usize,
+    processed: AtomicUsize,
+}
+
+impl Progress {
+    fn report(&self, file: &ImportFileResult) {
+        let processed = self.processed.fetch_add(1, Ordering::SeqCst) + 1;
+        if file.status == ImportStatus::Failed {
+            println!("❌ 导入失败 [{}/{}] {}: {}", processed, self.total, file.path, file.message.as_deref().unwrap_or(""));
+        }
+
+        let _ = self.app.emit(EVENT_IMPORT_PROGRESS, ImportProgressEvent {
+            import_id: self.import_id.clone(),
+            processed,
+            total: self.total,
+            file: file.clone(),
+        });
+    }
+}
+
+/// 导入整个文件夹
+///
+/// 递归扫描目录（跳过隐藏文件和目录），按 include / exclude 规则过滤后，跳过不支持的
+/// 格式和内容重复的文件，其余文件经有界队列由固定数量的任务并发导入。每个文件处理完后
+/// 发送 `import://progress` 事件，全部完成后返回汇总报告。
+#[tauri::command]
+pub async fn import_folder(
+    request: ImportFolderRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportSummary, String> {
+    if !state.is_rag_initialized() {
+        return Err("请先配置 API Key".to_string());
+    }
+
+    let root = PathBuf::from(&request.path);
+    if !root.is_dir() {
+        return Err("文件夹不存在".to_string());
+    }
+    let include = build_glob_set(&request.include)?;
+    let exclude = build_glob_set(&request.exclude)?;
+    let import_id = request.import_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    let knowledge_base_id = request.knowledge_base_id.clone()
+        .unwrap_or_else(|| DEFAULT_KNOWLEDGE_BASE_ID.to_string());
+    state.knowledge_base_settings(&knowledge_base_id).await?;
+    let start_time = std::time::Instant::now();
+
+    // 1. 扫描目录
+    let files = tokio::task::spawn_blocking(move || scan_folder(&root, include.as_ref(), exclude.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("📂 文件夹扫描完成: {} 个文件 ({})", files.len(), request.path);
+
+    let progress = Arc::new(Progress {
+        app: app.clone(),
+        import_id: import_id.clone(),
+        total: files.len(),
+        processed: AtomicUsize::new(0),
+    });
+
+    // 2. 启动导入任务，从有界队列中取文件
+    let (sender, receiver) = mpsc::channel::<(PathBuf, String)>(IMPORT_QUEUE_CAPACITY);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers: Vec<_> = (0..IMPORT_WORKERS)
+        .map(|_| {
+            let app = app.clone();
+            let receiver = receiver.clone();
+            let progress = progress.clone();
+            let chunk_strategy = request.chunk_strategy;
+            let knowledge_base_id = knowledge_base_id.clone();
+
+            tauri::async_runtime::spawn(async move {
+                let state = app.state::<AppState>();
+                let mut results = Vec::new();
+
+                loop {
+                    let job = receiver.lock().await.recv().await;
+                    let Some((path, relative)) = job else { break };
+
+                    let file_path = path.to_string_lossy().to_string();
+                    let source = JobSource::File { path: file_path.clone() };
+                    let job = ingestion::run_job_now(&app, &state, &knowledge_base_id, &file_name(&file_path), &source, chunk_strategy).await;
+                    let result = match job {
+                        Ok(job) if job.status == "completed" && job.stage == "duplicate" => ImportFileResult {
+                            document_id: Some(job.document_id),
+                            ..ImportFileResult::new(relative, ImportStatus::Duplicate, Some("与已导入的文档内容相同".to_string()))
+                        },
+                        Ok(job) if job.status == "completed" && job.stage == "unchanged" => ImportFileResult {
+                            document_id: Some(job.document_id),
+                            ..ImportFileResult::new(relative, ImportStatus::Duplicate, Some("内容未变化".to_string()))
+                        },
+                        Ok(job) if job.status == "completed" => ImportFileResult {
+                            document_id: Some(job.document_id),
+                            ..ImportFileResult::new(relative, ImportStatus::Imported, None)
+                        },
+                        Ok(job) if job.status == "cancelled" => {
+                            ImportFileResult::new(relative, ImportStatus::Failed, Some("导入已取消".to_string()))
+                        }
+                        Ok(job) => ImportFileResult::new(relative, ImportStatus::Failed, job.error),
+                        Err(e) => ImportFileResult::new(relative, ImportStatus::Failed, Some(e)),
+                    };
+                    progress.report(&result);
+                    results.push(result);
+                }
+
+                results
+            })
+        })
+        .collect();
+
+    // 3. 跳过不支持和重复的文件，其余放入队列（队列满时等待）
+    let mut results = Vec::new();
+    let mut seen_hashes = HashSet::new();
+    for (path, relative) in files {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let skipped = if !is_supported_extension(&extension) {
+            Some(ImportFileResult::new(relative, ImportStatus::Unsupported, Some("不支持的文件格式".to_string())))
+        } else {
+            match file_hash(&path).await {
+                Err(e) => Some(ImportFileResult::new(relative, ImportStatus::Failed, Some(e))),
+                Ok(hash) if !seen_hashes.insert(hash.clone()) => Some(ImportFileResult::new(
+                    relative,
+                    ImportStatus::Duplicate,
+                    Some("与本次导入的其他文件内容相同".to_string()),
+                )),
+                // 查询失败只影响该文件，导入任务仍在执行，需要等它们结束后返回汇总
+                Ok(hash) => match find_document_by_hash(&state, &knowledge_base_id, &hash).await {
+                    Err(e) => Some(ImportFileResult::new(relative, ImportStatus::Failed, Some(e))),
+                    Ok(Some(name)) => Some(ImportFileResult::new(
+                        relative,
+                        ImportStatus::Duplicate,
+                        Some(format!("已导入为「{}」", name)),
+                    )),
+                    Ok(None) => {
+                        if sender.send((path, relative)).await.is_err() {
+                            break;
+                        }
+                        None
+                    }
+                },
+            }
+        };
+
+        if let Some(result) = skipped {
+            progress.report(&result);
+            results.push(result);
+        }
+    }
+    drop(sender);
+
+    for worker in workers {
+        results.extend(worker.await.map_err(|e| e.to_string())?);
+    }
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let count = |status: ImportStatus| results.iter().filter(|r| r.status == status).count();
+    let summary = ImportSummary {
+        import_id,
+        total: results.len(),
+        imported: count(ImportStatus::Imported),
+        duplicates: count(ImportStatus::Duplicate),
+        unsupported: count(ImportStatus::Unsupported),
+        failed: count(ImportStatus::Failed),
+        elapsed_ms: start_time.elapsed().as_millis() as u64,
+        files: results,
+    };
+    println!(
+        "✅ 文件夹导入完成: 导入 {}，重复 {}，不支持 {}，失败 {} (耗时: {:.2}秒)",
+        summary.imported,
+        summary.duplicates,
+        summary.unsupported,
+        summary.failed,
+        start_time.elapsed().as_secs_f64()
+    );
+
+    Ok(summary)
+}
+
+/// 编译通配符规则，没有规则时返回 None
+pub(crate) fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    let patterns: Vec<&str> = patterns.iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| format!("无效的匹配规则 {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+
+    builder.build()
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// 文件相对导入目录的路径，统一使用 `/` 分隔以便匹配通配符
+pub(crate) fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// 递归列出目录下需要处理的文件（按路径排序），返回绝对路径和相对路径
+pub(crate) fn scan_folder(root: &Path, include: Option<&GlobSet>, exclude: Option<&GlobSet>) -> Vec<(PathBuf, String)> {
+    WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let excluded = exclude.is_some_and(|set| set.is_match(relative_path(root, entry.path())));
+            !hidden && !excluded
+        })
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                println!("⚠️ 跳过无法访问的路径: {}", e);
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = relative_path(root, entry.path());
+            let included = include.is_none_or(|set| set.is_match(&relative));
+            included.then(|| (entry.into_path(), relative))
+        })
+        .collect()
+}
+
+/// 查找知识库中内容哈希相同的已导入文档，返回文档名称
+async fn find_document_by_hash(state: &AppState, knowledge_base_id: &str, hash: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT name FROM documents WHERE knowledge_base_id = ? AND json_extract(metadata, '$.file_hash') = ? LIMIT 1"
+    )
+    .bind(knowledge_base_id)
+    .bind(hash)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_state;
+    use crate::db::TempDataDir;
+
+    #[test]
+    fn test_scan_folder_filters() {
+        let root = TempDataDir::new();
+        for file in ["a.md", "docs/b.md", "docs/c.txt", "build/d.md", ".git/e.md"] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, file).unwrap();
+        }
+
+        let include = build_glob_set(&["**/*.md".to_string()]).unwrap();
+        let exclude = build_glob_set(&["build".to_string(), " ".to_string()]).unwrap();
+        let files: Vec<String> = scan_folder(&root, include.as_ref(), exclude.as_ref())
+            .into_iter()
+            .map(|(_, relative)| relative)
+            .collect();
+        assert_eq!(files, vec!["a.md", "docs/b.md"]);
+
+        assert!(build_glob_set(&[]).unwrap().is_none());
+        assert!(build_glob_set(&["[".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_document_by_hash_in_knowledge_base() {
+        let (state, _data_dir) = test_state().await;
+
+        sqlx::query("INSERT INTO documents (id, name, content, created_at, updated_at, metadata) VALUES ('doc', 'a.md', '', 0, 0, ?)")
+            .bind(serde_json::json!({ "file_hash": "abc" }).to_string())
+            .execute(state.db.pool())
+            .await
+            .unwrap();
+
+        // 只在同一知识库中判断重复导入
+        let found = find_document_by_hash(&state, DEFAULT_KNOWLEDGE_BASE_ID, "abc").await.unwrap();
+        assert_eq!(found.as_deref(), Some("a.md"));
+        assert!(find_document_by_hash(&state, "other", "abc").await.unwrap().is_none());
+        assert!(find_document_by_hash(&state, DEFAULT_KNOWLEDGE_BASE_ID, "def").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,1021 @@
+use crate::app_state::{document_vectors, AppState};
+use crate::commands::document::{prepare_file, remove_document, split_document, UploadDocumentRequest};
+use crate::db::models::IngestionJob;
+use crate::hash::content_hash;
+use crate::rag::keyword;
+use crate::rag::text_splitter::TextChunk;
+use crate::rag::vector_store::encode_embedding;
+use crate::rag::ChunkStrategy;
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+/// 导入任务事件：状态、阶段或进度变化时发送（携带任务快照）
+pub const EVENT_INGESTION_PROGRESS: &str = "ingestion://progress";
+
+/// 每批向量化并写入数据库的块数，重试或恢复时从尚未完成的批次继续
+pub(crate) const EMBEDDING_BATCH_SIZE: i64 = 64;
+
+/// 任务列表返回的最大数量
+const MAX_LISTED_JOBS: i64 = 100;
+
+/// 导入任务的来源
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum JobSource {
+    /// 直接上传的文本
+    Text {
+        content: String,
+        file_type: Option<String>,
+        metadata: Option<serde_json::Value>,
+    },
+    /// 在任务中提取内容的文件
+    File { path: String },
+}
+
+/// 暂存的提取结果，任务完成时写入 documents 表
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StagedDocument {
+    content: String,
+    file_type: Option<String>,
+    metadata: Option<serde_json::Value>,
+}
+
+/// 暂存的文档块
+#[derive(sqlx::FromRow)]
+struct StagedChunk {
+    chunk_index: i64,
+    content: String,
+    start_offset: Option<i64>,
+    end_offset: Option<i64>,
+    section: Option<String>,
+    page_start: Option<i64>,
+    page_end: Option<i64>,
+    symbol: Option<String>,
+    embedding: Option<Vec<u8>>,
+    content_hash: Option<String>,
+}
+
+/// 任务中止的原因
+#[derive(Debug)]
+enum JobError {
+    /// 任务被取消或删除
+    Cancelled,
+    /// 内容与已有文档相同（重新导入时即内容未变化），无需导入
+    Duplicate { document_id: String },
+    Failed(String),
+}
+
+impl From<String> for JobError {
+    fn from(error: String) -> Self {
+        JobError::Failed(error)
+    }
+}
+
+/// 加入后台导入队列，导入到指定知识库
+pub(crate) async fn enqueue_job(
+    state: &AppState,
+    knowledge_base_id: &str,
+    name: &str,
+    source: &JobSource,
+    chunk_strategy: Option<ChunkStrategy>,
+) -> Result<IngestionJob, String> {
+    let job = create_job(state, knowledge_base_id, name, source, chunk_strategy, "queued").await?;
+    state.ingestion_notify.notify_one();
+
+    println!("📥 已加入导入队列: {}", name);
+    Ok(job)
+}
+
+/// 在当前任务中立即执行导入（不经过后台队列），返回结束时的任务状态
+pub(crate) async fn run_job_now(
+    app: &AppHandle,
+    state: &AppState,
+    knowledge_base_id: &str,
+    name: &str,
+    source: &JobSource,
+    chunk_strategy: Option<ChunkStrategy>,
+) -> Result<IngestionJob, String> {
+    let job = create_job(state, knowledge_base_id, name, source, chunk_strategy, "running").await?;
+    run_job(app, state, &job.id).await;
+
+    load_job(state, &job.id).await?
+        .ok_or_else(|| "导入任务已被删除".to_string())
+}
+
+/// 启动后台导入任务：先恢复上次中断的任务，再按创建顺序逐个执行排队的任务
+pub fn spawn_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+
+        match requeue_interrupted_jobs(&state).await {
+            Ok(count) if count > 0 => println!("🔄 恢复 {} 个未完成的导入任务", count),
+            Ok(_) => {}
+            Err(e) => println!("⚠️ 恢复导入任务失败: {}", e),
+        }
+
+        loop {
+            match claim_next_job(&state).await {
+                Ok(Some(job_id)) => run_job(&app, &state, &job_id).await,
+                Ok(None) => state.ingestion_notify.notified().await,
+                Err(e) => {
+                    println!("⚠️ 读取导入任务失败: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+}
+
+/// 上次退出时正在执行的任务重新排队，从已完成的阶段继续，返回恢复的任务数
+async fn requeue_interrupted_jobs(state: &AppState) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("UPDATE ingestion_jobs SET status = 'queued' WHERE status = 'running'")
+        .execute(state.db.pool())
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// 获取最近的导入任务
+#[tauri::command]
+pub async fn get_ingestion_jobs(
+    state: State<'_, AppState>,
+) -> Result<Vec<IngestionJob>, String> {
+    sqlx::query_as::<_, IngestionJob>(
+        "SELECT * FROM ingestion_jobs ORDER BY created_at DESC LIMIT ?"
+    )
+    .bind(MAX_LISTED_JOBS)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 取消导入任务，并清除暂存的分块结果
+///
+/// 正在执行的任务在下一个检查点（每批向量化之前或提交时）停止并清理。
+#[tauri::command]
+pub async fn cancel_ingestion_job(
+    job_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let cancelled = cancel_job(&state, &job_id).await?;
+    if cancelled {
+        emit_job(&app, &state, &job_id).await;
+    }
+
+    Ok(cancelled)
+}
+
+/// 将任务标记为已取消，任务已结束时返回 false
+async fn cancel_job(state: &AppState, job_id: &str) -> Result<bool, String> {
+    let job = load_job(state, job_id).await?
+        .ok_or_else(|| "导入任务不存在".to_string())?;
+
+    let result = sqlx::query(
+        "UPDATE ingestion_jobs SET status = 'cancelled', updated_at = ?
+         WHERE id = ? AND status IN ('queued', 'running', 'failed')"
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(job_id)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    if job.status != "running" {
+        discard_staging(state, job_id).await;
+    }
+
+    Ok(true)
+}
+
+/// 重试失败的导入任务，从失败的阶段（例如未完成的向量批次）继续
+#[tauri::command]
+pub async fn retry_ingestion_job(
+    job_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE ingestion_jobs SET status = 'queued', error = NULL, updated_at = ?
+         WHERE id = ? AND status = 'failed'"
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&job_id)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    state.ingestion_notify.notify_one();
+    emit_job(&app, &state, &job_id).await;
+
+    Ok(true)
+}
+
+/// 分块策略在数据库中保存的名称（与前端一致，如 `recursive`）
+pub(crate) fn strategy_name(strategy: ChunkStrategy) -> Option<String> {
+    serde_json::to_value(strategy).ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+}
+
+/// 解析数据库中保存的分块策略名称
+pub(crate) fn parse_strategy(name: &str) -> Option<ChunkStrategy> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+async fn create_job(
+    state: &AppState,
+    knowledge_base_id: &str,
+    name: &str,
+    source: &JobSource,
+    chunk_strategy: Option<ChunkStrategy>,
+    status: &str,
+) -> Result<IngestionJob, String> {
+    // 知识库不存在时直接报错
+    state.knowledge_base_settings(knowledge_base_id).await?;
+
+    let timestamp = chrono::Utc::now().timestamp();
+    let chunk_strategy = chunk_strategy.and_then(strategy_name);
+
+    // 已导入过的文件重新导入到同一知识库时替换原文档；
+    // 文件还有未完成的导入任务时沿用该任务的文档 ID，避免同一文件产生两个文档
+    let existing = match source {
+        JobSource::File { path } => sqlx::query_scalar::<_, String>(
+            "SELECT id FROM (
+                 SELECT id, 0 AS priority FROM documents
+                 WHERE knowledge_base_id = ?1 AND json_extract(metadata, '$.source_path') = ?2
+                 UNION ALL
+                 SELECT document_id, 1 FROM ingestion_jobs
+                 WHERE knowledge_base_id = ?1 AND status IN ('queued', 'running') AND json_extract(source, '$.path') = ?2
+             )
+             ORDER BY priority LIMIT 1"
+        )
+        .bind(knowledge_base_id)
+        .bind(path)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?,
+        JobSource::Text { .. } => None,
+    };
+
+    let job = IngestionJob {
+        id: Uuid::new_v4().to_string(),
+        document_id: existing.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        knowledge_base_id: knowledge_base_id.to_string(),
+        name: name.to_string(),
+        source: serde_json::to_string(source).map_err(|e| e.to_string())?,
+        chunk_strategy,
+        status: status.to_string(),
+        stage: "extracting".to_string(),
+        progress_done: 0,
+        progress_total: 0,
+        error: None,
+        created_at: timestamp,
+        updated_at: timestamp,
+    };
+
+    sqlx::query(
+        "INSERT INTO ingestion_jobs (id, document_id, knowledge_base_id, name, source, chunk_strategy, status, stage, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&job.id)
+    .bind(&job.document_id)
+    .bind(&job.knowledge_base_id)
+    .bind(&job.name)
+    .bind(&job.source)
+    .bind(&job.chunk_strategy)
+    .bind(&job.status)
+    .bind(&job.stage)
+    .bind(job.created_at)
+    .bind(job.updated_at)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(job)
+}
+
+async fn load_job(state: &AppState, job_id: &str) -> Result<Option<IngestionJob>, String> {
+    sqlx::query_as::<_, IngestionJob>("SELECT * FROM ingestion_jobs WHERE id = ?")
+        .bind(job_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 认领最早排队的任务（标记为 running），没有任务时返回 None
+async fn claim_next_job(state: &AppState) -> Result<Option<String>, sqlx::Error> {
+    loop {
+        let next = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM ingestion_jobs WHERE status = 'queued' ORDER BY created_at LIMIT 1"
+        )
+        .fetch_optional(state.db.pool())
+        .await?;
+
+        let Some(job_id) = next else {
+            return Ok(None);
+        };
+
+        // 任务可能在查询之后被取消
+        let claimed = sqlx::query(
+            "UPDATE ingestion_jobs SET status = 'running', updated_at = ? WHERE id = ? AND status = 'queued'"
+        )
+        .bind(chrono::Utc::now().timestamp())
+        .bind(&job_id)
+        .execute(state.db.pool())
+        .await?;
+
+        if claimed.rows_affected() > 0 {
+            return Ok(Some(job_id));
+        }
+    }
+}
+
+/// 执行已认领的任务，结束时更新状态并通知前端
+async fn run_job(app: &AppHandle, state: &AppState, job_id: &str) {
+    let job = match load_job(state, job_id).await {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            println!("⚠️ 读取导入任务失败: {}", e);
+            return;
+        }
+    };
+
+    println!("⚙️ 开始导入: {}", job.name);
+    let start_time = std::time::Instant::now();
+    emit_job(app, state, job_id).await;
+
+    match process_job(app, state, &job).await {
+        Ok(()) => {
+            println!("✅ 导入完成: {} (耗时: {:.2}秒)", job.name, start_time.elapsed().as_secs_f64());
+        }
+        Err(JobError::Cancelled) => {
+            println!("🚫 导入已取消: {}", job.name);
+            discard_staging(state, job_id).await;
+        }
+        Err(JobError::Duplicate { document_id }) => complete_duplicate(state, &job, &document_id).await,
+        Err(JobError::Failed(error)) => {
+            println!("❌ 导入失败: {}: {}", job.name, error);
+            let failed = sqlx::query(
+                "UPDATE ingestion_jobs SET status = 'failed', error = ?, updated_at = ? WHERE id = ? AND status = 'running'"
+            )
+            .bind(&error)
+            .bind(chrono::Utc::now().timestamp())
+            .bind(job_id)
+            .execute(state.db.pool())
+            .await;
+
+            // 失败的同时任务已被取消：由这里清理暂存数据
+            if matches!(failed, Ok(result) if result.rows_affected() == 0) {
+                discard_staging(state, job_id).await;
+            }
+        }
+    }
+
+    emit_job(app, state, job_id).await;
+}
+
+/// 跳过内容与已有文档相同的任务：标记为完成并指向该文档
+async fn complete_duplicate(state: &AppState, job: &IngestionJob, document_id: &str) {
+    let stage = if document_id == job.document_id { "unchanged" } else { "duplicate" };
+    println!("⏭️ 跳过导入: {} ({})", job.name, stage);
+    discard_staging(state, &job.id).await;
+
+    let completed = sqlx::query(
+        "UPDATE ingestion_jobs SET status = 'completed', stage = ?, document_id = ?, updated_at = ?
+         WHERE id = ? AND status = 'running'"
+    )
+    .bind(stage)
+    .bind(document_id)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&job.id)
+    .execute(state.db.pool())
+    .await;
+
+    if stage == "duplicate" && matches!(completed, Ok(result) if result.rows_affected() > 0) {
+        if let Err(e) = remove_stale_document(state, job, document_id).await {
+            println!("⚠️ 删除原文档失败: {}: {}", job.name, e);
+        }
+    }
+}
+
+/// 重新导入的文件内容与另一文档相同时，原文档的内容已过期，将其删除
+///
+/// 否则原文档保留旧的文件哈希，监听目录每次同步都会重新导入该文件。
+/// 同一文档还有其他未完成的任务时由那个任务替换原文档，这里不删除。
+async fn remove_stale_document(state: &AppState, job: &IngestionJob, duplicate_of: &str) -> Result<(), String> {
+    let pending = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM ingestion_jobs WHERE document_id = ? AND id != ? AND status IN ('queued', 'running')"
+    )
+    .bind(&job.document_id)
+    .bind(&job.id)
+    .fetch_one(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    if pending > 0 {
+        return Ok(());
+    }
+
+    if let Some(knowledge_base_id) = remove_document(state, &job.document_id).await? {
+        state.persist_vector_index(&knowledge_base_id);
+        println!("🗑️ 删除内容已过期的原文档: {}（与文档 {} 重复）", job.name, duplicate_of);
+    }
+    Ok(())
+}
+
+/// 分阶段处理：提取与分块 → 分批向量化 → 提交文档
+async fn process_job(app: &AppHandle, state: &AppState, job: &IngestionJob) -> Result<(), JobError> {
+    // 分块和向量化使用知识库自己的设置
+    let (config, embedding_service) = state.knowledge_base_services(&job.knowledge_base_id).await?;
+
+    // 1. 提取和分块，结果写入暂存区（重试或恢复时已暂存则跳过）
+    let staged = sqlx::query_scalar::<_, Option<String>>("SELECT document FROM ingestion_jobs WHERE id = ?")
+        .bind(&job.id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or(JobError::Cancelled)?;
+
+    let document = match staged {
+        Some(json) => serde_json::from_str::<StagedDocument>(&json)
+            .map_err(|e| format!("暂存文档解析失败: {}", e))?,
+        None => {
+            update_progress(app, state, &job.id, "extracting", 0, 0).await?;
+
+            let source: JobSource = serde_json::from_str(&job.source)
+                .map_err(|e| format!("任务来源解析失败: {}", e))?;
+            let mut request = match source {
+                JobSource::Text { content, file_type, metadata } => UploadDocumentRequest {
+                    name: job.name.clone(),
+                    content,
+                    file_type,
+                    metadata,
+                    chunk_strategy: None,
+                    knowledge_base_id: None,
+                    sections: Vec::new(),
+                    page_starts: Vec::new(),
+                },
+                JobSource::File { path } => prepare_file(&path).await?,
+            };
+            if let Some(document_id) = find_duplicate(state, job, &request.content).await? {
+                return Err(JobError::Duplicate { document_id });
+            }
+
+            request.chunk_strategy = job.chunk_strategy.as_deref().and_then(parse_strategy);
+
+            let chunks = split_document(&config, &embedding_service, &mut request).await?;
+            let document = StagedDocument {
+                content: request.content,
+                file_type: request.file_type,
+                metadata: request.metadata,
+            };
+            stage_document(state, &job.id, &document, &chunks).await?;
+            document
+        }
+    };
+
+    // 2. 分批生成向量，每批完成后写入暂存区
+    // 重新导入时，内容未变化的块直接复用原文档中的向量（仅当原向量由同一 Embedding 服务生成）
+    let embedding_model = config.embedding_signature();
+    let reused = sqlx::query(
+        "UPDATE ingestion_chunks SET embedding = (
+             SELECT c.embedding FROM chunks c
+             WHERE c.document_id = ?1 AND c.content_hash = ingestion_chunks.content_hash AND c.embedding IS NOT NULL
+             LIMIT 1
+         )
+         WHERE job_id = ?2 AND embedding IS NULL
+           AND EXISTS (SELECT 1 FROM documents d WHERE d.id = ?1 AND d.embedding_model = ?3)
+           AND EXISTS (
+             SELECT 1 FROM chunks c
+             WHERE c.document_id = ?1 AND c.content_hash = ingestion_chunks.content_hash AND c.embedding IS NOT NULL
+           )"
+    )
+    .bind(&job.document_id)
+    .bind(&job.id)
+    .bind(&embedding_model)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    if reused.rows_affected() > 0 {
+        println!("♻️ 复用 {} 个未变化块的向量", reused.rows_affected());
+    }
+
+    let (total, mut done) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COUNT(embedding) FROM ingestion_chunks WHERE job_id = ?"
+    )
+    .bind(&job.id)
+    .fetch_one(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    loop {
+        ensure_running(state, &job.id).await?;
+        update_progress(app, state, &job.id, "embedding", done, total).await?;
+
+        let batch = sqlx::query_as::<_, (i64, String)>(
+            "SELECT chunk_index, content FROM ingestion_chunks
+             WHERE job_id = ? AND embedding IS NULL ORDER BY chunk_index LIMIT ?"
+        )
+        .bind(&job.id)
+        .bind(EMBEDDING_BATCH_SIZE)
+        .fetch_all(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if batch.is_empty() {
+            break;
+        }
+
+        let texts: Vec<String> = batch.iter().map(|(_, content)| content.clone()).collect();
+        let embeddings = embedding_service.embed_batch(&texts).await
+            .map_err(|e| format!("向量生成失败（已完成 {}/{} 个块）: {}", done, total, e))?;
+        if embeddings.len() != batch.len() {
+            return Err(JobError::Failed(format!("向量数量不匹配: {} 个块，{} 个向量", batch.len(), embeddings.len())));
+        }
+
+        let mut tx = state.db.pool().begin().await
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+        for ((chunk_index, _), embedding) in batch.iter().zip(&embeddings) {
+            sqlx::query("UPDATE ingestion_chunks SET embedding = ? WHERE job_id = ? AND chunk_index = ?")
+                .bind(encode_embedding(embedding))
+                .bind(&job.id)
+                .bind(chunk_index)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("保存向量失败: {}", e))?;
+        }
+        tx.commit().await
+            .map_err(|e| format!("提交事务失败: {}", e))?;
+
+        done += batch.len() as i64;
+    }
+
+    // 3. 提交文档
+    update_progress(app, state, &job.id, "indexing", done, total).await?;
+    commit_document(state, job, &document, &embedding_model).await
+}
+
+/// 同一知识库中内容相同的文档（优先匹配重新导入的原文档，即内容未变化）
+async fn find_duplicate(state: &AppState, job: &IngestionJob, content: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT id FROM documents WHERE knowledge_base_id = ? AND content_hash = ? ORDER BY id = ? DESC LIMIT 1"
+    )
+    .bind(&job.knowledge_base_id)
+    .bind(content_hash(content))
+    .bind(&job.document_id)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 在一个事务中写入文档、文档块（含向量）和关键词索引，并将任务标记为完成
+///
+/// 重新导入时整体替换原文档的内容和文档块。向量在提交前写入向量存储，
+/// 提交失败时恢复为原来的向量，两者始终一致。`embedding_model` 为生成向量的 Embedding 服务标识。
+async fn commit_document(
+    state: &AppState,
+    job: &IngestionJob,
+    document: &StagedDocument,
+    embedding_model: &str,
+) -> Result<(), JobError> {
+    let timestamp = chrono::Utc::now().timestamp();
+    let mut tx = state.db.pool().begin().await
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+
+    let previous_vectors = document_vectors(&mut tx, &job.document_id).await
+        .map_err(|e| format!("读取文档向量失败: {}", e))?;
+    let document_hash = content_hash(&document.content);
+    let metadata = document.metadata.as_ref().map(|m| m.to_string());
+
+    let replaced = sqlx::query(
+        "UPDATE documents SET name = ?, content = ?, file_type = ?, file_size = ?, updated_at = ?, metadata = ?, content_hash = ?,
+             embedding_model = ?
+         WHERE id = ?"
+    )
+    .bind(&job.name)
+    .bind(&document.content)
+    .bind(&document.file_type)
+    .bind(document.content.len() as i64)
+    .bind(timestamp)
+    .bind(&metadata)
+    .bind(&document_hash)
+    .bind(embedding_model)
+    .bind(&job.document_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("更新文档失败: {}", e))?;
+
+    if replaced.rows_affected() > 0 {
+        // 关键词索引由触发器同步删除
+        sqlx::query("DELETE FROM chunks WHERE document_id = ?")
+            .bind(&job.document_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("删除原文档块失败: {}", e))?;
+    } else {
+        sqlx::query(
+            "INSERT INTO documents (id, knowledge_base_id, name, content, file_type, file_size, created_at, updated_at, metadata, content_hash, embedding_model) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&job.document_id)
+        .bind(&job.knowledge_base_id)
+        .bind(&job.name)
+        .bind(&document.content)
+        .bind(&document.file_type)
+        .bind(document.content.len() as i64)
+        .bind(timestamp)
+        .bind(timestamp)
+        .bind(&metadata)
+        .bind(&document_hash)
+        .bind(embedding_model)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("写入文档失败: {}", e))?;
+    }
+
+    let chunks = sqlx::query_as::<_, StagedChunk>(
+        "SELECT * FROM ingestion_chunks WHERE job_id = ? ORDER BY chunk_index"
+    )
+    .bind(&job.id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for chunk in &chunks {
+        let chunk_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO chunks (id, document_id, content, chunk_index, created_at, start_offset, end_offset, section, page_start, page_end, symbol, embedding, content_hash) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&chunk_id)
+        .bind(&job.document_id)
+        .bind(&chunk.content)
+        .bind(chunk.chunk_index)
+        .bind(timestamp)
+        .bind(chunk.start_offset)
+        .bind(chunk.end_offset)
+        .bind(&chunk.section)
+        .bind(chunk.page_start)
+        .bind(chunk.page_end)
+        .bind(&chunk.symbol)
+        .bind(&chunk.embedding)
+        .bind(&chunk.content_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("插入块 {} 失败: {}", chunk.chunk_index, e))?;
+
+        keyword::index_chunk(&mut tx, &chunk_id, &chunk.content)
+            .await
+            .map_err(|e| format!("写入关键词索引失败: {}", e))?;
+    }
+
+    let completed = sqlx::query(
+        "UPDATE ingestion_jobs SET status = 'completed', document = NULL, error = NULL, updated_at = ?
+         WHERE id = ? AND status = 'running'"
+    )
+    .bind(timestamp)
+    .bind(&job.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if completed.rows_affected() == 0 {
+        // 事务未提交，随 tx 丢弃而回滚
+        return Err(JobError::Cancelled);
+    }
+
+    sqlx::query("DELETE FROM ingestion_chunks WHERE job_id = ?")
+        .bind(&job.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let vector_docs = document_vectors(&mut tx, &job.document_id).await
+        .map_err(|e| format!("读取文档向量失败: {}", e))?;
+    let vector_store = state.vector_store(&job.knowledge_base_id);
+    vector_store.remove_by_document_id(&job.document_id);
+    vector_store.add_documents(vector_docs);
+
+    if let Err(e) = tx.commit().await {
+        vector_store.remove_by_document_id(&job.document_id);
+        vector_store.add_documents(previous_vectors);
+        return Err(JobError::Failed(format!("提交事务失败: {}", e)));
+    }
+    state.persist_vector_index(&job.knowledge_base_id);
+
+    Ok(())
+}
+
+/// 在一个事务中暂存提取后的文档和分块结果
+async fn stage_document(
+    state: &AppState,
+    job_id: &str,
+    document: &StagedDocument,
+    chunks: &[TextChunk],
+) -> Result<(), JobError> {
+    let document_json = serde_json::to_string(document).map_err(|e| e.to_string())?;
+    let mut tx = state.db.pool().begin().await
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+
+    let staged = sqlx::query(
+        "UPDATE ingestion_jobs SET document = ?, progress_total = ? WHERE id = ? AND status = 'running'"
+    )
+    .bind(&document_json)
+    .bind(chunks.len() as i64)
+    .bind(job_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if staged.rows_affected() == 0 {
+        return Err(JobError::Cancelled);
+    }
+
+    sqlx::query("DELETE FROM ingestion_chunks WHERE job_id = ?")
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO ingestion_chunks (job_id, chunk_index, content, start_offset, end_offset, section, page_start, page_end, symbol, content_hash) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(job_id)
+        .bind(index as i64)
+        .bind(&chunk.content)
+        .bind(chunk.start as i64)
+        .bind(chunk.end as i64)
+        .bind(&chunk.section)
+        .bind(chunk.page_start.map(|p| p as i64))
+        .bind(chunk.page_end.map(|p| p as i64))
+        .bind(&chunk.symbol)
+        .bind(content_hash(&chunk.content))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("暂存块 {} 失败: {}", index, e))?;
+    }
+
+    tx.commit().await
+        .map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+/// 任务仍在执行时返回 Ok，已取消或被删除时返回 Cancelled
+async fn ensure_running(state: &AppState, job_id: &str) -> Result<(), JobError> {
+    let status = sqlx::query_scalar::<_, String>("SELECT status FROM ingestion_jobs WHERE id = ?")
+        .bind(job_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match status.as_deref() {
+        Some("running") => Ok(()),
+        _ => Err(JobError::Cancelled),
+    }
+}
+
+/// 更新任务阶段和进度，并通知前端
+async fn update_progress(
+    app: &AppHandle,
+    state: &AppState,
+    job_id: &str,
+    stage: &str,
+    done: i64,
+    total: i64,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE ingestion_jobs SET stage = ?, progress_done = ?, progress_total = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(stage)
+    .bind(done)
+    .bind(total)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(job_id)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    emit_job(app, state, job_id).await;
+    Ok(())
+}
+
+/// 发送任务的最新状态
+async fn emit_job(app: &AppHandle, state: &AppState, job_id: &str) {
+    if let Ok(Some(job)) = load_job(state, job_id).await {
+        let _ = app.emit(EVENT_INGESTION_PROGRESS, job);
+    }
+}
+
+/// 清除任务暂存的文档和分块结果
+async fn discard_staging(state: &AppState, job_id: &str) {
+    let mut tx = match state.db.pool().begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            println!("⚠️ 清理导入暂存数据失败: {}", e);
+            return;
+        }
+    };
+
+    let cleared = async {
+        sqlx::query("DELETE FROM ingestion_chunks WHERE job_id = ?")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE ingestion_jobs SET document = NULL WHERE id = ?")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    };
+
+    if let Err(e) = cleared.await {
+        println!("⚠️ 清理导入暂存数据失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_state;
+    use crate::rag::knowledge_base::DEFAULT_KNOWLEDGE_BASE_ID;
+
+    const EMBEDDING_MODEL: &str = "Stub||test";
+
+    async fn count(state: &AppState, sql: &str, id: &str) -> i64 {
+        sqlx::query_scalar(sql).bind(id).fetch_one(state.db.pool()).await.unwrap()
+    }
+
+    async fn create_running_job(state: &AppState, name: &str, source: &JobSource) -> IngestionJob {
+        create_job(state, DEFAULT_KNOWLEDGE_BASE_ID, name, source, None, "running").await.unwrap()
+    }
+
+    fn text_source(content: &str) -> JobSource {
+        JobSource::Text { content: content.to_string(), file_type: None, metadata: None }
+    }
+
+    /// 完成提取和向量化两个阶段：按空行分块暂存，并为每个块写入向量
+    async fn stage_embedded(state: &AppState, job: &IngestionJob, content: &str, source_path: Option<&str>) -> StagedDocument {
+        let document = StagedDocument {
+            content: content.to_string(),
+            file_type: None,
+            metadata: source_path.map(|path| serde_json::json!({ "source_path": path })),
+        };
+        let chunks: Vec<TextChunk> = content
+            .split("\n\n")
+            .map(|chunk| TextChunk { content: chunk.to_string(), ..Default::default() })
+            .collect();
+        stage_document(state, &job.id, &document, &chunks).await.unwrap();
+
+        sqlx::query("UPDATE ingestion_chunks SET embedding = ? WHERE job_id = ?")
+            .bind(encode_embedding(&[1.0, 0.0]))
+            .bind(&job.id)
+            .execute(state.db.pool())
+            .await
+            .unwrap();
+        document
+    }
+
+    #[tokio::test]
+    async fn test_requeue_interrupted_jobs() {
+        let (state, _data_dir) = test_state().await;
+
+        let interrupted = create_running_job(&state, "a", &text_source("alpha")).await;
+        stage_embedded(&state, &interrupted, "alpha", None).await;
+        let failed = create_job(&state, DEFAULT_KNOWLEDGE_BASE_ID, "b", &text_source("beta"), None, "failed").await.unwrap();
+
+        // 只有中断的任务重新排队，已暂存的结果保留，恢复后从已完成的阶段继续
+        assert_eq!(requeue_interrupted_jobs(&state).await.unwrap(), 1);
+        assert_eq!(load_job(&state, &interrupted.id).await.unwrap().unwrap().status, "queued");
+        assert_eq!(load_job(&state, &failed.id).await.unwrap().unwrap().status, "failed");
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM ingestion_chunks WHERE job_id = ? AND embedding IS NOT NULL", &interrupted.id).await, 1);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM ingestion_jobs WHERE id = ? AND document IS NOT NULL", &interrupted.id).await, 1);
+
+        assert_eq!(claim_next_job(&state).await.unwrap(), Some(interrupted.id.clone()));
+        assert_eq!(claim_next_job(&state).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_between_stages() {
+        let (state, _data_dir) = test_state().await;
+
+        let job = create_running_job(&state, "doc", &text_source("alpha\n\nbeta")).await;
+        let document = stage_embedded(&state, &job, "alpha\n\nbeta", None).await;
+        assert!(cancel_job(&state, &job.id).await.unwrap());
+        assert!(!cancel_job(&state, &job.id).await.unwrap());
+
+        // 正在执行的任务在下一个检查点停止，由执行方清理暂存数据
+        assert!(matches!(ensure_running(&state, &job.id).await, Err(JobError::Cancelled)));
+        assert!(matches!(commit_document(&state, &job, &document, EMBEDDING_MODEL).await, Err(JobError::Cancelled)));
+        discard_staging(&state, &job.id).await;
+
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM ingestion_chunks WHERE job_id = ?", &job.id).await, 0);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM ingestion_jobs WHERE id = ? AND document IS NOT NULL", &job.id).await, 0);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM documents WHERE id = ?", &job.document_id).await, 0);
+        assert_eq!(load_job(&state, &job.id).await.unwrap().unwrap().status, "cancelled");
+    }
+
+    #[tokio::test]
+    async fn test_commit_rollback_leaves_no_partial_document() {
+        let (state, data_dir) = test_state().await;
+        let path = data_dir.join("notes.md").to_string_lossy().to_string();
+        let source = JobSource::File { path: path.clone() };
+        let vector_store = state.vector_store(DEFAULT_KNOWLEDGE_BASE_ID);
+
+        let first = create_running_job(&state, "notes.md", &source).await;
+        let document = stage_embedded(&state, &first, "alpha\n\nbeta", Some(&path)).await;
+        commit_document(&state, &first, &document, EMBEDDING_MODEL).await.unwrap();
+        assert_eq!(vector_store.document_len(&first.document_id), 2);
+
+        // 新文档提交时任务已被取消：文档、文档块、关键词索引和向量都不写入
+        let other = create_running_job(&state, "other", &text_source("gamma")).await;
+        let staged = stage_embedded(&state, &other, "gamma", None).await;
+        cancel_job(&state, &other.id).await.unwrap();
+        assert!(matches!(commit_document(&state, &other, &staged, EMBEDDING_MODEL).await, Err(JobError::Cancelled)));
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM documents WHERE id = ?", &other.document_id).await, 0);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM chunks WHERE document_id = ?", &other.document_id).await, 0);
+        assert_eq!(vector_store.document_len(&other.document_id), 0);
+
+        // 重新导入的提交回滚后，原文档的内容、文档块、关键词索引和向量保持不变
+        let reimport = create_running_job(&state, "notes.md", &source).await;
+        assert_eq!(reimport.document_id, first.document_id);
+        let staged = stage_embedded(&state, &reimport, "alpha\n\ndelta\n\nepsilon", Some(&path)).await;
+        cancel_job(&state, &reimport.id).await.unwrap();
+        assert!(matches!(commit_document(&state, &reimport, &staged, EMBEDDING_MODEL).await, Err(JobError::Cancelled)));
+
+        let content: String = sqlx::query_scalar("SELECT content FROM documents WHERE id = ?")
+            .bind(&first.document_id)
+            .fetch_one(state.db.pool())
+            .await
+            .unwrap();
+        assert_eq!(content, "alpha\n\nbeta");
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM chunks WHERE document_id = ?", &first.document_id).await, 2);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM chunk_fts_map WHERE chunk_id IN (SELECT id FROM chunks WHERE document_id = ?)", &first.document_id).await, 2);
+        let indexed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM chunks_fts")
+            .fetch_one(state.db.pool())
+            .await
+            .unwrap();
+        assert_eq!(indexed, 2);
+        assert_eq!(vector_store.document_len(&first.document_id), 2);
+        assert_eq!(vector_store.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_job_keeps_document_id() {
+        let (state, data_dir) = test_state().await;
+        let source = JobSource::File { path: data_dir.join("notes.md").to_string_lossy().to_string() };
+
+        // 文件在任务执行期间再次变化：新任务写入同一文档，不会产生第二个文档
+        let running = create_running_job(&state, "notes.md", &source).await;
+        let queued = create_job(&state, DEFAULT_KNOWLEDGE_BASE_ID, "notes.md", &source, None, "queued").await.unwrap();
+        assert_eq!(queued.document_id, running.document_id);
+    }
+
+    #[tokio::test]
+    async fn test_reimport_unchanged_and_duplicate() {
+        let (state, data_dir) = test_state().await;
+        let path = data_dir.join("notes.md").to_string_lossy().to_string();
+        let source = JobSource::File { path: path.clone() };
+        let vector_store = state.vector_store(DEFAULT_KNOWLEDGE_BASE_ID);
+
+        let first = create_running_job(&state, "notes.md", &source).await;
+        let document = stage_embedded(&state, &first, "alpha", Some(&path)).await;
+        commit_document(&state, &first, &document, EMBEDDING_MODEL).await.unwrap();
+
+        let other = create_running_job(&state, "other", &text_source("beta")).await;
+        let document = stage_embedded(&state, &other, "beta", None).await;
+        commit_document(&state, &other, &document, EMBEDDING_MODEL).await.unwrap();
+
+        // 内容未变化：匹配原文档本身，原文档保留
+        let unchanged = create_running_job(&state, "notes.md", &source).await;
+        let duplicate = find_duplicate(&state, &unchanged, "alpha").await.unwrap();
+        assert_eq!(duplicate.as_deref(), Some(first.document_id.as_str()));
+        complete_duplicate(&state, &unchanged, &first.document_id).await;
+
+        let job = load_job(&state, &unchanged.id).await.unwrap().unwrap();
+        assert_eq!((job.status.as_str(), job.stage.as_str()), ("completed", "unchanged"));
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM documents WHERE id = ?", &first.document_id).await, 1);
+
+        // 修改后与另一文档内容相同：任务指向那个文档，删除内容已过期的原文档
+        let changed = create_running_job(&state, "notes.md", &source).await;
+        let duplicate = find_duplicate(&state, &changed, "beta").await.unwrap();
+        assert_eq!(duplicate.as_deref(), Some(other.document_id.as_str()));
+        complete_duplicate(&state, &changed, &other.document_id).await;
+
+        let job = load_job(&state, &changed.id).await.unwrap().unwrap();
+        assert_eq!((job.status.as_str(), job.stage.as_str()), ("completed", "duplicate"));
+        assert_eq!(job.document_id, other.document_id);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM documents WHERE id = ?", &first.document_id).await, 0);
+        assert_eq!(count(&state, "SELECT COUNT(*) FROM documents WHERE id = ?", &other.document_id).await, 1);
+        assert_eq!(vector_store.document_len(&first.document_id), 0);
+        assert_eq!(vector_store.document_len(&other.document_id), 1);
+    }
+}
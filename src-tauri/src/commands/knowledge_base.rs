@@ -0,0 +1,196 @@
+use crate::app_state::AppState;
+use crate::db::models::KnowledgeBase;
+use crate::rag::knowledge_base::{KnowledgeBaseSettings, DEFAULT_KNOWLEDGE_BASE_ID};
+use tauri::State;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct SaveKnowledgeBaseRequest {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    /// 覆盖全局 RAG 配置的分块和 Embedding 设置
+    #[serde(default)]
+    settings: KnowledgeBaseSettings,
+}
+
+/// 知识库及其文档数
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct KnowledgeBaseSummary {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    knowledge_base: KnowledgeBase,
+    document_count: i64,
+}
+
+/// 创建知识库
+#[tauri::command]
+pub async fn create_knowledge_base(
+    request: SaveKnowledgeBaseRequest,
+    state: State<'_, AppState>,
+) -> Result<KnowledgeBase, String> {
+    let name = validate_name(&state, &request.name, None).await?;
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let knowledge_base = KnowledgeBase {
+        id: Uuid::new_v4().to_string(),
+        name,
+        description: request.description.filter(|d| !d.trim().is_empty()),
+        settings: serde_json::to_string(&request.settings).map_err(|e| e.to_string())?,
+        created_at: timestamp,
+        updated_at: timestamp,
+    };
+
+    sqlx::query(
+        "INSERT INTO knowledge_bases (id, name, description, settings, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&knowledge_base.id)
+    .bind(&knowledge_base.name)
+    .bind(&knowledge_base.description)
+    .bind(&knowledge_base.settings)
+    .bind(knowledge_base.created_at)
+    .bind(knowledge_base.updated_at)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    println!("📚 创建知识库: {}", knowledge_base.name);
+    Ok(knowledge_base)
+}
+
+/// 获取所有知识库（默认知识库在最前）
+#[tauri::command]
+pub async fn get_knowledge_bases(
+    state: State<'_, AppState>,
+) -> Result<Vec<KnowledgeBaseSummary>, String> {
+    sqlx::query_as::<_, KnowledgeBaseSummary>(
+        "SELECT k.*, (SELECT COUNT(*) FROM documents d WHERE d.knowledge_base_id = k.id) AS document_count
+         FROM knowledge_bases k
+         ORDER BY k.id = ? DESC, k.created_at"
+    )
+    .bind(DEFAULT_KNOWLEDGE_BASE_ID)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 修改知识库名称、描述和设置
+///
+/// 已有文档的向量由原来的 Embedding 模型生成，此时不能修改 Embedding 设置；
+/// 分块设置只影响之后导入的文档。
+#[tauri::command]
+pub async fn update_knowledge_base(
+    knowledge_base_id: String,
+    request: SaveKnowledgeBaseRequest,
+    state: State<'_, AppState>,
+) -> Result<KnowledgeBase, String> {
+    let previous = state.knowledge_base_settings(&knowledge_base_id).await?;
+    let name = validate_name(&state, &request.name, Some(&knowledge_base_id)).await?;
+
+    if previous.embedding_settings() != request.settings.embedding_settings() {
+        let document_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE knowledge_base_id = ?")
+            .bind(&knowledge_base_id)
+            .fetch_one(state.db.pool())
+            .await
+            .map_err(|e| e.to_string())?;
+        if document_count > 0 {
+            return Err("知识库中已有文档，不能修改 Embedding 设置".to_string());
+        }
+    }
+
+    sqlx::query(
+        "UPDATE knowledge_bases SET name = ?, description = ?, settings = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(&name)
+    .bind(request.description.filter(|d| !d.trim().is_empty()))
+    .bind(serde_json::to_string(&request.settings).map_err(|e| e.to_string())?)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(&knowledge_base_id)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    state.invalidate_knowledge_base(&knowledge_base_id);
+
+    sqlx::query_as::<_, KnowledgeBase>("SELECT * FROM knowledge_bases WHERE id = ?")
+        .bind(&knowledge_base_id)
+        .fetch_one(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 删除知识库及其全部文档、导入任务和监听目录（默认知识库不能删除）
+#[tauri::command]
+pub async fn delete_knowledge_base(
+    knowledge_base_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    if knowledge_base_id == DEFAULT_KNOWLEDGE_BASE_ID {
+        return Err("默认知识库不能删除".to_string());
+    }
+
+    let name = sqlx::query_scalar::<_, String>("SELECT name FROM knowledge_bases WHERE id = ?")
+        .bind(&knowledge_base_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "知识库不存在".to_string())?;
+
+    // 停止监听该知识库的目录
+    let folder_ids = sqlx::query_scalar::<_, String>("SELECT id FROM watched_folders WHERE knowledge_base_id = ?")
+        .bind(&knowledge_base_id)
+        .fetch_all(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+    {
+        let mut watchers = state.folder_watchers.lock().unwrap();
+        for folder_id in &folder_ids {
+            watchers.remove(folder_id);
+        }
+    }
+
+    // 文档块、关键词索引和暂存数据随之级联删除，正在执行的导入任务会在下一个检查点停止
+    let mut tx = state.db.pool().begin().await
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+    for table in ["watched_folders", "ingestion_jobs", "documents"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE knowledge_base_id = ?", table))
+            .bind(&knowledge_base_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    sqlx::query("DELETE FROM knowledge_bases WHERE id = ?")
+        .bind(&knowledge_base_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await
+        .map_err(|e| format!("提交事务失败: {}", e))?;
+
+    state.remove_vector_store(&knowledge_base_id);
+
+    println!("🗑️ 删除知识库: {}", name);
+    Ok(true)
+}
+
+/// 校验知识库名称非空且不与其他知识库重名，返回去除首尾空白后的名称
+async fn validate_name(state: &AppState, name: &str, knowledge_base_id: Option<&str>) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("知识库名称不能为空".to_string());
+    }
+
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM knowledge_bases WHERE name = ? AND id IS NOT ?"
+    )
+    .bind(name)
+    .bind(knowledge_base_id)
+    .fetch_one(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    if existing > 0 {
+        return Err(format!("已存在名为「{}」的知识库", name));
+    }
+
+    Ok(name.to_string())
+}
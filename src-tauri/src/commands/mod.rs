@@ -2,4 +2,8 @@ pub mod document;
 pub mod chat;
 pub mod config;
 pub mod file;
+pub mod import;
 
+pub mod ingestion;
+pub mod knowledge_base;
+pub mod watch;
@@ -0,0 +1,536 @@
+use crate::app_state::AppState;
+use crate::commands::document::{file_name, remove_document};
+use crate::commands::file::is_supported_extension;
+use crate::commands::import::{build_glob_set, relative_path, scan_folder};
+use crate::commands::ingestion::{self, parse_strategy, strategy_name, JobSource};
+use crate::db::models::WatchedFolder;
+use crate::hash::file_hash;
+use crate::rag::knowledge_base::DEFAULT_KNOWLEDGE_BASE_ID;
+use crate::rag::ChunkStrategy;
+use globset::GlobSet;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// 最后一次文件变化后的静默时间，期间的连续变化合并为一次同步
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+/// 持续变化时最长等待时间，超过后先同步已收集的变化
+const MAX_DEBOUNCE: Duration = Duration::from_secs(30);
+
+#[derive(serde::Deserialize)]
+pub struct AddWatchedFolderRequest {
+    path: String,
+    /// 只导入匹配的文件（相对监听目录的通配符），为空时导入全部
+    #[serde(default)]
+    include: Vec<String>,
+    /// 排除匹配的文件或目录
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// 该目录使用的分块策略，为空时使用 RAG 配置中的默认策略
+    #[serde(default)]
+    chunk_strategy: Option<ChunkStrategy>,
+    /// 导入到的知识库，为空时导入默认知识库
+    #[serde(default)]
+    knowledge_base_id: Option<String>,
+}
+
+/// 一次同步的结果
+#[derive(Debug, Default, serde::Serialize)]
+pub struct FolderSyncSummary {
+    /// 新增或修改、已加入导入队列的文件数
+    queued: usize,
+    /// 文件已删除、随之删除的文档数
+    removed: usize,
+    /// 同步失败的文件及原因（不影响其他文件的同步）
+    errors: Vec<String>,
+}
+
+impl FolderSyncSummary {
+    /// 记录单个文件同步失败，继续同步其余文件
+    fn record_error(&mut self, path: &Path, error: String) {
+        println!("⚠️ 同步文件失败 {}: {}", path.display(), error);
+        self.errors.push(format!("{}: {}", path.display(), error));
+    }
+}
+
+/// 监听目录的过滤规则（与导入文件夹相同：跳过隐藏文件和目录，再按 include / exclude 过滤）
+struct FolderFilter {
+    root: PathBuf,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl FolderFilter {
+    fn new(folder: &WatchedFolder) -> Result<Self, String> {
+        let patterns = |json: &str| serde_json::from_str::<Vec<String>>(json).unwrap_or_default();
+
+        Ok(Self {
+            root: PathBuf::from(&folder.path),
+            include: build_glob_set(&patterns(&folder.include))?,
+            exclude: build_glob_set(&patterns(&folder.exclude))?,
+        })
+    }
+
+    /// 文件是否需要导入：不在隐藏或被排除的目录下，且格式受支持
+    fn matches(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut ancestor = self.root.clone();
+        for component in relative.components() {
+            ancestor.push(component);
+            let hidden = component.as_os_str().to_string_lossy().starts_with('.');
+            let excluded = self.exclude.as_ref()
+                .is_some_and(|set| set.is_match(relative_path(&self.root, &ancestor)));
+            if hidden || excluded {
+                return false;
+            }
+        }
+
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let included = self.include.as_ref()
+            .is_none_or(|set| set.is_match(relative_path(&self.root, path)));
+        included && is_supported_extension(&extension)
+    }
+}
+
+/// 添加监听目录：立即开始监听，并在后台导入目录中已有的文件
+#[tauri::command]
+pub async fn add_watched_folder(
+    request: AddWatchedFolderRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<WatchedFolder, String> {
+    if !Path::new(&request.path).is_dir() {
+        return Err("文件夹不存在".to_string());
+    }
+
+    let knowledge_base_id = request.knowledge_base_id
+        .unwrap_or_else(|| DEFAULT_KNOWLEDGE_BASE_ID.to_string());
+    state.knowledge_base_settings(&knowledge_base_id).await?;
+
+    let folder = WatchedFolder {
+        id: Uuid::new_v4().to_string(),
+        knowledge_base_id,
+        path: request.path,
+        include: serde_json::to_string(&request.include).map_err(|e| e.to_string())?,
+        exclude: serde_json::to_string(&request.exclude).map_err(|e| e.to_string())?,
+        chunk_strategy: request.chunk_strategy.and_then(strategy_name),
+        created_at: chrono::Utc::now().timestamp(),
+        last_synced_at: None,
+    };
+    // 先校验匹配规则
+    FolderFilter::new(&folder)?;
+
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM watched_folders WHERE knowledge_base_id = ? AND path = ?"
+    )
+    .bind(&folder.knowledge_base_id)
+    .bind(&folder.path)
+    .fetch_one(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    if existing > 0 {
+        return Err("该文件夹已在监听中".to_string());
+    }
+
+    sqlx::query(
+        "INSERT INTO watched_folders (id, knowledge_base_id, path, include, exclude, chunk_strategy, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&folder.id)
+    .bind(&folder.knowledge_base_id)
+    .bind(&folder.path)
+    .bind(&folder.include)
+    .bind(&folder.exclude)
+    .bind(&folder.chunk_strategy)
+    .bind(folder.created_at)
+    .execute(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    start_watching(&app, &state, folder.clone())?;
+    println!("👀 开始监听文件夹: {}", folder.path);
+
+    let initial = folder.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        if let Err(e) = sync_folder(&state, &initial).await {
+            println!("⚠️ 同步监听文件夹失败 {}: {}", initial.path, e);
+        }
+    });
+
+    Ok(folder)
+}
+
+/// 获取所有监听目录
+#[tauri::command]
+pub async fn get_watched_folders(
+    state: State<'_, AppState>,
+) -> Result<Vec<WatchedFolder>, String> {
+    sqlx::query_as::<_, WatchedFolder>("SELECT * FROM watched_folders ORDER BY created_at")
+        .fetch_all(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 移除监听目录，可同时删除从该目录导入的文档
+#[tauri::command]
+pub async fn remove_watched_folder(
+    folder_id: String,
+    remove_documents: bool,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let folder = load_folder(&state, &folder_id).await?;
+
+    // 丢弃监听器即停止监听
+    state.folder_watchers.lock().unwrap().remove(&folder_id);
+
+    sqlx::query("DELETE FROM watched_folders WHERE id = ?")
+        .bind(&folder_id)
+        .execute(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if remove_documents {
+        let removed = remove_documents_under(&state, &folder.knowledge_base_id, Path::new(&folder.path)).await?;
+        if removed > 0 {
+            state.persist_vector_index(&folder.knowledge_base_id);
+        }
+    }
+
+    println!("🛑 停止监听文件夹: {}", folder.path);
+    Ok(true)
+}
+
+/// 立即完整同步一个监听目录
+#[tauri::command]
+pub async fn sync_watched_folder(
+    folder_id: String,
+    state: State<'_, AppState>,
+) -> Result<FolderSyncSummary, String> {
+    let folder = load_folder(&state, &folder_id).await?;
+    sync_folder(&state, &folder).await
+}
+
+/// 启动时恢复所有监听目录，并同步应用关闭期间发生的变化
+pub fn spawn_watchers(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let folders = match sqlx::query_as::<_, WatchedFolder>("SELECT * FROM watched_folders ORDER BY created_at")
+            .fetch_all(state.db.pool())
+            .await
+        {
+            Ok(folders) => folders,
+            Err(e) => {
+                println!("⚠️ 读取监听文件夹失败: {}", e);
+                return;
+            }
+        };
+
+        for folder in folders {
+            if let Err(e) = start_watching(&app, &state, folder.clone()) {
+                println!("⚠️ 监听文件夹失败 {}: {}", folder.path, e);
+                continue;
+            }
+            if let Err(e) = sync_folder(&state, &folder).await {
+                println!("⚠️ 同步监听文件夹失败 {}: {}", folder.path, e);
+            }
+        }
+    });
+}
+
+async fn load_folder(state: &AppState, folder_id: &str) -> Result<WatchedFolder, String> {
+    sqlx::query_as::<_, WatchedFolder>("SELECT * FROM watched_folders WHERE id = ?")
+        .bind(folder_id)
+        .fetch_optional(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "监听文件夹不存在".to_string())
+}
+
+/// 注册文件监听器，变化经去抖后逐个同步
+fn start_watching(app: &AppHandle, state: &AppState, folder: WatchedFolder) -> Result<(), String> {
+    let filter = FolderFilter::new(&folder)?;
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PathBuf>();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        match event {
+            Ok(event) if !event.kind.is_access() => {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => println!("⚠️ 文件监听出错: {}", e),
+        }
+    })
+    .map_err(|e| format!("创建文件监听失败: {}", e))?;
+    watcher.watch(Path::new(&folder.path), RecursiveMode::Recursive)
+        .map_err(|e| format!("监听文件夹失败: {}", e))?;
+
+    // 监听器被丢弃后通道关闭，任务随之结束
+    let app = app.clone();
+    let folder_id = folder.id.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+
+        while let Some(path) = receiver.recv().await {
+            let mut changed = HashSet::from([path]);
+            let deadline = Instant::now() + MAX_DEBOUNCE;
+            loop {
+                let wait = DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+                match tokio::time::timeout(wait, receiver.recv()).await {
+                    Ok(Some(path)) => {
+                        changed.insert(path);
+                    }
+                    _ => break,
+                }
+            }
+
+            let mut summary = FolderSyncSummary::default();
+            for path in &changed {
+                if let Err(e) = sync_path(&state, &folder, &filter, path, &mut summary).await {
+                    summary.record_error(path, e);
+                }
+            }
+            if summary.removed > 0 {
+                state.persist_vector_index(&folder.knowledge_base_id);
+            }
+            if summary.queued > 0 || summary.removed > 0 {
+                println!(
+                    "🔄 监听文件夹变化 ({}): 重新导入 {} 个文件，删除 {} 个文档",
+                    folder.path, summary.queued, summary.removed
+                );
+            }
+        }
+    });
+
+    state.folder_watchers.lock().unwrap().insert(folder_id, watcher);
+    Ok(())
+}
+
+/// 完整同步监听目录：导入新增或修改的文件，删除文件已不存在的文档
+async fn sync_folder(state: &AppState, folder: &WatchedFolder) -> Result<FolderSyncSummary, String> {
+    let filter = FolderFilter::new(folder)?;
+    let root = filter.root.clone();
+    let (include, exclude) = (filter.include.clone(), filter.exclude.clone());
+    let files = tokio::task::spawn_blocking(move || scan_folder(&root, include.as_ref(), exclude.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 单个文件失败时记录原因并继续，删除和同步时间的更新照常进行
+    let mut summary = FolderSyncSummary::default();
+    for (path, _) in &files {
+        if filter.matches(path) {
+            if let Err(e) = enqueue_file(state, folder, path, &mut summary).await {
+                summary.record_error(path, e);
+            }
+        }
+    }
+
+    // 从该目录导入、但文件已被删除的文档
+    let documents = documents_under(state, &folder.knowledge_base_id, Path::new(&folder.path)).await?;
+    for (document_id, source_path) in documents {
+        let source_path = Path::new(&source_path);
+        if !source_path.exists() {
+            match remove_document(state, &document_id).await {
+                Ok(_) => summary.removed += 1,
+                Err(e) => summary.record_error(source_path, e),
+            }
+        }
+    }
+    if summary.removed > 0 {
+        state.persist_vector_index(&folder.knowledge_base_id);
+    }
+
+    sqlx::query("UPDATE watched_folders SET last_synced_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().timestamp())
+        .bind(&folder.id)
+        .execute(state.db.pool())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "🔄 监听文件夹同步完成 ({}): 导入 {} 个文件，删除 {} 个文档，失败 {} 个",
+        folder.path, summary.queued, summary.removed, summary.errors.len()
+    );
+    Ok(summary)
+}
+
+/// 同步单个变化的路径：文件加入导入队列，新出现的目录整体扫描，已删除的路径删除对应文档
+async fn sync_path(
+    state: &AppState,
+    folder: &WatchedFolder,
+    filter: &FolderFilter,
+    path: &Path,
+    summary: &mut FolderSyncSummary,
+) -> Result<(), String> {
+    if path.is_file() {
+        if filter.matches(path) {
+            enqueue_file(state, folder, path, summary).await?;
+        }
+    } else if path.is_dir() {
+        // 移入的目录只产生一个事件，需要扫描其中的文件
+        let root = path.to_path_buf();
+        let files = tokio::task::spawn_blocking(move || scan_folder(&root, None, None))
+            .await
+            .map_err(|e| e.to_string())?;
+        for (file, _) in &files {
+            if filter.matches(file) {
+                if let Err(e) = enqueue_file(state, folder, file, summary).await {
+                    summary.record_error(file, e);
+                }
+            }
+        }
+    } else if !path.exists() {
+        summary.removed += remove_documents_under(state, &folder.knowledge_base_id, path).await?;
+    }
+
+    Ok(())
+}
+
+/// 将新增或修改的文件加入导入队列（内容未变化或已在队列中时跳过）
+async fn enqueue_file(
+    state: &AppState,
+    folder: &WatchedFolder,
+    path: &Path,
+    summary: &mut FolderSyncSummary,
+) -> Result<(), String> {
+    let file_path = path.to_string_lossy().to_string();
+
+    // 排队中的任务执行时会读取最新的文件内容；正在执行的任务可能已读取旧内容，
+    // 此时仍加入队列，新任务沿用其文档 ID，完成后替换为最新内容
+    let queued = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM ingestion_jobs
+         WHERE status = 'queued' AND knowledge_base_id = ? AND json_extract(source, '$.path') = ?"
+    )
+    .bind(&folder.knowledge_base_id)
+    .bind(&file_path)
+    .fetch_one(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?;
+    if queued > 0 {
+        return Ok(());
+    }
+
+    let stored_hash = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT json_extract(metadata, '$.file_hash') FROM documents
+         WHERE knowledge_base_id = ? AND json_extract(metadata, '$.source_path') = ?"
+    )
+    .bind(&folder.knowledge_base_id)
+    .bind(&file_path)
+    .fetch_optional(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())?
+    .flatten();
+    if stored_hash.is_some() && stored_hash == Some(file_hash(path).await?) {
+        return Ok(());
+    }
+
+    if !state.is_rag_initialized() {
+        return Err("请先配置 API Key".to_string());
+    }
+
+    let source = JobSource::File { path: file_path.clone() };
+    let chunk_strategy = folder.chunk_strategy.as_deref().and_then(parse_strategy);
+    ingestion::enqueue_job(state, &folder.knowledge_base_id, &file_name(&file_path), &source, chunk_strategy).await?;
+    summary.queued += 1;
+
+    Ok(())
+}
+
+/// 知识库中从指定文件或目录导入的文档，返回文档 ID 和原文件路径
+async fn documents_under(state: &AppState, knowledge_base_id: &str, path: &Path) -> Result<Vec<(String, String)>, String> {
+    let path = path.to_string_lossy().to_string();
+    let prefix = format!("{}{}", path.trim_end_matches(std::path::MAIN_SEPARATOR), std::path::MAIN_SEPARATOR);
+
+    sqlx::query_as::<_, (String, String)>(
+        "SELECT id, source_path FROM (
+             SELECT id, json_extract(metadata, '$.source_path') AS source_path FROM documents
+             WHERE knowledge_base_id = ?
+         )
+         WHERE source_path = ? OR substr(source_path, 1, length(?)) = ?"
+    )
+    .bind(knowledge_base_id)
+    .bind(&path)
+    .bind(&prefix)
+    .bind(&prefix)
+    .fetch_all(state.db.pool())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 删除知识库中从指定文件或目录导入的文档，返回删除数量
+async fn remove_documents_under(state: &AppState, knowledge_base_id: &str, path: &Path) -> Result<usize, String> {
+    let documents = documents_under(state, knowledge_base_id, path).await?;
+    for (document_id, source_path) in &documents {
+        println!("🗑️ 删除文档: {}", source_path);
+        remove_document(state, document_id).await?;
+    }
+
+    Ok(documents.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::test_state;
+
+    #[tokio::test]
+    async fn test_sync_folder_continues_after_file_errors() {
+        let (state, data_dir) = test_state().await;
+
+        let root = data_dir.join("notes");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.md"), "alpha").unwrap();
+        std::fs::write(root.join("b.md"), "beta").unwrap();
+
+        let folder = WatchedFolder {
+            id: Uuid::new_v4().to_string(),
+            path: root.to_string_lossy().to_string(),
+            include: "[]".to_string(),
+            exclude: "[]".to_string(),
+            chunk_strategy: None,
+            created_at: 0,
+            last_synced_at: None,
+            knowledge_base_id: DEFAULT_KNOWLEDGE_BASE_ID.to_string(),
+        };
+        sqlx::query("INSERT INTO watched_folders (id, knowledge_base_id, path, include, exclude, created_at) VALUES (?, ?, ?, '[]', '[]', 0)")
+            .bind(&folder.id)
+            .bind(&folder.knowledge_base_id)
+            .bind(&folder.path)
+            .execute(state.db.pool())
+            .await
+            .unwrap();
+
+        // 文件已被删除的文档
+        let deleted = root.join("deleted.md").to_string_lossy().to_string();
+        sqlx::query("INSERT INTO documents (id, name, content, created_at, updated_at, metadata) VALUES ('stale', 'deleted.md', '', 0, 0, ?)")
+            .bind(serde_json::json!({ "source_path": deleted }).to_string())
+            .execute(state.db.pool())
+            .await
+            .unwrap();
+
+        // 未配置 API Key 时每个文件都无法加入队列，删除和同步时间照常更新
+        let summary = sync_folder(&state, &folder).await.unwrap();
+        assert_eq!(summary.queued, 0);
+        assert_eq!(summary.errors.len(), 2);
+        assert_eq!(summary.removed, 1);
+
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM documents")
+            .fetch_one(state.db.pool())
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+        let synced = load_folder(&state, &folder.id).await.unwrap();
+        assert!(synced.last_synced_at.is_some());
+    }
+}
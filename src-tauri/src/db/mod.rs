@@ -5,6 +5,27 @@ use std::str::FromStr;
 
 pub mod models;
 
+/// 数据库迁移脚本（按版本号顺序执行，版本记录在 PRAGMA user_version 中）
+const MIGRATIONS: &[(i64, &str)] = &[
+    (1, include_str!("../../migrations/001_initial.sql")),
+    (2, include_str!("../../migrations/002_chunk_embeddings.sql")),
+    (3, include_str!("../../migrations/003_chunks_fts.sql")),
+    (4, include_str!("../../migrations/004_chunk_offsets.sql")),
+    (5, include_str!("../../migrations/005_document_metadata.sql")),
+    (6, include_str!("../../migrations/006_chunk_sections.sql")),
+    (7, include_str!("../../migrations/007_chunk_pages.sql")),
+    (8, include_str!("../../migrations/008_chunk_symbols.sql")),
+    (9, include_str!("../../migrations/009_ingestion_jobs.sql")),
+    (10, include_str!("../../migrations/010_ingestion_staging.sql")),
+    (11, include_str!("../../migrations/011_content_hashes.sql")),
+    (12, include_str!("../../migrations/012_watched_folders.sql")),
+    (13, include_str!("../../migrations/013_knowledge_bases.sql")),
+    (14, include_str!("../../migrations/014_chunks_fts_chunk_id.sql")),
+    (15, include_str!("../../migrations/015_document_embedding_model.sql")),
+    (16, include_str!("../../migrations/016_chunk_fts_map.sql")),
+    (17, include_str!("../../migrations/017_watched_folders_per_knowledge_base.sql")),
+];
+
 /// 数据库连接池
 pub struct Database {
     pool: SqlitePool,
@@ -29,16 +50,153 @@ impl Database {
             .await?;
         
         // 运行迁移
-        sqlx::query(include_str!("../../migrations/001_initial.sql"))
-            .execute(&pool)
-            .await?;
+        Self::run_migrations(&pool).await?;
         
         Ok(Self { pool })
     }
     
+    /// 执行尚未应用的迁移
+    async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+        let current_version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(pool)
+            .await?;
+        
+        for (version, sql) in MIGRATIONS {
+            if *version <= current_version {
+                continue;
+            }
+            
+            // 每个迁移在独立事务中执行，失败时不会留下半完成的表结构
+            let mut tx = pool.begin().await?;
+            sqlx::query(sql).execute(&mut *tx).await?;
+            sqlx::query(&format!("PRAGMA user_version = {}", version))
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            
+            println!("🗄️ 数据库迁移完成: v{}", version);
+        }
+        
+        Ok(())
+    }
+    
     /// 获取连接池引用
     pub fn pool(&self) -> &SqlitePool {
         &self.pool
     }
 }
 
+/// 测试用的临时数据目录，离开作用域时删除（断言失败时也会清理）
+#[cfg(test)]
+pub(crate) struct TempDataDir(PathBuf);
+
+#[cfg(test)]
+impl TempDataDir {
+    pub(crate) fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("wali-test-{}", uuid::Uuid::new_v4())))
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDataDir {
+    type Target = PathBuf;
+    
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDataDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_migrations_are_idempotent() {
+        let data_dir = TempDataDir::new();
+        
+        // 重复打开同一数据库，迁移只会执行一次
+        for _ in 0..2 {
+            let db = Database::new(&data_dir).await.unwrap();
+            let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+            assert_eq!(version, MIGRATIONS.last().unwrap().0);
+            db.pool().close().await;
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_keyword_index_survives_vacuum() {
+        let data_dir = TempDataDir::new();
+        let db = Database::new(&data_dir).await.unwrap();
+        let pool = db.pool();
+        
+        sqlx::query("INSERT INTO documents (id, name, content, created_at, updated_at) VALUES ('doc', 'doc', '', 0, 0)")
+            .execute(pool)
+            .await
+            .unwrap();
+        for (id, terms) in [("a", "alpha"), ("b", "beta"), ("c", "gamma")] {
+            sqlx::query("INSERT INTO chunks (id, document_id, content, chunk_index, created_at) VALUES (?, 'doc', ?, 0, 0)")
+                .bind(id)
+                .bind(terms)
+                .execute(pool)
+                .await
+                .unwrap();
+            let mut conn = pool.acquire().await.unwrap();
+            crate::rag::keyword::index_chunk(&mut conn, id, terms).await.unwrap();
+        }
+        
+        // 删除中间的块后 VACUUM 会重新编号 chunks 的 rowid，索引仍按映射表对应
+        sqlx::query("DELETE FROM chunks WHERE id = 'a'").execute(pool).await.unwrap();
+        sqlx::query("VACUUM").execute(pool).await.unwrap();
+        sqlx::query("DELETE FROM chunks WHERE id = 'b'").execute(pool).await.unwrap();
+        
+        let search = |term: &'static str| async move {
+            sqlx::query_scalar::<_, String>(
+                "SELECT c.id FROM chunks_fts
+                 JOIN chunk_fts_map m ON m.fts_rowid = chunks_fts.rowid
+                 JOIN chunks c ON c.id = m.chunk_id
+                 WHERE chunks_fts MATCH ?"
+            )
+            .bind(term)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+        };
+        assert!(search("alpha").await.is_empty());
+        assert!(search("beta").await.is_empty());
+        assert_eq!(search("gamma").await, vec!["c".to_string()]);
+        
+        // 触发器同时清理了映射表和索引
+        let indexed: (i64, i64) = sqlx::query_as("SELECT (SELECT COUNT(*) FROM chunk_fts_map), (SELECT COUNT(*) FROM chunks_fts)")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(indexed, (1, 1));
+    }
+    
+    #[tokio::test]
+    async fn test_watched_folder_unique_per_knowledge_base() {
+        let data_dir = TempDataDir::new();
+        let db = Database::new(&data_dir).await.unwrap();
+        
+        let insert = |id: &'static str, knowledge_base_id: &'static str| {
+            sqlx::query("INSERT INTO watched_folders (id, knowledge_base_id, path, created_at) VALUES (?, ?, '/notes', 0)")
+                .bind(id)
+                .bind(knowledge_base_id)
+                .execute(db.pool())
+        };
+        // 同一目录可以被不同知识库监听，同一知识库内不能重复
+        insert("a", "default").await.unwrap();
+        insert("b", "other").await.unwrap();
+        assert!(insert("c", "default").await.is_err());
+    }
+}
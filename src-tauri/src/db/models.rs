@@ -10,6 +10,10 @@ pub struct Document {
     pub file_size: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub metadata: Option<String>, // JSON
+    pub content_hash: Option<String>, // 正文的 SHA-256
+    pub embedding_model: Option<String>, // 生成向量的 Embedding 服务标识
+    pub knowledge_base_id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -19,6 +23,55 @@ pub struct Chunk {
     pub content: String,
     pub chunk_index: i64,
     pub created_at: i64,
+    #[serde(skip)]
+    pub embedding: Option<Vec<u8>>, // 小端序 f32 数组
+    pub start_offset: Option<i64>, // 原文字符偏移（旧数据为空）
+    pub end_offset: Option<i64>,
+    pub section: Option<String>, // 所属章节
+    pub page_start: Option<i64>, // 起止页码（仅分页文档）
+    pub page_end: Option<i64>,
+    pub symbol: Option<String>, // 代码块所属的符号
+    pub content_hash: Option<String>, // 块内容的 SHA-256（重新导入时复用未变化块的向量）
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IngestionJob {
+    pub id: String,
+    pub document_id: String,
+    pub name: String,
+    #[serde(skip)]
+    pub source: String, // JSON：文本内容或文件路径（可能很大，不返回前端）
+    pub chunk_strategy: Option<String>,
+    pub status: String, // "queued" | "running" | "completed" | "failed" | "cancelled"
+    pub stage: String,  // "extracting" | "embedding" | "indexing"，跳过时为 "duplicate" | "unchanged"
+    pub progress_done: i64,
+    pub progress_total: i64,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub knowledge_base_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WatchedFolder {
+    pub id: String,
+    pub path: String,
+    pub include: String, // JSON 数组
+    pub exclude: String, // JSON 数组
+    pub chunk_strategy: Option<String>,
+    pub created_at: i64,
+    pub last_synced_at: Option<i64>,
+    pub knowledge_base_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct KnowledgeBase {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub settings: String, // JSON：KnowledgeBaseSettings
+    pub created_at: i64,
+    pub updated_at: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
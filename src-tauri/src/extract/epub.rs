@@ -0,0 +1,384 @@
+//! EPUB 电子书文本提取
+//!
+//! EPUB 是包含 XHTML 章节的 ZIP 包：从 `META-INF/container.xml` 找到 OPF 包文件，
+//! 按 spine 顺序读取章节，章节标题取自目录（EPUB 3 导航文档或 EPUB 2 的 NCX），
+//! 并记录每一章在全文中的字符范围，分块时按章节切分。
+
+use anyhow::{Context, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+use super::html::extract_chapter;
+use super::office::{attr, read_part, require_part, resolve_part};
+use super::ExtractedDocument;
+use crate::rag::text_splitter::Section;
+
+/// 提取电子书正文、书名和章节划分
+pub fn extract_epub(path: &Path) -> Result<ExtractedDocument> {
+    let file = File::open(path).with_context(|| format!("无法打开文件 {}", path.display()))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .context("不是有效的 EPUB 文件（ZIP 结构损坏）")?;
+    epub_document(&mut archive)
+}
+
+/// OPF 清单中的一项
+struct ManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+/// OPF 包文件的内容
+#[derive(Default)]
+struct Package {
+    title: Option<String>,
+    author: Option<String>,
+    manifest: HashMap<String, ManifestItem>,
+    /// 按阅读顺序排列的清单 ID
+    spine: Vec<String>,
+    /// EPUB 2 目录（NCX）的清单 ID
+    toc: Option<String>,
+}
+
+impl Package {
+    /// 清单项在包内的路径
+    fn item_path(&self, base_dir: &str, id: &str) -> Option<String> {
+        self.manifest.get(id).map(|item| part_path(base_dir, &item.href))
+    }
+
+    /// EPUB 3 导航文档的清单 ID
+    fn nav_id(&self) -> Option<&str> {
+        self.manifest
+            .iter()
+            .find(|(_, item)| item.properties.split_whitespace().any(|p| p == "nav"))
+            .map(|(id, _)| id.as_str())
+    }
+}
+
+fn epub_document<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<ExtractedDocument> {
+    let container = require_part(archive, "META-INF/container.xml")?;
+    let opf_path = rootfile_path(&container)?;
+    let opf_dir = opf_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let package = parse_package(&require_part(archive, &opf_path)?)?;
+
+    let nav_id = package.nav_id();
+    let mut toc = HashMap::new();
+    if let Some(nav_path) = nav_id.and_then(|id| package.item_path(opf_dir, id)) {
+        if let Some(xhtml) = read_part(archive, &nav_path)? {
+            let nav_dir = nav_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            toc = nav_titles(&xhtml, nav_dir);
+        }
+    }
+    if toc.is_empty() {
+        if let Some(ncx_path) = package.toc.as_deref().and_then(|id| package.item_path(opf_dir, id)) {
+            if let Some(xml) = read_part(archive, &ncx_path)? {
+                let ncx_dir = ncx_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+                toc = ncx_titles(&xml, ncx_dir)?;
+            }
+        }
+    }
+
+    let mut extracted = ExtractedDocument::default();
+    let mut length = 0;
+
+    for id in &package.spine {
+        let Some(item) = package.manifest.get(id) else { continue };
+        if Some(id.as_str()) == nav_id || !item.media_type.contains("html") {
+            continue;
+        }
+
+        let path = part_path(opf_dir, &item.href);
+        let Some(xhtml) = read_part(archive, &path)? else { continue };
+        let chapter = extract_chapter(&xhtml);
+        if chapter.text.trim().is_empty() {
+            continue;
+        }
+
+        // 目录标题优先，其次章节内的第一个标题；都没有时视为上一章的延续
+        let title = toc
+            .get(&path)
+            .cloned()
+            .or_else(|| chapter.metadata.get("title").and_then(|t| t.as_str()).map(str::to_string))
+            .or_else(|| extracted.sections.last().map(|s: &Section| s.title.clone()))
+            .unwrap_or_else(|| format!("第 {} 部分", extracted.sections.len() + 1));
+
+        if !extracted.text.is_empty() {
+            extracted.text.push_str("\n\n");
+            length += 2;
+        }
+        let start = length;
+        extracted.text.push_str(&chapter.text);
+        length += chapter.text.chars().count();
+
+        // 同一章拆分在多个文件中时合并为一个章节
+        match extracted.sections.last_mut() {
+            Some(last) if last.title == title => last.end = length,
+            _ => extracted.sections.push(Section { title, start, end: length }),
+        }
+    }
+
+    if extracted.text.is_empty() {
+        anyhow::bail!("电子书中没有可提取的文本");
+    }
+
+    if let Some(title) = package.title {
+        extracted.metadata.insert("title".to_string(), title.into());
+    }
+    if let Some(author) = package.author {
+        extracted.metadata.insert("author".to_string(), author.into());
+    }
+
+    Ok(extracted)
+}
+
+/// 从 container.xml 读取 OPF 包文件路径
+fn rootfile_path(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rootfile" => {
+                if let Some(path) = attr(&e, b"full-path") {
+                    return Ok(path);
+                }
+            }
+            Event::Eof => anyhow::bail!("container.xml 中缺少 rootfile"),
+            _ => {}
+        }
+    }
+}
+
+/// 解析 OPF：书名、作者、清单和阅读顺序
+fn parse_package(xml: &str) -> Result<Package> {
+    let mut package = Package::default();
+    let mut reader = Reader::from_str(xml);
+    let mut in_metadata = false;
+    let mut field: Option<&str> = None;
+    let mut value = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"metadata" => in_metadata = true,
+                b"title" if in_metadata && package.title.is_none() => field = Some("title"),
+                b"creator" if in_metadata && package.author.is_none() => field = Some("author"),
+                b"spine" => package.toc = attr(&e, b"toc"),
+                _ => {}
+            },
+            Event::Text(t) if field.is_some() => value.push_str(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"metadata" => in_metadata = false,
+                b"title" | b"creator" => {
+                    let text = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                    match field.take() {
+                        Some("title") if !text.is_empty() => package.title = Some(text),
+                        Some("author") if !text.is_empty() => package.author = Some(text),
+                        _ => {}
+                    }
+                    value.clear();
+                }
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"item" => {
+                if let (Some(id), Some(href)) = (attr(&e, b"id"), attr(&e, b"href")) {
+                    package.manifest.insert(id, ManifestItem {
+                        href,
+                        media_type: attr(&e, b"media-type").unwrap_or_default(),
+                        properties: attr(&e, b"properties").unwrap_or_default(),
+                    });
+                }
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"itemref" => {
+                if let Some(idref) = attr(&e, b"idref") {
+                    package.spine.push(idref);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(package)
+}
+
+/// EPUB 3 导航文档中的目录：章节路径 -> 标题
+fn nav_titles(xhtml: &str, base_dir: &str) -> HashMap<String, String> {
+    let document = Html::parse_document(xhtml);
+    let nav_selector = Selector::parse("nav").expect("内置 CSS 选择器无效");
+    let link_selector = Selector::parse("a[href]").expect("内置 CSS 选择器无效");
+
+    // 优先使用 epub:type="toc" 的导航，其余（页码列表、地标）不是章节目录
+    let navs: Vec<_> = document.select(&nav_selector).collect();
+    let toc = navs
+        .iter()
+        .find(|nav| nav.value().attrs().any(|(name, value)| name.ends_with("type") && value == "toc"))
+        .or(navs.first());
+
+    let mut titles = HashMap::new();
+    if let Some(toc) = toc {
+        for link in toc.select(&link_selector) {
+            let title = link.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            if let Some(href) = link.value().attr("href").filter(|_| !title.is_empty()) {
+                // 同一文件内有多个锚点时，使用第一个（章标题）
+                titles.entry(part_path(base_dir, href)).or_insert(title);
+            }
+        }
+    }
+    titles
+}
+
+/// EPUB 2 NCX 目录：章节路径 -> 标题
+fn ncx_titles(xml: &str, base_dir: &str) -> Result<HashMap<String, String>> {
+    let mut titles = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut in_label = false;
+    let mut label = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"navLabel" => {
+                in_label = true;
+                label.clear();
+            }
+            Event::End(e) if e.local_name().as_ref() == b"navLabel" => in_label = false,
+            Event::Text(t) if in_label => label.push_str(&t.unescape()?),
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"content" => {
+                let title = label.split_whitespace().collect::<Vec<_>>().join(" ");
+                if let Some(src) = attr(&e, b"src").filter(|_| !title.is_empty()) {
+                    titles.entry(part_path(base_dir, &src)).or_insert(title);
+                }
+                label.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(titles)
+}
+
+/// 将清单或目录中的链接解析为包内路径（去掉锚点并解码百分号转义）
+fn part_path(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    resolve_part(base_dir, &percent_decode(href))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::package;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container xmlns="urn:oasis:names:tc:opendocument:xmlns:container" version="1.0">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    fn chapter(body: &str) -> String {
+        format!(r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><head><title>x</title></head><body>{}</body></html>"#, body)
+    }
+
+    #[test]
+    fn test_epub3_chapters_follow_spine_and_nav() {
+        let opf = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>深入 Rust</dc:title><dc:creator>张三</dc:creator>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="text/ch%201.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/ch2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2b" href="text/ch2b.xhtml" media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine><itemref idref="nav"/><itemref idref="c2"/><itemref idref="c2b"/><itemref idref="c1"/></spine>
+</package>"#;
+        let nav = chapter(r#"<nav epub:type="landmarks"><a href="text/ch2.xhtml">正文</a></nav>
+<nav epub:type="toc"><ol><li><a href="text/ch%201.xhtml">序言</a></li><li><a href="text/ch2.xhtml#start">第一章 所有权</a></li></ol></nav>"#);
+        let ch1 = chapter("<p>这是序言。</p>");
+        let ch2 = chapter(r#"<header><h1>所有权</h1></header><p>每个值都有一个所有者。</p>"#);
+        let ch2b = chapter("<p>借用规则。</p>");
+        let mut archive = package(&[
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", opf),
+            ("OEBPS/nav.xhtml", &nav),
+            ("OEBPS/text/ch 1.xhtml", &ch1),
+            ("OEBPS/text/ch2.xhtml", &ch2),
+            ("OEBPS/text/ch2b.xhtml", &ch2b),
+        ]);
+
+        let book = epub_document(&mut archive).unwrap();
+
+        assert_eq!(book.text, "# 所有权\n\n每个值都有一个所有者。\n\n借用规则。\n\n这是序言。");
+        assert_eq!(book.metadata["title"], "深入 Rust");
+        assert_eq!(book.metadata["author"], "张三");
+        let sections: Vec<(&str, String)> = book.sections.iter()
+            .map(|s| (s.title.as_str(), book.text.chars().skip(s.start).take(s.end - s.start).collect()))
+            .collect();
+        assert_eq!(sections, vec![
+            ("第一章 所有权", "# 所有权\n\n每个值都有一个所有者。\n\n借用规则。".to_string()),
+            ("序言", "这是序言。".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_epub2_ncx_titles() {
+        let opf = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+  <metadata><dc:title xmlns:dc="http://purl.org/dc/elements/1.1/">旧书</dc:title></metadata>
+  <manifest>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="a" href="a.html" media-type="application/xhtml+xml"/>
+    <item id="b" href="b.html" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine toc="ncx"><itemref idref="a"/><itemref idref="b"/></spine>
+</package>"#;
+        let ncx = r#"<?xml version="1.0"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/"><navMap>
+  <navPoint id="p1"><navLabel><text>开篇</text></navLabel><content src="a.html"/></navPoint>
+</navMap></ncx>"#;
+        let a = chapter("<p>第一段。</p>");
+        let b = chapter("<h2>结语</h2><p>最后。</p>");
+        let mut archive = package(&[
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", opf),
+            ("OEBPS/toc.ncx", ncx),
+            ("OEBPS/a.html", &a),
+            ("OEBPS/b.html", &b),
+        ]);
+
+        let book = epub_document(&mut archive).unwrap();
+        let titles: Vec<&str> = book.sections.iter().map(|s| s.title.as_str()).collect();
+
+        assert_eq!(titles, vec!["开篇", "结语"]);
+        assert_eq!(book.sections[1].start, "第一段。\n\n".chars().count());
+    }
+}
@@ -0,0 +1,687 @@
+//! HTML 与网页存档（MHTML）文本提取
+//!
+//! 去掉脚本、样式和导航、页脚、侧栏等模板内容，优先选取 `<main>` / `<article>`
+//! 作为正文，标题、列表、表格和代码块转换为 Markdown 风格的文本。
+//! 页面标题和 canonical 链接作为文档元数据返回。
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use encoding_rs::{Encoding, UTF_8};
+use scraper::node::Node;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+use super::{markdown_table, ExtractedDocument};
+
+/// 正文候选区域的最少字符数，过短时退回整个 body
+const MIN_MAIN_CONTENT_CHARS: usize = 200;
+
+/// 直接丢弃的元素（不含可读正文）
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object",
+    "form", "button", "input", "select", "textarea", "dialog",
+];
+
+/// 导航、侧栏等始终视为模板内容的元素
+const NAVIGATION_TAGS: &[&str] = &["nav", "aside", "menu"];
+
+/// 以 body 为正文时视为模板内容的元素（文章内的 header 通常包含标题）
+const LAYOUT_TAGS: &[&str] = &["header", "footer"];
+
+/// 表示模板内容的 ARIA role
+const BOILERPLATE_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary", "search", "menu"];
+
+/// class / id 中出现这些词时视为模板内容
+const BOILERPLATE_HINTS: &[&str] = &[
+    "nav", "navbar", "menu", "sidebar", "footer", "breadcrumb", "breadcrumbs", "cookie",
+    "banner", "advert", "ads", "share", "social", "related", "subscribe", "popup", "modal",
+    "comments", "toolbar", "pagination",
+];
+
+/// 提取 HTML 页面正文和元数据
+pub fn extract_html(html: &str) -> ExtractedDocument {
+    let document = Html::parse_document(html);
+    let mut extracted = ExtractedDocument::default();
+
+    if let Some(title) = page_title(&document) {
+        extracted.metadata.insert("title".to_string(), title.into());
+    }
+    if let Some(url) = canonical_url(&document) {
+        extracted.metadata.insert("canonical_url".to_string(), url.into());
+    }
+
+    let (root, root_is_body) = content_root(&document);
+    let mut renderer = Renderer { root_is_body };
+    extracted.text = renderer.render_blocks(root).join("\n\n");
+    extracted
+}
+
+/// 提取电子书章节（XHTML）正文
+///
+/// 章节文件本身就是正文，整个 body 参与渲染，章首的 `<header>` 不视为模板内容；
+/// 元数据中的 `title` 为章节内的第一个标题。
+pub fn extract_chapter(html: &str) -> ExtractedDocument {
+    let document = Html::parse_document(html);
+    let mut extracted = ExtractedDocument::default();
+
+    if let Some(heading) = document.select(&selector("h1, h2, h3")).map(element_text).find(|t| !t.is_empty()) {
+        extracted.metadata.insert("title".to_string(), heading.into());
+    }
+
+    let root = document.select(&selector("body")).next().unwrap_or_else(|| document.root_element());
+    let mut renderer = Renderer { root_is_body: false };
+    extracted.text = renderer.render_blocks(root).join("\n\n");
+    extracted
+}
+
+/// 从文件字节中提取 HTML（根据 BOM 和 `<meta charset>` 判断编码）
+pub fn extract_html_bytes(bytes: &[u8]) -> ExtractedDocument {
+    extract_html(&decode_html(bytes, None))
+}
+
+/// 提取 MHTML 网页存档中的主页面
+pub fn extract_mhtml(bytes: &[u8]) -> Result<ExtractedDocument> {
+    let (headers, body) = split_headers(bytes);
+    let content_type = headers.get("content-type").cloned().unwrap_or_default();
+    let boundary = header_param(&content_type, "boundary").context("不是有效的 MHTML 文件：缺少 boundary")?;
+
+    let part = multipart_parts(body, &boundary)
+        .into_iter()
+        .map(split_headers)
+        .find(|(headers, _)| {
+            headers.get("content-type")
+                .is_some_and(|t| t.to_ascii_lowercase().starts_with("text/html"))
+        })
+        .context("MHTML 文件中没有 HTML 页面")?;
+
+    let (part_headers, part_body) = part;
+    let encoding = part_headers.get("content-transfer-encoding")
+        .map(|e| e.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let raw = match encoding.as_str() {
+        "quoted-printable" => decode_quoted_printable(part_body),
+        "base64" => {
+            let compact: Vec<u8> = part_body.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(compact)
+                .context("MHTML 页面 base64 解码失败")?
+        }
+        _ => part_body.to_vec(),
+    };
+
+    let charset = part_headers.get("content-type").and_then(|t| header_param(t, "charset"));
+    let mut extracted = extract_html(&decode_html(&raw, charset.as_deref()));
+
+    // 保存网页时记录的原始地址
+    let source_url = headers.get("snapshot-content-location")
+        .or_else(|| part_headers.get("content-location"))
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+    if let Some(url) = source_url {
+        extracted.metadata
+            .entry("canonical_url".to_string())
+            .or_insert_with(|| url.clone().into());
+        extracted.metadata.insert("source_url".to_string(), url.into());
+    }
+
+    Ok(extracted)
+}
+
+// ==================== 元数据 ====================
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("内置 CSS 选择器无效")
+}
+
+/// 合并空白后的元素文本
+fn element_text(element: ElementRef) -> String {
+    collapse_whitespace(&element.text().collect::<String>())
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn meta_content(document: &Html, css: &str) -> Option<String> {
+    document
+        .select(&selector(css))
+        .filter_map(|e| e.value().attr("content"))
+        .map(collapse_whitespace)
+        .find(|c| !c.is_empty())
+}
+
+/// 页面标题：`<title>`，其次 og:title，最后第一个 `<h1>`
+fn page_title(document: &Html) -> Option<String> {
+    document
+        .select(&selector("title"))
+        .map(element_text)
+        .find(|t| !t.is_empty())
+        .or_else(|| meta_content(document, r#"meta[property="og:title"]"#))
+        .or_else(|| {
+            document.select(&selector("h1")).map(element_text).find(|t| !t.is_empty())
+        })
+}
+
+/// canonical 链接，其次 og:url
+fn canonical_url(document: &Html) -> Option<String> {
+    document
+        .select(&selector(r#"link[rel~="canonical"]"#))
+        .filter_map(|e| e.value().attr("href"))
+        .map(|href| href.trim().to_string())
+        .find(|href| !href.is_empty())
+        .or_else(|| meta_content(document, r#"meta[property="og:url"]"#))
+}
+
+/// 选取正文区域：文字最多的 main / article，过短时使用 body
+fn content_root(document: &Html) -> (ElementRef<'_>, bool) {
+    let main = document
+        .select(&selector(r#"main, article, [role="main"]"#))
+        .max_by_key(|e| e.text().map(|t| t.trim().chars().count()).sum::<usize>())
+        .filter(|e| e.text().map(|t| t.trim().chars().count()).sum::<usize>() >= MIN_MAIN_CONTENT_CHARS);
+
+    match main {
+        Some(main) => (main, false),
+        None => {
+            let body = document.select(&selector("body")).next();
+            (body.unwrap_or_else(|| document.root_element()), true)
+        }
+    }
+}
+
+// ==================== 正文渲染 ====================
+
+struct Renderer {
+    /// 正文区域为 body 时，header / footer 等布局元素也视为模板内容
+    root_is_body: bool,
+}
+
+/// 块级元素内的行内文本缓冲
+#[derive(Default)]
+struct InlineText(String);
+
+impl InlineText {
+    fn push_text(&mut self, text: &str) {
+        if text.starts_with(char::is_whitespace) {
+            self.push_space();
+        }
+        for (i, word) in text.split_whitespace().enumerate() {
+            if i > 0 {
+                self.push_space();
+            }
+            self.0.push_str(word);
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.push_space();
+        }
+    }
+
+    fn push_space(&mut self) {
+        if !self.0.is_empty() && !self.0.ends_with([' ', '\n']) {
+            self.0.push(' ');
+        }
+    }
+
+    fn push_raw(&mut self, text: &str) {
+        self.0.push_str(text);
+    }
+
+    fn flush_into(&mut self, blocks: &mut Vec<String>) {
+        let text = std::mem::take(&mut self.0);
+        let text = text.lines().map(str::trim).collect::<Vec<_>>().join("\n");
+        let text = text.trim();
+        if !text.is_empty() {
+            blocks.push(text.to_string());
+        }
+    }
+}
+
+impl Renderer {
+    /// 渲染元素的子节点，返回块列表
+    fn render_blocks(&mut self, element: ElementRef) -> Vec<String> {
+        let mut blocks = Vec::new();
+        let mut inline = InlineText::default();
+        self.render_children(element, &mut blocks, &mut inline);
+        inline.flush_into(&mut blocks);
+        blocks
+    }
+
+    /// 渲染为单行文本（用于标题、列表项、表格单元格）
+    fn render_inline(&mut self, element: ElementRef) -> String {
+        collapse_whitespace(&self.render_blocks(element).join(" "))
+    }
+
+    fn render_children(&mut self, element: ElementRef, blocks: &mut Vec<String>, inline: &mut InlineText) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.render_element(child, blocks, inline);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn render_element(&mut self, element: ElementRef, blocks: &mut Vec<String>, inline: &mut InlineText) {
+        if self.is_boilerplate(element) {
+            return;
+        }
+
+        let name = element.value().name();
+        match name {
+            "br" => inline.push_raw("\n"),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                inline.flush_into(blocks);
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = self.render_inline(element);
+                if !text.is_empty() {
+                    blocks.push(format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "ul" | "ol" => {
+                inline.flush_into(blocks);
+                let lines = self.render_list(element, 0);
+                if !lines.is_empty() {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+            "table" if !is_layout_table(element) => {
+                inline.flush_into(blocks);
+                let rows = self.table_rows(element);
+                if !rows.is_empty() {
+                    blocks.push(markdown_table(&rows));
+                }
+            }
+            "pre" => {
+                inline.flush_into(blocks);
+                let code = element.text().collect::<String>();
+                let code = code.trim_matches('\n');
+                if !code.trim().is_empty() {
+                    blocks.push(format!("```{}\n{}\n```", code_language(element), code));
+                }
+            }
+            "blockquote" => {
+                inline.flush_into(blocks);
+                let quoted = self.render_blocks(element).join("\n\n");
+                if !quoted.is_empty() {
+                    blocks.push(quoted.lines().map(|l| format!("> {}", l).trim_end().to_string()).collect::<Vec<_>>().join("\n"));
+                }
+            }
+            "code" | "kbd" | "samp" => {
+                let text = element_text(element);
+                if !text.is_empty() {
+                    inline.push_raw(&format!("`{}`", text));
+                }
+            }
+            "img" | "picture" | "video" | "audio" | "source" | "track" | "map" | "area" => {}
+            _ if is_block(name) => {
+                inline.flush_into(blocks);
+                self.render_children(element, blocks, inline);
+                inline.flush_into(blocks);
+            }
+            _ => self.render_children(element, blocks, inline),
+        }
+    }
+
+    fn render_list(&mut self, list: ElementRef, depth: usize) -> Vec<String> {
+        let ordered = list.value().name() == "ol";
+        let mut lines = Vec::new();
+        let mut number = 0;
+
+        for item in list.children().filter_map(ElementRef::wrap) {
+            if item.value().name() != "li" || self.is_boilerplate(item) {
+                continue;
+            }
+            number += 1;
+
+            // 列表项中嵌套的子列表单独缩进输出
+            let mut text = InlineText::default();
+            let mut nested = Vec::new();
+            let mut item_blocks = Vec::new();
+            for child in item.children() {
+                match child.value() {
+                    Node::Text(t) => text.push_text(t),
+                    Node::Element(_) => {
+                        let Some(child) = ElementRef::wrap(child) else { continue };
+                        if matches!(child.value().name(), "ul" | "ol") {
+                            nested.extend(self.render_list(child, depth + 1));
+                        } else {
+                            self.render_element(child, &mut item_blocks, &mut text);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            text.flush_into(&mut item_blocks);
+
+            let content = collapse_whitespace(&item_blocks.join(" "));
+            if !content.is_empty() {
+                let marker = if ordered { format!("{}.", number) } else { "-".to_string() };
+                lines.push(format!("{}{} {}", "  ".repeat(depth), marker, content));
+            }
+            lines.extend(nested);
+        }
+
+        lines
+    }
+
+    fn table_rows(&mut self, table: ElementRef) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        for row in table.select(&selector("tr")) {
+            // 跳过嵌套表格中的行
+            let owner = row.ancestors().filter_map(ElementRef::wrap).find(|e| e.value().name() == "table");
+            if owner.map(|o| o.id()) != Some(table.id()) {
+                continue;
+            }
+
+            let cells: Vec<String> = row
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|c| matches!(c.value().name(), "td" | "th"))
+                .map(|c| self.render_inline(c))
+                .collect();
+            if cells.iter().any(|c| !c.is_empty()) {
+                rows.push(cells);
+            }
+        }
+        rows
+    }
+
+    /// 判断元素是否为导航、页脚、广告等模板内容
+    fn is_boilerplate(&self, element: ElementRef) -> bool {
+        let value = element.value();
+        let name = value.name();
+
+        if SKIPPED_TAGS.contains(&name) || NAVIGATION_TAGS.contains(&name) {
+            return true;
+        }
+        if self.root_is_body && LAYOUT_TAGS.contains(&name) {
+            return true;
+        }
+
+        if value.attr("hidden").is_some()
+            || value.attr("aria-hidden") == Some("true")
+            || value.attr("style").is_some_and(|s| s.replace(' ', "").contains("display:none"))
+        {
+            return true;
+        }
+        if value.attr("role").is_some_and(|r| BOILERPLATE_ROLES.contains(&r)) {
+            return true;
+        }
+
+        let hinted = value.classes().chain(value.id())
+            .flat_map(|token| token.split(['-', '_']))
+            .any(|word| BOILERPLATE_HINTS.contains(&word.to_ascii_lowercase().as_str()));
+        if hinted && !matches!(name, "body" | "main" | "article") {
+            return true;
+        }
+
+        // 链接文字占绝大部分的容器通常是导航或推荐列表
+        if matches!(name, "div" | "section" | "ul" | "ol" | "table") {
+            let links: Vec<ElementRef> = element.select(&selector("a")).collect();
+            if links.len() >= 5 {
+                let text_len: usize = element.text().map(|t| t.trim().chars().count()).sum();
+                let link_len: usize = links.iter()
+                    .flat_map(|a| a.text())
+                    .map(|t| t.trim().chars().count())
+                    .sum();
+                if link_len * 10 >= text_len * 8 {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div" | "section" | "article" | "main" | "header" | "footer" | "aside" | "nav"
+            | "figure" | "figcaption" | "dl" | "dt" | "dd" | "address" | "details" | "summary"
+            | "hr" | "li" | "tr" | "td" | "th" | "table" | "tbody" | "thead" | "tfoot"
+            | "caption" | "body" | "html" | "center" | "fieldset" | "legend"
+    )
+}
+
+/// 包含嵌套表格或大段文字的表格用于页面布局，按普通容器处理
+fn is_layout_table(table: ElementRef) -> bool {
+    table.select(&selector("table")).nth(1).is_some()
+        || table.select(&selector("td, th")).any(|cell| {
+            cell.text().map(|t| t.trim().chars().count()).sum::<usize>() > 500
+        })
+}
+
+/// 从 `language-xxx` / `lang-xxx` class 中读取代码语言
+fn code_language(pre: ElementRef) -> String {
+    std::iter::once(pre)
+        .chain(pre.select(&selector("code")))
+        .flat_map(|e| e.value().classes())
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")))
+        .unwrap_or_default()
+        .to_string()
+}
+
+// ==================== 编码与 MHTML ====================
+
+/// 按 BOM、声明的字符集或 `<meta charset>` 解码 HTML，默认 UTF-8
+fn decode_html(bytes: &[u8], declared: Option<&str>) -> String {
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| declared.and_then(|label| Encoding::for_label(label.trim().as_bytes())))
+        .or_else(|| sniff_meta_charset(bytes))
+        .unwrap_or(UTF_8);
+
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// 在文档开头查找 `charset=`（兼容 `<meta charset>` 和 http-equiv 两种写法）
+fn sniff_meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]).to_ascii_lowercase();
+    let start = head.find("charset=")? + "charset=".len();
+    let label: String = head[start..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+
+    Encoding::for_label(label.as_bytes())
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// 拆分 MIME 头部和正文，头部名称统一为小写（折行已展开）
+fn split_headers(data: &[u8]) -> (HashMap<String, String>, &[u8]) {
+    let (header_end, body_start) = match (find_bytes(data, b"\r\n\r\n"), find_bytes(data, b"\n\n")) {
+        (Some(crlf), Some(lf)) if lf < crlf => (lf, lf + 2),
+        (Some(crlf), _) => (crlf, crlf + 4),
+        (None, Some(lf)) => (lf, lf + 2),
+        (None, None) => (data.len(), data.len()),
+    };
+
+    let mut headers: HashMap<String, String> = HashMap::new();
+    let mut last_key: Option<String> = None;
+    for line in String::from_utf8_lossy(&data[..header_end]).lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = last_key.as_ref().and_then(|k| headers.get_mut(k)) {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().to_ascii_lowercase();
+            headers.insert(key.clone(), value.trim().to_string());
+            last_key = Some(key);
+        }
+    }
+
+    (headers, &data[body_start..])
+}
+
+/// 读取头部参数，例如 `boundary="----abc"`
+fn header_param(header: &str, name: &str) -> Option<String> {
+    header.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        (key.trim().eq_ignore_ascii_case(name)).then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// 按 boundary 拆分 multipart 正文
+fn multipart_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut parts = Vec::new();
+    let mut rest = body;
+
+    while let Some(position) = find_bytes(rest, &delimiter) {
+        let after = &rest[position + delimiter.len()..];
+        if after.starts_with(b"--") {
+            break;
+        }
+        let after = after.strip_prefix(b"\r\n").or_else(|| after.strip_prefix(b"\n")).unwrap_or(after);
+        let end = find_bytes(after, &delimiter).unwrap_or(after.len());
+        parts.push(&after[..end]);
+        rest = &after[end..];
+    }
+
+    parts
+}
+
+/// 解码 quoted-printable 编码
+fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut i = 0;
+
+    while i < data.len() {
+        if data[i] != b'=' {
+            output.push(data[i]);
+            i += 1;
+            continue;
+        }
+
+        // 软换行
+        if data[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if data[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = data.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            output.push(byte);
+            i += 3;
+        } else {
+            output.push(b'=');
+            i += 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <meta charset="utf-8">
+  <title> 安装指南 - 示例文档 </title>
+  <link rel="canonical" href="https://docs.example.com/install">
+  <style>.x { color: red }</style>
+  <script>var tracking = 1;</script>
+</head><body>
+  <header class="site-header"><a href="/">首页</a></header>
+  <nav><ul><li><a href="/a">文档</a></li><li><a href="/b">博客</a></li></ul></nav>
+  <div class="cookie-banner">本站使用 Cookie</div>
+  <main>
+    <h1>安装指南</h1>
+    <p>本文介绍如何在   <strong>Linux</strong> 上安装，命令为 <code>cargo install</code>。</p>
+    <h2>依赖</h2>
+    <ul>
+      <li>Rust 1.75+
+        <ol><li>rustup</li><li>cargo</li></ol>
+      </li>
+      <li>Node.js</li>
+    </ul>
+    <table>
+      <tr><th>系统</th><th>状态</th></tr>
+      <tr><td>Ubuntu</td><td>支持</td></tr>
+    </table>
+    <pre><code class="language-bash">cargo build --release
+./target/release/app</code></pre>
+    <p>更多细节请参阅发行说明，这里补充一些文字让正文区域超过最小长度限制，以便被选为正文。这里补充一些文字让正文区域超过最小长度限制，以便被选为正文。</p>
+  </main>
+  <footer>版权所有</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_extract_html() {
+        let extracted = extract_html(PAGE);
+
+        assert_eq!(extracted.metadata["title"], "安装指南 - 示例文档");
+        assert_eq!(extracted.metadata["canonical_url"], "https://docs.example.com/install");
+
+        let text = extracted.text;
+        assert!(text.starts_with("# 安装指南\n\n本文介绍如何在 Linux 上安装，命令为 `cargo install`。"));
+        assert!(text.contains("## 依赖\n\n- Rust 1.75+\n  1. rustup\n  2. cargo\n- Node.js"));
+        assert!(text.contains("| 系统 | 状态 |\n| --- | --- |\n| Ubuntu | 支持 |"));
+        assert!(text.contains("```bash\ncargo build --release\n./target/release/app\n```"));
+        for boilerplate in ["首页", "博客", "Cookie", "版权所有", "tracking", "color"] {
+            assert!(!text.contains(boilerplate), "未移除: {}", boilerplate);
+        }
+    }
+
+    #[test]
+    fn test_body_fallback_drops_layout() {
+        let extracted = extract_html(
+            "<html><body><header>站点导航</header><p>短正文</p><footer>页脚</footer></body></html>",
+        );
+
+        assert_eq!(extracted.text, "短正文");
+        assert_eq!(extracted.metadata.get("title"), None);
+    }
+
+    #[test]
+    fn test_decode_gbk_html() {
+        let (bytes, _, _) = encoding_rs::GBK.encode("<meta charset=\"gbk\"><title>中文标题</title><p>正文</p>");
+        let extracted = extract_html_bytes(&bytes);
+
+        assert_eq!(extracted.metadata["title"], "中文标题");
+        assert_eq!(extracted.text, "正文");
+    }
+
+    #[test]
+    fn test_extract_mhtml() {
+        let mhtml = "From: <Saved by Blink>\r\n\
+Snapshot-Content-Location: https://example.com/page\r\n\
+Subject: =?utf-8?Q?test?=\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/related;\r\n\
+\ttype=\"text/html\";\r\n\
+\tboundary=\"----MultipartBoundary--abc\"\r\n\
+\r\n\
+------MultipartBoundary--abc\r\n\
+Content-Type: text/html\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+Content-Location: https://example.com/page\r\n\
+\r\n\
+<html><head><title>=E7=A4=BA=E4=BE=8B</title></head><body><p>=E4=BD=A0=E5=A5=BD=\r\n\
+ world</p></body></html>\r\n\
+------MultipartBoundary--abc\r\n\
+Content-Type: text/css\r\n\
+\r\n\
+p { color: red }\r\n\
+------MultipartBoundary--abc--\r\n";
+
+        let extracted = extract_mhtml(mhtml.as_bytes()).unwrap();
+
+        assert_eq!(extracted.metadata["title"], "示例");
+        assert_eq!(extracted.metadata["canonical_url"], "https://example.com/page");
+        assert_eq!(extracted.metadata["source_url"], "https://example.com/page");
+        assert_eq!(extracted.text, "你好world");
+    }
+}
@@ -0,0 +1,90 @@
+//! 文档内容提取
+//!
+//! 将各种格式的文件转换为适合分块的纯文本：标题以 `#` 开头、表格转换为
+//! Markdown 表格、段落之间以空行分隔，尽量保留原文档的结构。
+
+use crate::rag::text_splitter::Section;
+
+pub mod epub;
+pub mod html;
+pub mod ocr;
+pub mod office;
+pub mod pdf;
+
+/// 提取结果：正文、文档级元数据（网页标题、原始链接等）以及章节划分
+#[derive(Debug, Clone, Default)]
+pub struct ExtractedDocument {
+    pub text: String,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    /// 章节在正文中的字符范围，分块时不跨越章节
+    pub sections: Vec<Section>,
+    /// 分页文档（PDF）每一页在正文中的起始字符偏移
+    pub page_starts: Vec<usize>,
+}
+
+impl From<String> for ExtractedDocument {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            metadata: serde_json::Map::new(),
+            sections: Vec::new(),
+            page_starts: Vec::new(),
+        }
+    }
+}
+
+/// 将二维表格转换为 Markdown 表格，第一行作为表头
+pub(crate) fn markdown_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let format_row = |row: &Vec<String>| {
+        let cells: Vec<String> = (0..columns)
+            .map(|i| {
+                row.get(i)
+                    .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|"))
+                    .unwrap_or_default()
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    lines.push(format_row(&rows[0]));
+    lines.push(format!("|{}", " --- |".repeat(columns)));
+    lines.extend(rows[1..].iter().map(format_row));
+    lines.join("\n")
+}
+
+/// 测试用：在内存中生成包含指定文件（路径，内容）的 zip 包（Office 文档、EPUB）
+#[cfg(test)]
+pub(crate) fn package(parts: &[(&str, &str)]) -> zip::ZipArchive<std::io::Cursor<Vec<u8>>> {
+    use std::io::Write;
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in parts {
+        writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    zip::ZipArchive::new(writer.finish().unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_table() {
+        let rows = vec![
+            vec!["姓名".to_string(), "部门".to_string()],
+            vec!["张三".to_string(), "研发|测试".to_string(), "多余".to_string()],
+        ];
+
+        assert_eq!(
+            markdown_table(&rows),
+            "| 姓名 | 部门 |  |\n| --- | --- | --- |\n| 张三 | 研发\\|测试 | 多余 |"
+        );
+    }
+}
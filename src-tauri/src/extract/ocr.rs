@@ -0,0 +1,239 @@
+//! 本地 OCR（Tesseract）
+//!
+//! 调用本机安装的 `tesseract` 命令行识别图片文字（纯 CPU，不上传任何内容），
+//! 以 TSV 格式输出，从中还原段落和行并计算平均置信度。扫描版 PDF 的页面先用
+//! poppler 的 `pdftoppm` 渲染为图片再识别。
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::rag::keyword::is_cjk;
+
+/// 按优先级尝试的识别语言（只使用本机已安装语言包的部分）
+const PREFERRED_LANGUAGES: &[&str] = &["chi_sim", "chi_tra", "eng"];
+
+/// 渲染 PDF 页面的分辨率，Tesseract 在 300 DPI 左右效果最好
+const PDF_RENDER_DPI: u32 = 300;
+
+/// 单页识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrPage {
+    /// 页码（从 1 开始，图片为 1）
+    pub page: usize,
+    pub text: String,
+    /// 平均置信度（0-100），没有识别出文字时为 None
+    pub confidence: Option<f32>,
+}
+
+/// 识别图片中的文字
+pub fn ocr_image(path: &Path) -> Result<OcrPage> {
+    let tesseract = Tesseract::locate()?;
+    let (text, confidence) = tesseract.recognize(path)?;
+    Ok(OcrPage { page: 1, text, confidence })
+}
+
+/// 识别 PDF 指定页面（页码从 1 开始）的文字
+pub fn ocr_pdf_pages(path: &Path, pages: &[usize]) -> Result<Vec<OcrPage>> {
+    let tesseract = Tesseract::locate()?;
+    let work_dir = TempDir::new()?;
+    let mut results = Vec::with_capacity(pages.len());
+
+    for &page in pages {
+        let image = render_pdf_page(path, page, &work_dir.0)?;
+        let (text, confidence) = tesseract.recognize(&image)?;
+        let _ = std::fs::remove_file(&image);
+        results.push(OcrPage { page, text, confidence });
+    }
+
+    Ok(results)
+}
+
+struct Tesseract {
+    program: PathBuf,
+    languages: Option<String>,
+}
+
+impl Tesseract {
+    /// 查找 tesseract 可执行文件（PATH 或 Windows 默认安装位置）及可用语言
+    fn locate() -> Result<Self> {
+        let mut candidates = vec![PathBuf::from("tesseract")];
+        if cfg!(windows) {
+            candidates.push(PathBuf::from(r"C:\Program Files\Tesseract-OCR\tesseract.exe"));
+        }
+
+        for program in candidates {
+            let Ok(output) = Command::new(&program).arg("--list-langs").output() else {
+                continue;
+            };
+            if !output.status.success() {
+                continue;
+            }
+
+            // 语言列表可能输出到 stdout 或 stderr（不同版本不一致）
+            let listing = format!(
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let installed: Vec<&str> = listing.lines().map(str::trim).collect();
+            let languages: Vec<&str> = PREFERRED_LANGUAGES
+                .iter()
+                .copied()
+                .filter(|lang| installed.contains(lang))
+                .collect();
+
+            return Ok(Self {
+                program,
+                languages: (!languages.is_empty()).then(|| languages.join("+")),
+            });
+        }
+
+        anyhow::bail!("未找到 Tesseract OCR，请安装 tesseract（并安装 chi_sim 中文语言包）后重试")
+    }
+
+    /// 识别单张图片，返回文本和平均置信度
+    fn recognize(&self, image: &Path) -> Result<(String, Option<f32>)> {
+        let mut command = Command::new(&self.program);
+        command.arg(image).arg("stdout");
+        if let Some(languages) = &self.languages {
+            command.arg("-l").arg(languages);
+        }
+        command.arg("tsv");
+
+        let output = command.output().context("启动 tesseract 失败")?;
+        if !output.status.success() {
+            anyhow::bail!("tesseract 识别失败: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// 使用 pdftoppm 将 PDF 的一页渲染为 PNG
+fn render_pdf_page(pdf: &Path, page: usize, work_dir: &Path) -> Result<PathBuf> {
+    let prefix = work_dir.join(format!("page{}", page));
+    let output = Command::new("pdftoppm")
+        .args(["-r", &PDF_RENDER_DPI.to_string(), "-png", "-singlefile"])
+        .args(["-f", &page.to_string(), "-l", &page.to_string()])
+        .arg(pdf)
+        .arg(&prefix)
+        .output()
+        .context("未找到 pdftoppm，请安装 poppler 以识别扫描版 PDF")?;
+
+    if !output.status.success() {
+        anyhow::bail!("渲染 PDF 第 {} 页失败: {}", page, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(prefix.with_extension("png"))
+}
+
+/// 临时工作目录，离开作用域时删除
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("wali-ocr-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).context("创建 OCR 临时目录失败")?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 中日韩文字及全角标点之间不插入空格
+fn is_wide(c: char) -> bool {
+    is_cjk(c) || matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// 解析 Tesseract 的 TSV 输出
+///
+/// 同一行的单词拼接为一行（中文之间不加空格），段落之间以空行分隔；
+/// 置信度为所有单词按字符数加权的平均值。
+fn parse_tsv(tsv: &str) -> (String, Option<f32>) {
+    let mut paragraphs: Vec<Vec<String>> = Vec::new();
+    let mut current_paragraph = None;
+    let mut current_line = None;
+    let mut weighted_confidence = 0.0;
+    let mut total_chars = 0usize;
+
+    // 列：level page_num block_num par_num line_num word_num left top width height conf text
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let word = columns[11].trim();
+        let Ok(confidence) = columns[10].parse::<f32>() else { continue };
+        if word.is_empty() || confidence < 0.0 {
+            continue;
+        }
+
+        let paragraph = (columns[2], columns[3]);
+        let line = (columns[2], columns[3], columns[4]);
+        if current_paragraph != Some(paragraph) {
+            paragraphs.push(Vec::new());
+            current_paragraph = Some(paragraph);
+            current_line = None;
+        }
+        let lines = paragraphs.last_mut().expect("段落已创建");
+        if current_line != Some(line) {
+            lines.push(String::new());
+            current_line = Some(line);
+        }
+
+        let text = lines.last_mut().expect("行已创建");
+        let joins_wide = text.chars().last().is_some_and(is_wide) && word.starts_with(is_wide);
+        if !text.is_empty() && !joins_wide {
+            text.push(' ');
+        }
+        text.push_str(word);
+
+        let chars = word.chars().count();
+        weighted_confidence += confidence * chars as f32;
+        total_chars += chars;
+    }
+
+    let text = paragraphs
+        .into_iter()
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let confidence = (total_chars > 0).then(|| weighted_confidence / total_chars as f32);
+
+    (text, confidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t
+5\t1\t1\t1\t1\t1\t10\t10\t40\t20\t90\t报销
+5\t1\t1\t1\t1\t2\t50\t10\t40\t20\t80\t流程
+5\t1\t1\t1\t1\t3\t90\t10\t40\t20\t95\tv2.0
+5\t1\t1\t1\t2\t1\t10\t40\t40\t20\t96\tStep
+5\t1\t1\t1\t2\t2\t60\t40\t40\t20\t96\tone
+5\t1\t1\t2\t1\t1\t10\t90\t40\t20\t70\t结束。
+5\t1\t1\t2\t1\t2\t10\t90\t40\t20\t-1\t
+";
+
+        let (text, confidence) = parse_tsv(tsv);
+
+        assert_eq!(text, "报销流程 v2.0\nStep one\n\n结束。");
+        let expected = (90.0 * 2.0 + 80.0 * 2.0 + 95.0 * 4.0 + 96.0 * 4.0 + 96.0 * 3.0 + 70.0 * 3.0) / 18.0;
+        assert!((confidence.unwrap() - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parse_tsv_empty() {
+        assert_eq!(parse_tsv("level\tpage_num\n"), (String::new(), None));
+    }
+}
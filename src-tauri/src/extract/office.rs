@@ -0,0 +1,765 @@
+//! Office Open XML（DOCX、PPTX、XLSX）文本提取
+//!
+//! OOXML 文件是包含若干 XML 部件的 ZIP 包，这里直接解析 XML：
+//! Word 保留标题层级、列表和表格，PowerPoint 按放映顺序输出每页的标题、
+//! 正文、表格和备注，Excel 按工作表顺序把单元格输出为 Markdown 表格。
+
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use super::markdown_table;
+
+/// 提取 Word 文档文本
+pub fn extract_docx(path: &Path) -> Result<String> {
+    docx_text(&mut open_package(path)?)
+}
+
+/// 提取 PowerPoint 演示文稿文本
+pub fn extract_pptx(path: &Path) -> Result<String> {
+    pptx_text(&mut open_package(path)?)
+}
+
+/// 提取 Excel 工作簿文本
+pub fn extract_xlsx(path: &Path) -> Result<String> {
+    xlsx_text(&mut open_package(path)?)
+}
+
+fn open_package(path: &Path) -> Result<ZipArchive<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("无法打开文件 {}", path.display()))?;
+    ZipArchive::new(BufReader::new(file)).context("不是有效的 Office 文档（ZIP 结构损坏或文件已加密）")
+}
+
+// ==================== 通用工具 ====================
+
+/// 读取包内部件，不存在时返回 None
+pub(super) fn read_part<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .with_context(|| format!("读取 {} 失败", name))?;
+    Ok(Some(content))
+}
+
+/// 读取必需的部件
+pub(super) fn require_part<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<String> {
+    read_part(archive, name)?.with_context(|| format!("文档缺少 {}", name))
+}
+
+/// 按本地名（忽略命名空间前缀）读取属性值
+pub(super) fn attr(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// 读取引用关系的 `r:id` 属性（与同名的无前缀 `id` 属性区分）
+fn relationship_id(element: &BytesStart) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == b"id" && a.key.prefix().is_some())
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// 解析 .rels 文件：关系 ID -> (部件路径, 关系类型)
+fn read_relationships<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    part: &str,
+) -> Result<HashMap<String, (String, String)>> {
+    let (dir, file_name) = part.rsplit_once('/').unwrap_or(("", part));
+    let rels_path = if dir.is_empty() {
+        format!("_rels/{}.rels", file_name)
+    } else {
+        format!("{}/_rels/{}.rels", dir, file_name)
+    };
+
+    let mut relationships = HashMap::new();
+    let Some(xml) = read_part(archive, &rels_path)? else {
+        return Ok(relationships);
+    };
+
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attr(&e, b"Id"), attr(&e, b"Target")) {
+                    let kind = attr(&e, b"Type").unwrap_or_default();
+                    relationships.insert(id, (resolve_part(dir, &target), kind));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(relationships)
+}
+
+/// 将关系中的相对路径解析为包内绝对路径
+pub(super) fn resolve_part(base_dir: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        base_dir.split('/').filter(|s| !s.is_empty()).collect()
+    };
+
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+/// 段落缓冲：合并文本片段并规范化空白
+#[derive(Default)]
+struct TextBuffer(String);
+
+impl TextBuffer {
+    fn push(&mut self, text: &str) {
+        self.0.push_str(text);
+    }
+
+    fn take(&mut self) -> String {
+        let text = std::mem::take(&mut self.0);
+        text.lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string()
+    }
+}
+
+/// 表格构建：按行收集单元格文本
+#[derive(Default)]
+struct TableBuilder {
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    cell: Vec<String>,
+}
+
+impl TableBuilder {
+    fn push_paragraph(&mut self, text: String) {
+        if !text.is_empty() {
+            self.cell.push(text);
+        }
+    }
+
+    fn end_cell(&mut self) {
+        let cell = std::mem::take(&mut self.cell);
+        self.row.push(cell.join(" "));
+    }
+
+    fn end_row(&mut self) {
+        let row = std::mem::take(&mut self.row);
+        if row.iter().any(|c| !c.is_empty()) {
+            self.rows.push(row);
+        }
+    }
+
+    fn finish(self) -> String {
+        if self.rows.is_empty() {
+            String::new()
+        } else {
+            markdown_table(&self.rows)
+        }
+    }
+}
+
+// ==================== Word ====================
+
+/// 从 styles.xml 读取标题样式：样式 ID -> 标题级别
+fn docx_heading_styles(xml: &str) -> Result<HashMap<String, usize>> {
+    let mut headings = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<String> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"style" => {
+                current = attr(&e, b"styleId");
+            }
+            Event::End(e) if e.local_name().as_ref() == b"style" => {
+                current = None;
+            }
+            Event::Start(e) | Event::Empty(e) => {
+                let Some(style_id) = &current else { continue };
+                let level = match e.local_name().as_ref() {
+                    // 中文版 Word 的标题样式 ID 是数字，需要根据样式名称判断
+                    b"name" => attr(&e, b"val").and_then(|name| {
+                        let name = name.to_lowercase();
+                        if name == "title" {
+                            Some(1)
+                        } else {
+                            name.strip_prefix("heading ").and_then(|n| n.trim().parse().ok())
+                        }
+                    }),
+                    b"outlineLvl" => attr(&e, b"val")
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .map(|v| v + 1),
+                    _ => None,
+                };
+                if let Some(level) = level.filter(|l| (1..=9).contains(l)) {
+                    headings.entry(style_id.clone()).or_insert(level);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(headings)
+}
+
+fn docx_text<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<String> {
+    let document = require_part(archive, "word/document.xml")?;
+    let headings = match read_part(archive, "word/styles.xml")? {
+        Some(styles) => docx_heading_styles(&styles)?,
+        None => HashMap::new(),
+    };
+
+    let mut blocks: Vec<String> = Vec::new();
+    // 嵌套表格的内容合并到最外层单元格
+    let mut tables: Vec<TableBuilder> = Vec::new();
+    let mut paragraph = TextBuffer::default();
+    let mut heading_level: Option<usize> = None;
+    let mut is_list_item = false;
+    let mut in_text = false;
+
+    let mut reader = Reader::from_str(&document);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"p" => {
+                    heading_level = None;
+                    is_list_item = false;
+                }
+                b"t" => in_text = true,
+                b"tbl" => tables.push(TableBuilder::default()),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"pStyle" => {
+                    heading_level = attr(&e, b"val").and_then(|id| headings.get(&id).copied());
+                }
+                b"outlineLvl" => {
+                    if let Some(level) = attr(&e, b"val").and_then(|v| v.parse::<usize>().ok()) {
+                        if level < 9 {
+                            heading_level = Some(level + 1);
+                        }
+                    }
+                }
+                b"numPr" => is_list_item = true,
+                b"tab" => paragraph.push("\t"),
+                b"br" | b"cr" => paragraph.push("\n"),
+                _ => {}
+            },
+            Event::Text(t) if in_text => paragraph.push(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"numPr" => is_list_item = true,
+                b"p" => {
+                    let text = paragraph.take();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    if let Some(table) = tables.last_mut() {
+                        table.push_paragraph(text);
+                    } else if let Some(level) = heading_level {
+                        blocks.push(format!("{} {}", "#".repeat(level), text.replace('\n', " ")));
+                    } else if is_list_item {
+                        blocks.push(format!("- {}", text));
+                    } else {
+                        blocks.push(text);
+                    }
+                }
+                b"tc" => {
+                    if let Some(table) = tables.last_mut() {
+                        table.end_cell();
+                    }
+                }
+                b"tr" => {
+                    if let Some(table) = tables.last_mut() {
+                        table.end_row();
+                    }
+                }
+                b"tbl" => {
+                    let Some(table) = tables.pop() else { continue };
+                    let text = table.finish();
+                    if let Some(outer) = tables.last_mut() {
+                        outer.push_paragraph(text.replace('\n', " "));
+                    } else if !text.is_empty() {
+                        blocks.push(text);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(join_blocks(blocks))
+}
+
+/// 段落之间以空行分隔，连续的列表项保持紧凑
+fn join_blocks(blocks: Vec<String>) -> String {
+    let mut output = String::new();
+    let mut previous_is_list = false;
+
+    for block in blocks {
+        let is_list = block.starts_with("- ");
+        if !output.is_empty() {
+            output.push_str(if is_list && previous_is_list { "\n" } else { "\n\n" });
+        }
+        output.push_str(&block);
+        previous_is_list = is_list;
+    }
+
+    output
+}
+
+// ==================== PowerPoint ====================
+
+const REL_TYPE_NOTES_SLIDE: &str = "/notesSlide";
+
+/// 单页幻灯片的内容
+#[derive(Default)]
+struct SlideContent {
+    title: Option<String>,
+    blocks: Vec<String>,
+}
+
+/// 解析幻灯片或备注页的 XML
+fn pptx_slide_content(xml: &str) -> Result<SlideContent> {
+    let mut content = SlideContent::default();
+    let mut reader = Reader::from_str(xml);
+
+    let mut paragraph = TextBuffer::default();
+    let mut shape_paragraphs: Vec<String> = Vec::new();
+    let mut is_title_shape = false;
+    let mut table: Option<TableBuilder> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"sp" => {
+                    is_title_shape = false;
+                    shape_paragraphs.clear();
+                }
+                b"t" => in_text = true,
+                b"tbl" => table = Some(TableBuilder::default()),
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"ph" => {
+                    let kind = attr(&e, b"type").unwrap_or_default();
+                    is_title_shape = kind == "title" || kind == "ctrTitle";
+                }
+                b"br" => paragraph.push("\n"),
+                _ => {}
+            },
+            Event::Text(t) if in_text => paragraph.push(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => {
+                    let text = paragraph.take();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match table.as_mut() {
+                        Some(table) => table.push_paragraph(text),
+                        None => shape_paragraphs.push(text),
+                    }
+                }
+                b"tc" => {
+                    if let Some(table) = table.as_mut() {
+                        table.end_cell();
+                    }
+                }
+                b"tr" => {
+                    if let Some(table) = table.as_mut() {
+                        table.end_row();
+                    }
+                }
+                b"tbl" => {
+                    if let Some(table) = table.take() {
+                        let text = table.finish();
+                        if !text.is_empty() {
+                            content.blocks.push(text);
+                        }
+                    }
+                }
+                b"sp" => {
+                    let paragraphs = std::mem::take(&mut shape_paragraphs);
+                    if paragraphs.is_empty() {
+                        continue;
+                    }
+                    if is_title_shape && content.title.is_none() {
+                        content.title = Some(paragraphs.join(" ").replace('\n', " "));
+                    } else {
+                        content.blocks.push(paragraphs.join("\n"));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(content)
+}
+
+fn pptx_text<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<String> {
+    const PRESENTATION: &str = "ppt/presentation.xml";
+
+    let presentation = require_part(archive, PRESENTATION)?;
+    let relationships = read_relationships(archive, PRESENTATION)?;
+
+    // 幻灯片顺序以 presentation.xml 中的 sldIdLst 为准，而不是文件名
+    let mut slide_parts = Vec::new();
+    let mut reader = Reader::from_str(&presentation);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sldId" => {
+                if let Some((part, _)) = relationship_id(&e).and_then(|id| relationships.get(&id)) {
+                    slide_parts.push(part.clone());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut sections = Vec::with_capacity(slide_parts.len());
+    for (index, part) in slide_parts.iter().enumerate() {
+        let Some(xml) = read_part(archive, part)? else { continue };
+        let slide = pptx_slide_content(&xml)?;
+
+        let heading = match &slide.title {
+            Some(title) => format!("## 幻灯片 {}：{}", index + 1, title),
+            None => format!("## 幻灯片 {}", index + 1),
+        };
+        let mut blocks = vec![heading];
+        blocks.extend(slide.blocks);
+
+        // 演讲者备注
+        let notes_part = read_relationships(archive, part)?
+            .into_values()
+            .find(|(_, kind)| kind.ends_with(REL_TYPE_NOTES_SLIDE))
+            .map(|(part, _)| part);
+        if let Some(notes_xml) = match notes_part {
+            Some(notes_part) => read_part(archive, &notes_part)?,
+            None => None,
+        } {
+            // 备注页中还包含幻灯片缩略图和页码占位符，只保留正文
+            let notes = pptx_slide_content(&notes_xml)?.blocks
+                .into_iter()
+                .filter(|b| b.parse::<usize>().is_err())
+                .collect::<Vec<_>>();
+            if !notes.is_empty() {
+                blocks.push(format!("备注：{}", notes.join("\n")));
+            }
+        }
+
+        sections.push(blocks.join("\n\n"));
+    }
+
+    Ok(sections.join("\n\n"))
+}
+
+// ==================== Excel ====================
+
+/// 读取共享字符串表
+fn xlsx_shared_strings(xml: &str) -> Result<Vec<String>> {
+    let mut strings = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current = String::new();
+    let mut in_text = false;
+    // 日文注音（rPh）中的文本不属于单元格内容
+    let mut in_phonetic = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"si" => current.clear(),
+                b"t" if !in_phonetic => in_text = true,
+                b"rPh" => in_phonetic = true,
+                _ => {}
+            },
+            Event::Text(t) if in_text => current.push_str(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                b"si" => strings.push(std::mem::take(&mut current)),
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == b"si" => strings.push(String::new()),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(strings)
+}
+
+/// 将单元格引用（如 `AB12`）中的列字母转换为从 0 开始的列号
+fn column_index(cell_ref: &str) -> Option<usize> {
+    let letters: String = cell_ref.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+
+    let index = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0usize, |acc, b| acc * 26 + (b - b'A' + 1) as usize);
+    Some(index - 1)
+}
+
+/// 解析工作表，返回去掉空行空列后的二维表格
+fn xlsx_sheet_rows(xml: &str, shared_strings: &[String]) -> Result<Vec<Vec<String>>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<(usize, String)> = Vec::new();
+    let mut reader = Reader::from_str(xml);
+
+    let mut cell_type = String::new();
+    let mut cell_column = 0;
+    let mut value = String::new();
+    let mut in_value = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"row" => row.clear(),
+                b"c" => {
+                    cell_type = attr(&e, b"t").unwrap_or_default();
+                    cell_column = attr(&e, b"r")
+                        .and_then(|r| column_index(&r))
+                        .unwrap_or_else(|| row.last().map_or(0, |(c, _)| c + 1));
+                    value.clear();
+                }
+                b"v" | b"t" => in_value = true,
+                _ => {}
+            },
+            Event::Text(t) if in_value => value.push_str(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"v" | b"t" => in_value = false,
+                b"c" => {
+                    let text = match cell_type.as_str() {
+                        "s" => value
+                            .trim()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| shared_strings.get(i).cloned())
+                            .unwrap_or_default(),
+                        "b" => if value.trim() == "1" { "TRUE" } else { "FALSE" }.to_string(),
+                        _ => value.clone(),
+                    };
+                    if !text.trim().is_empty() {
+                        row.push((cell_column, text.trim().to_string()));
+                    }
+                }
+                b"row" => {
+                    if let Some(max_column) = row.iter().map(|(c, _)| *c).max() {
+                        let mut cells = vec![String::new(); max_column + 1];
+                        for (column, text) in row.drain(..) {
+                            cells[column] = text;
+                        }
+                        rows.push(cells);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    // 去掉所有行都为空的列（通常是左侧留白）
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let used: Vec<usize> = (0..columns)
+        .filter(|&c| rows.iter().any(|r| r.get(c).is_some_and(|v| !v.is_empty())))
+        .collect();
+
+    Ok(rows
+        .into_iter()
+        .map(|r| used.iter().map(|&c| r.get(c).cloned().unwrap_or_default()).collect())
+        .collect())
+}
+
+fn xlsx_text<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<String> {
+    const WORKBOOK: &str = "xl/workbook.xml";
+
+    let workbook = require_part(archive, WORKBOOK)?;
+    let relationships = read_relationships(archive, WORKBOOK)?;
+    let shared_strings = match read_part(archive, "xl/sharedStrings.xml")? {
+        Some(xml) => xlsx_shared_strings(&xml)?,
+        None => Vec::new(),
+    };
+
+    // 工作表顺序以 workbook.xml 为准
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(&workbook);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheet" => {
+                let name = attr(&e, b"name").unwrap_or_default();
+                if let Some((part, _)) = relationship_id(&e).and_then(|id| relationships.get(&id)) {
+                    sheets.push((name, part.clone()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut sections = Vec::with_capacity(sheets.len());
+    for (name, part) in sheets {
+        let Some(xml) = read_part(archive, &part)? else { continue };
+        let rows = xlsx_sheet_rows(&xml, &shared_strings)?;
+        if rows.is_empty() {
+            continue;
+        }
+        sections.push(format!("## 工作表：{}\n\n{}", name, markdown_table(&rows)));
+    }
+
+    Ok(sections.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::package;
+
+    #[test]
+    fn test_resolve_part() {
+        assert_eq!(resolve_part("ppt/slides", "../notesSlides/notesSlide1.xml"), "ppt/notesSlides/notesSlide1.xml");
+        assert_eq!(resolve_part("xl", "/xl/worksheets/sheet1.xml"), "xl/worksheets/sheet1.xml");
+        assert_eq!(column_index("AB12"), Some(27));
+    }
+
+    #[test]
+    fn test_docx_text() {
+        let styles = r#"<w:styles xmlns:w="w">
+            <w:style w:styleId="1"><w:name w:val="heading 1"/></w:style>
+            <w:style w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>
+        </w:styles>"#;
+        let document = r#"<w:document xmlns:w="w"><w:body>
+            <w:p><w:pPr><w:pStyle w:val="1"/></w:pPr><w:r><w:t>安装指南</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">支持 </w:t></w:r><w:r><w:t>Linux &amp; macOS。</w:t></w:r></w:p>
+            <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>依赖</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>Rust</w:t></w:r></w:p>
+            <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>Node</w:t></w:r></w:p>
+            <w:tbl>
+                <w:tr><w:tc><w:p><w:r><w:t>系统</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>版本</w:t></w:r></w:p></w:tc></w:tr>
+                <w:tr><w:tc><w:p><w:r><w:t>Ubuntu</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>22.04</w:t></w:r></w:p></w:tc></w:tr>
+            </w:tbl>
+        </w:body></w:document>"#;
+
+        let mut archive = package(&[("word/document.xml", document), ("word/styles.xml", styles)]);
+        let text = docx_text(&mut archive).unwrap();
+
+        assert_eq!(
+            text,
+            "# 安装指南\n\n支持 Linux & macOS。\n\n## 依赖\n\n- Rust\n- Node\n\n| 系统 | 版本 |\n| --- | --- |\n| Ubuntu | 22.04 |"
+        );
+    }
+
+    #[test]
+    fn test_pptx_slide_order_and_notes() {
+        let presentation = r#"<p:presentation xmlns:p="p" xmlns:r="r"><p:sldIdLst>
+            <p:sldId id="257" r:id="rId3"/><p:sldId id="256" r:id="rId2"/>
+        </p:sldIdLst></p:presentation>"#;
+        let rels = r#"<Relationships>
+            <Relationship Id="rId2" Type="http://schemas/slide" Target="slides/slide1.xml"/>
+            <Relationship Id="rId3" Type="http://schemas/slide" Target="slides/slide2.xml"/>
+        </Relationships>"#;
+        let slide = |title: &str, body: &str| {
+            format!(
+                r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+                <p:sp><p:nvSpPr><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp>
+                <p:sp><p:nvSpPr><p:nvPr><p:ph idx="1"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp>
+                </p:spTree></p:cSld></p:sld>"#,
+                title, body
+            )
+        };
+        let slide1 = slide("第二页", "后出现");
+        let slide2 = slide("第一页", "先出现");
+        let slide2_rels = r#"<Relationships>
+            <Relationship Id="rId1" Type="http://schemas/notesSlide" Target="../notesSlides/notesSlide1.xml"/>
+        </Relationships>"#;
+        let notes = r#"<p:notes xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree>
+            <p:sp><p:txBody><a:p><a:r><a:t>讲解要点</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:sp><p:txBody><a:p><a:r><a:t>1</a:t></a:r></a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:notes>"#;
+
+        let mut archive = package(&[
+            ("ppt/presentation.xml", presentation),
+            ("ppt/_rels/presentation.xml.rels", rels),
+            ("ppt/slides/slide1.xml", &slide1),
+            ("ppt/slides/slide2.xml", &slide2),
+            ("ppt/slides/_rels/slide2.xml.rels", slide2_rels),
+            ("ppt/notesSlides/notesSlide1.xml", notes),
+        ]);
+        let text = pptx_text(&mut archive).unwrap();
+
+        assert_eq!(
+            text,
+            "## 幻灯片 1：第一页\n\n先出现\n\n备注：讲解要点\n\n## 幻灯片 2：第二页\n\n后出现"
+        );
+    }
+
+    #[test]
+    fn test_xlsx_text() {
+        let workbook = r#"<workbook xmlns:r="r"><sheets>
+            <sheet name="员工" sheetId="1" r:id="rId1"/>
+        </sheets></workbook>"#;
+        let rels = r#"<Relationships>
+            <Relationship Id="rId1" Type="http://schemas/worksheet" Target="worksheets/sheet1.xml"/>
+        </Relationships>"#;
+        let shared = r#"<sst><si><t>姓名</t></si><si><t>在职</t></si><si><r><t>张</t></r><r><t>三</t></r></si></sst>"#;
+        let sheet = r#"<worksheet><sheetData>
+            <row r="1"><c r="B1" t="s"><v>0</v></c><c r="C1" t="s"><v>1</v></c><c r="D1" t="str"><v>工龄</v></c></row>
+            <row r="2"><c r="B2" t="s"><v>2</v></c><c r="C2" t="b"><v>1</v></c><c r="D2"><v>3</v></c></row>
+            <row r="3"><c r="B3" t="inlineStr"><is><t>李四</t></is></c></row>
+        </sheetData></worksheet>"#;
+
+        let mut archive = package(&[
+            ("xl/workbook.xml", workbook),
+            ("xl/_rels/workbook.xml.rels", rels),
+            ("xl/sharedStrings.xml", shared),
+            ("xl/worksheets/sheet1.xml", sheet),
+        ]);
+        let text = xlsx_text(&mut archive).unwrap();
+
+        assert_eq!(
+            text,
+            "## 工作表：员工\n\n| 姓名 | 在职 | 工龄 |\n| --- | --- | --- |\n| 张三 | TRUE | 3 |\n| 李四 |  |  |"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! PDF 文本提取
+//!
+//! 逐页读取文本层；没有文本层的页面（扫描件）交给本地 OCR 识别，
+//! 识别的页码和置信度记录在文档元数据的 `ocr` 字段中。
+//! 同时记录每页在正文中的起始位置，分块后据此标注页码。
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+
+use super::{ocr, ExtractedDocument};
+
+/// 文本层少于该字符数（不含空白）的页面视为扫描页
+const MIN_TEXT_LAYER_CHARS: usize = 20;
+
+/// 提取 PDF 正文，扫描页自动 OCR
+pub fn extract_pdf(path: &Path) -> Result<ExtractedDocument> {
+    let mut pages = pdf_extract::extract_text_by_pages(path)
+        .context("无法读取文本层，可能是加密或损坏的文件")?;
+
+    let scanned: Vec<usize> = pages
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| text.chars().filter(|c| !c.is_whitespace()).count() < MIN_TEXT_LAYER_CHARS)
+        .map(|(i, _)| i + 1)
+        .collect();
+
+    let mut extracted = ExtractedDocument::default();
+
+    if !scanned.is_empty() {
+        println!("🔍 PDF 有 {} 页没有文本层，尝试 OCR 识别", scanned.len());
+        match ocr::ocr_pdf_pages(path, &scanned) {
+            Ok(results) => {
+                let mut recognized = Vec::new();
+                for result in results {
+                    // OCR 结果比原有文本层更完整时才替换
+                    let page_text = &mut pages[result.page - 1];
+                    if result.text.trim().chars().count() > page_text.trim().chars().count() {
+                        *page_text = result.text;
+                    }
+                    recognized.push(json!({
+                        "page": result.page,
+                        "confidence": result.confidence,
+                    }));
+                }
+                extracted.metadata.insert(
+                    "ocr".to_string(),
+                    json!({ "engine": "tesseract", "pages": recognized }),
+                );
+            }
+            // 部分页面有文本层时仍然导入这些页面
+            Err(e) if scanned.len() < pages.len() => {
+                println!("⚠️ OCR 不可用，跳过 {} 个扫描页: {}", scanned.len(), e);
+            }
+            Err(e) => return Err(e.context("PDF 没有文本层（扫描版），OCR 识别失败")),
+        }
+    }
+
+    // 页面之间以空行分隔；空白页不占位置，起点与下一页相同
+    let mut length = 0;
+    for page in &pages {
+        let page = page.trim();
+        let separator = if extracted.text.is_empty() { 0 } else { 2 };
+        extracted.page_starts.push(length + separator);
+        if page.is_empty() {
+            continue;
+        }
+        if separator > 0 {
+            extracted.text.push_str("\n\n");
+        }
+        extracted.text.push_str(page);
+        length += separator + page.chars().count();
+    }
+
+    if extracted.text.is_empty() {
+        anyhow::bail!("PDF 中没有可提取的文本");
+    }
+
+    Ok(extracted)
+}
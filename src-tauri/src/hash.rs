@@ -0,0 +1,16 @@
+//! 内容哈希（SHA-256，十六进制），用于识别重复导入的文件、内容相同的文档和未变化的文档块
+
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// 计算文件内容的 SHA-256，用于识别重复导入的文件
+pub(crate) async fn file_hash(path: &Path) -> Result<String, String> {
+    let bytes = tokio::fs::read(path).await
+        .map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// 计算文本的 SHA-256，用于识别内容相同的文档和文档块
+pub(crate) fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
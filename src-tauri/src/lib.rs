@@ -2,6 +2,8 @@
 
 mod db;
 mod rag;
+mod extract;
+mod hash;
 mod app_state;
 mod commands;
 
@@ -30,15 +32,36 @@ pub fn run() {
             // 配置相关
             commands::config::set_api_key,
             commands::config::get_api_key_status,
+            commands::config::get_rag_config,
+            commands::config::set_rag_config,
+            // 知识库相关
+            commands::knowledge_base::create_knowledge_base,
+            commands::knowledge_base::get_knowledge_bases,
+            commands::knowledge_base::update_knowledge_base,
+            commands::knowledge_base::delete_knowledge_base,
             // 文档相关
             commands::document::upload_document,
             commands::document::upload_document_from_path,
             commands::document::get_documents,
             commands::document::delete_document,
+            commands::document::repair_documents,
+            commands::document::reimport_document,
+            commands::import::import_folder,
+            // 导入任务相关
+            commands::ingestion::get_ingestion_jobs,
+            commands::ingestion::cancel_ingestion_job,
+            commands::ingestion::retry_ingestion_job,
+            // 监听文件夹相关
+            commands::watch::add_watched_folder,
+            commands::watch::get_watched_folders,
+            commands::watch::remove_watched_folder,
+            commands::watch::sync_watched_folder,
             // 对话相关
             commands::chat::ask_question,
+            commands::chat::ask_question_stream,
             commands::chat::get_conversations,
             commands::chat::get_messages,
+            commands::chat::get_message_sources,
             commands::chat::delete_conversation,
             // 文件相关
             commands::file::read_file_content,
@@ -59,28 +82,46 @@ pub fn run() {
                     .expect("Failed to initialize database")
             });
             
-            // 创建应用状态
+            // 创建应用状态（同时从数据库恢复向量存储）
             let app_state = tauri::async_runtime::block_on(async {
-                AppState::new(db).await
+                AppState::new(db, &data_dir).await
                     .expect("Failed to create app state")
             });
             
-            // 尝试从数据库加载 API Key
+            // 尝试从数据库加载 API Key（本地服务无需 API Key 也可初始化）
             tauri::async_runtime::block_on(async {
-                if let Ok(Some(api_key)) = sqlx::query_scalar::<_, String>(
+                let api_key = sqlx::query_scalar::<_, String>(
                     "SELECT value FROM settings WHERE key = ?"
                 )
                 .bind("qwen_api_key")
                 .fetch_optional(app_state.db.pool())
                 .await
-                {
-                    app_state.init_rag_services(api_key);
+                .ok()
+                .flatten();
+                
+                let requires_api_key = app_state.rag_config.lock().unwrap().requires_api_key();
+                let api_key = match api_key {
+                    Some(api_key) => Some(api_key),
+                    None if !requires_api_key => Some(String::new()),
+                    None => None,
+                };
+                
+                if let Some(api_key) = api_key {
+                    if let Err(e) = app_state.init_rag_services(api_key) {
+                        println!("⚠️ RAG 服务初始化失败: {}", e);
+                    }
                 }
             });
             
             // 将状态添加到应用管理器
             app.manage(app_state);
             
+            // 启动后台导入任务（恢复上次未完成的任务）
+            commands::ingestion::spawn_worker(app.handle().clone());
+            
+            // 恢复监听文件夹
+            commands::watch::spawn_watchers(app.handle().clone());
+            
             // 仅在 Windows 平台上执行以下代码
             #[cfg(target_os = "windows")]
             {
@@ -0,0 +1,291 @@
+//! 回答中的内联引用标记
+//!
+//! 提示词中的参考片段按 `[1]`、`[2]` 编号，模型在回答中以相同格式引用；
+//! 生成结束后校验标记是否对应实际检索到的片段。代码块、行内代码和 Markdown 链接中的
+//! 方括号不是引用，超出片段范围的编号（例如年份 `[2023]`）也按原文保留。
+
+use super::tokenizer::ChunkUnit;
+
+/// 提供给模型的参考片段
+#[derive(Debug, Clone)]
+pub struct ContextPassage {
+    /// 片段来源（文档名称，或「书名 › 章节」）
+    pub title: String,
+    pub content: String,
+}
+
+fn format_passage(index: usize, passage: &ContextPassage) -> String {
+    format!("[{}] 来源：{}\n{}", index, passage.title, passage.content)
+}
+
+/// 将参考片段按 `[编号] 来源` 的格式拼接为提示词中的参考文档
+pub fn format_context(passages: &[ContextPassage]) -> String {
+    passages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format_passage(i + 1, p))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 按 token 上限截取参考片段（`max_tokens` 为 0 时不限制）
+///
+/// 片段按相关度顺序保留，放不下的片段及其后的片段被丢弃；第一个片段单独超限时
+/// 截断其内容，保证至少保留一个片段。返回保留的片段数。
+pub fn fit_context(passages: &mut Vec<ContextPassage>, max_tokens: usize) -> usize {
+    if max_tokens == 0 {
+        return passages.len();
+    }
+
+    let separator = ChunkUnit::Tokens.measure("\n\n");
+    let mut used = 0;
+    let mut kept = 0;
+    for (i, passage) in passages.iter().enumerate() {
+        let cost = ChunkUnit::Tokens.measure(&format_passage(i + 1, passage)) + if i > 0 { separator } else { 0 };
+        if used + cost > max_tokens {
+            break;
+        }
+        used += cost;
+        kept += 1;
+    }
+
+    if kept == 0 {
+        if let Some(first) = passages.first_mut() {
+            let header = ChunkUnit::Tokens.measure(&format_passage(1, &ContextPassage {
+                title: first.title.clone(),
+                content: String::new(),
+            }));
+            let chars: Vec<char> = first.content.chars().collect();
+            let keep = ChunkUnit::Tokens.fit_prefix(&chars, max_tokens.saturating_sub(header));
+            first.content = chars[..keep].iter().collect();
+            kept = 1;
+        }
+    }
+
+    passages.truncate(kept);
+    kept
+}
+
+/// 引用校验结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Citations {
+    /// 规范化后的回答：引用统一为 `[n]`，其余方括号内容按原文保留
+    pub answer: String,
+    /// 有效的引用编号（从 1 开始，按首次出现顺序）
+    pub cited: Vec<usize>,
+    /// 形如引用、但超出参考片段范围而按原文保留的编号
+    pub invalid: Vec<usize>,
+}
+
+/// 解析括号内的引用编号，支持 `1`、`1, 2`、`1，2`、`1-3`；不是引用格式时返回 None
+fn parse_marker(inner: &str) -> Option<Vec<usize>> {
+    let mut numbers = Vec::new();
+
+    for part in inner.split([',', '，', '、']) {
+        let part = part.trim();
+        if let Some((from, to)) = part.split_once(['-', '–']) {
+            let from: usize = from.trim().parse().ok()?;
+            let to: usize = to.trim().parse().ok()?;
+            // 防止异常范围展开出大量编号
+            if from > to || to - from > 20 {
+                return None;
+            }
+            numbers.extend(from..=to);
+        } else {
+            numbers.push(part.parse().ok()?);
+        }
+    }
+
+    Some(numbers)
+}
+
+/// 文本开头的代码（以反引号开始）的字节长度
+///
+/// 行内代码在相同数量的反引号处结束，没有结束标记时只跳过反引号本身；
+/// 代码块（三个及以上反引号）没有结束标记时延续到文本末尾。
+fn code_span_len(text: &str) -> usize {
+    let ticks = text.len() - text.trim_start_matches('`').len();
+    let fence = &text[..ticks];
+
+    let mut from = ticks;
+    while let Some(pos) = text[from..].find(fence) {
+        let start = from + pos;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == ticks {
+            return start + run;
+        }
+        from = start + run;
+    }
+
+    if ticks >= 3 { text.len() } else { ticks }
+}
+
+/// 扫描回答中的引用标记：`strip` 为 true 时移除合法的引用，否则改写为 `[n]`
+fn rewrite_citations(answer: &str, passage_count: usize, strip: bool) -> Citations {
+    let mut result = Citations::default();
+    let mut output = String::with_capacity(answer.len());
+    let mut rest = answer;
+
+    while let Some(open) = rest.find(['[', '【', '`']) {
+        output.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        // 代码中的方括号（数组下标等）原样保留
+        if rest.starts_with('`') {
+            let len = code_span_len(rest);
+            output.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+
+        let open_len = rest.chars().next().map_or(1, char::len_utf8);
+        let after_open = &rest[open_len..];
+        let Some(close) = after_open.find([']', '】']) else {
+            break;
+        };
+        let close_len = after_open[close..].chars().next().map_or(1, char::len_utf8);
+        let marker_len = open_len + close + close_len;
+
+        // Markdown 链接（文字和地址）原样保留
+        if rest[marker_len..].starts_with('(') {
+            let link_len = rest[marker_len..].find(')').map_or(marker_len, |end| marker_len + end + 1);
+            output.push_str(&rest[..link_len]);
+            rest = &rest[link_len..];
+            continue;
+        }
+
+        match parse_marker(&after_open[..close]) {
+            Some(numbers) if numbers.iter().all(|n| (1..=passage_count).contains(n)) => {
+                for n in numbers {
+                    if !strip {
+                        output.push_str(&format!("[{}]", n));
+                    }
+                    if !result.cited.contains(&n) {
+                        result.cited.push(n);
+                    }
+                }
+            }
+            // 超出范围的编号多半不是引用（年份、编号等），原样保留
+            Some(numbers) => {
+                for n in numbers {
+                    if !(1..=passage_count).contains(&n) && !result.invalid.contains(&n) {
+                        result.invalid.push(n);
+                    }
+                }
+                output.push_str(&rest[..marker_len]);
+            }
+            // 普通的方括号内容原样保留
+            None => output.push_str(&rest[..marker_len]),
+        }
+
+        rest = &rest[marker_len..];
+    }
+
+    output.push_str(rest);
+    result.answer = output;
+    result
+}
+
+/// 校验并规范化回答中的引用标记
+///
+/// 识别 `[1]`、`[1, 2]`、`【1】` 等写法，全部编号都在 `passage_count` 范围内时
+/// 改写为连续的 `[1][2]`，否则按原文保留。代码和 Markdown 链接中的方括号不做处理。
+pub fn extract_citations(answer: &str, passage_count: usize) -> Citations {
+    rewrite_citations(answer, passage_count, false)
+}
+
+/// 移除回答中引用前 `passage_count` 个参考片段的标记（历史回答带入提示词前使用）
+pub fn strip_citations(answer: &str, passage_count: usize) -> String {
+    rewrite_citations(answer, passage_count, true).answer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_context() {
+        let passages = vec![
+            ContextPassage { title: "员工手册.pdf".to_string(), content: "年假五天。".to_string() },
+            ContextPassage { title: "报销制度.docx".to_string(), content: "先提交申请。".to_string() },
+        ];
+
+        assert_eq!(
+            format_context(&passages),
+            "[1] 来源：员工手册.pdf\n年假五天。\n\n[2] 来源：报销制度.docx\n先提交申请。"
+        );
+    }
+
+    #[test]
+    fn test_fit_context() {
+        let passage = |content: &str| ContextPassage { title: "手册.md".to_string(), content: content.to_string() };
+        let long = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+
+        let mut passages = vec![passage("Short answer."), passage(&long), passage("Tail.")];
+        assert_eq!(fit_context(&mut passages, 50), 1);
+        assert_eq!(passages[0].content, "Short answer.");
+
+        let mut passages = vec![passage(&long), passage("Tail.")];
+        assert_eq!(fit_context(&mut passages, 40), 1);
+        assert!(ChunkUnit::Tokens.measure(&format_context(&passages)) <= 40);
+        assert!(long.starts_with(&passages[0].content));
+
+        let mut passages = vec![passage(&long), passage("Tail.")];
+        assert_eq!(fit_context(&mut passages, 0), 2);
+    }
+
+    #[test]
+    fn test_extract_citations() {
+        let citations = extract_citations("年假五天【1】，报销需先申请[2, 3]，病假见[7]。详见 [附录]", 3);
+
+        assert_eq!(citations.answer, "年假五天[1]，报销需先申请[2][3]，病假见[7]。详见 [附录]");
+        assert_eq!(citations.cited, vec![1, 2, 3]);
+        assert_eq!(citations.invalid, vec![7]);
+    }
+
+    #[test]
+    fn test_extract_citations_ranges_and_unclosed() {
+        let citations = extract_citations("见[1-2]和[2] [未闭合", 2);
+
+        assert_eq!(citations.answer, "见[1][2]和[2] [未闭合");
+        assert_eq!(citations.cited, vec![1, 2]);
+        assert!(citations.invalid.is_empty());
+    }
+
+    #[test]
+    fn test_extract_citations_keeps_non_citations() {
+        let answer = "RFC [7231] 发布于 [2014]，统计区间为 [2024-2025]。详见[文档](https://example.com/[1])和[2](https://example.com)。";
+        let citations = extract_citations(answer, 3);
+
+        assert_eq!(citations.answer, answer);
+        assert!(citations.cited.is_empty());
+        assert_eq!(citations.invalid, vec![7231, 2014, 2024, 2025]);
+    }
+
+    #[test]
+    fn test_extract_citations_skips_code() {
+        let answer = "用 `arr[0]` 和 `v[2]` 取值[1]：\n```rust\nlet x = v[2];\nlet y = m[1][2];\n```\n另见【2】";
+        let citations = extract_citations(answer, 2);
+
+        assert_eq!(
+            citations.answer,
+            "用 `arr[0]` 和 `v[2]` 取值[1]：\n```rust\nlet x = v[2];\nlet y = m[1][2];\n```\n另见[2]"
+        );
+        assert_eq!(citations.cited, vec![1, 2]);
+        assert!(citations.invalid.is_empty());
+
+        // 未闭合的代码块延续到末尾
+        let citations = extract_citations("示例[1]\n```\nlet a = b[1];", 1);
+        assert_eq!(citations.answer, "示例[1]\n```\nlet a = b[1];");
+        assert_eq!(citations.cited, vec![1]);
+    }
+
+    #[test]
+    fn test_strip_citations() {
+        assert_eq!(
+            strip_citations("年假五天[1][2]，始于 [2019] 年，见 `a[1]`。", 2),
+            "年假五天，始于 [2019] 年，见 `a[1]`。"
+        );
+        assert_eq!(strip_citations("没有来源 [1]", 0), "没有来源 [1]");
+    }
+}
@@ -0,0 +1,767 @@
+//! 源代码分块
+//!
+//! 不依赖完整的语法解析器，用轻量的启发式规则识别声明边界：花括号语言按括号深度
+//! 划分函数、类型、impl 等顶层条目，Python 按缩进划分。较小的相邻条目合并为一块，
+//! 超出块大小的类 / impl 再按成员拆分，每个块记录所属的符号名。
+
+use std::ops::Range;
+
+use super::text_splitter::{TextChunk, TextSplitter};
+use super::tokenizer::ChunkUnit;
+
+/// 编程语言的分块规则
+#[derive(Debug)]
+pub struct Language {
+    /// 语言名称（同时用作 Markdown 代码块的语言标记）
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    /// 按缩进划分代码块（Python），否则按括号深度
+    indented: bool,
+    line_comments: &'static [&'static str],
+    /// 声明关键字
+    declarations: &'static [&'static str],
+    /// 过大时可以按成员继续拆分的声明
+    containers: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "rust",
+        extensions: &["rs"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["fn", "struct", "enum", "trait", "impl", "mod", "type", "const", "static", "union", "macro_rules!"],
+        containers: &["impl", "trait", "mod"],
+    },
+    Language {
+        name: "typescript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["function", "function*", "class", "interface", "type", "enum", "namespace", "module", "const", "let", "var"],
+        containers: &["class", "namespace", "module"],
+    },
+    Language {
+        name: "javascript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["function", "function*", "class", "const", "let", "var"],
+        containers: &["class"],
+    },
+    Language {
+        name: "python",
+        extensions: &["py", "pyi"],
+        indented: true,
+        line_comments: &["#"],
+        declarations: &["def", "class"],
+        containers: &["class"],
+    },
+    Language {
+        name: "go",
+        extensions: &["go"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["func", "type", "var", "const"],
+        containers: &[],
+    },
+    Language {
+        name: "java",
+        extensions: &["java"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["class", "interface", "enum", "record", "@interface"],
+        containers: &["class", "interface", "enum", "record"],
+    },
+    Language {
+        name: "kotlin",
+        extensions: &["kt", "kts"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["fun", "class", "interface", "object", "val", "var", "typealias"],
+        containers: &["class", "interface", "object"],
+    },
+    Language {
+        name: "csharp",
+        extensions: &["cs"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["class", "interface", "struct", "enum", "record", "namespace", "delegate"],
+        containers: &["class", "interface", "struct", "record", "namespace"],
+    },
+    Language {
+        name: "swift",
+        extensions: &["swift"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["func", "class", "struct", "enum", "protocol", "extension", "var", "let", "typealias"],
+        containers: &["class", "struct", "enum", "protocol", "extension"],
+    },
+    Language {
+        name: "c",
+        extensions: &["c", "h"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["struct", "enum", "union", "typedef"],
+        containers: &[],
+    },
+    Language {
+        name: "cpp",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        indented: false,
+        line_comments: &["//"],
+        declarations: &["class", "struct", "enum", "union", "namespace", "typedef"],
+        containers: &["class", "struct", "namespace"],
+    },
+    Language {
+        name: "php",
+        extensions: &["php"],
+        indented: false,
+        line_comments: &["//", "#"],
+        declarations: &["function", "class", "interface", "trait", "enum"],
+        containers: &["class", "interface", "trait"],
+    },
+];
+
+/// 声明前可能出现的修饰符
+const MODIFIERS: &[&str] = &[
+    "pub", "export", "default", "async", "unsafe", "extern", "\"C\"", "public", "private", "protected",
+    "internal", "static", "final", "abstract", "sealed", "override", "virtual", "inline", "open", "data",
+    "suspend", "declare", "readonly", "partial", "fileprivate", "mutating", "constexpr", "template",
+];
+
+/// 形如 `name(` 但不是函数定义的关键字
+const CONTROL_KEYWORDS: &[&str] = &["if", "for", "while", "switch", "match", "return", "catch", "sizeof", "else"];
+
+/// 根据文件扩展名识别语言
+pub fn language_for_extension(extension: &str) -> Option<&'static Language> {
+    let extension = extension.to_ascii_lowercase();
+    LANGUAGES.iter().find(|l| l.extensions.contains(&extension.as_str()))
+}
+
+/// 根据语言名称查找
+pub fn language_by_name(name: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.name.eq_ignore_ascii_case(name))
+}
+
+/// 逐行扫描得到的信息
+#[derive(Debug, Clone, Copy, Default)]
+struct LineInfo {
+    /// 行首的括号深度
+    depth: usize,
+    /// 行尾的括号深度
+    depth_end: usize,
+    indent: usize,
+    blank: bool,
+    /// 注释、属性或装饰器行（归属到其后的声明）
+    leading: bool,
+    /// 行首位于多行注释或字符串内部
+    continuation: bool,
+    /// 行内最后一个有效字符（不含注释）
+    last: Option<char>,
+}
+
+/// 代码条目：一个声明（含其前的注释和属性）或一段零散语句
+struct Item {
+    lines: Range<usize>,
+    symbol: Option<String>,
+    /// 可按成员拆分时，成员所在的行范围及其基准深度（缩进）
+    body: Option<(Range<usize>, usize)>,
+}
+
+/// 分块的最小单位：一组连续的行及其符号
+struct Unit {
+    lines: Range<usize>,
+    symbol: Option<String>,
+}
+
+/// 源代码分块器
+pub struct CodeSplitter {
+    chunk_size: usize,
+    language: &'static Language,
+    unit: ChunkUnit,
+}
+
+impl CodeSplitter {
+    pub fn new(chunk_size: usize, language: &'static Language) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            language,
+            unit: ChunkUnit::Chars,
+        }
+    }
+
+    /// 设置块大小的计量单位（默认按字符）
+    pub fn with_unit(mut self, unit: ChunkUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// 按声明边界分块，并记录每个块的符号和字符范围
+    pub fn split(&self, text: &str) -> Vec<TextChunk> {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let infos = self.scan_lines(&lines);
+
+        // 每行在原文中的起止字符偏移（不含换行符）
+        let mut line_spans = Vec::with_capacity(lines.len());
+        let mut offset = 0;
+        for line in &lines {
+            let len = line.chars().count();
+            line_spans.push(offset..offset + len);
+            offset += len + 1;
+        }
+
+        let units = self.units(&lines, &infos, 0..lines.len(), 0, None);
+        let chars: Vec<char> = text.chars().collect();
+        let mut chunks = Vec::new();
+        let mut group: Vec<Unit> = Vec::new();
+
+        for unit in units {
+            let span = line_spans[unit.lines.start].start..line_spans[unit.lines.end - 1].end;
+            if self.unit.measure_chars(&chars[span.clone()]) > self.chunk_size {
+                self.flush(&mut group, &line_spans, &chars, &mut chunks);
+                self.split_oversized(&unit, &line_spans, &chars, &mut chunks);
+                continue;
+            }
+            if let Some(first) = group.first() {
+                if self.unit.measure_chars(&chars[line_spans[first.lines.start].start..span.end]) > self.chunk_size {
+                    self.flush(&mut group, &line_spans, &chars, &mut chunks);
+                }
+            }
+            group.push(unit);
+        }
+        self.flush(&mut group, &line_spans, &chars, &mut chunks);
+
+        chunks
+    }
+
+    /// 将相邻的小条目合并为一个块
+    fn flush(&self, group: &mut Vec<Unit>, spans: &[Range<usize>], chars: &[char], chunks: &mut Vec<TextChunk>) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            return;
+        };
+        let start = spans[first.lines.start].start;
+        let end = spans[last.lines.end - 1].end;
+
+        let symbols: Vec<&str> = group.iter().filter_map(|u| u.symbol.as_deref()).collect();
+        // 容器的头部与其成员合并时，只保留更具体的成员符号
+        let mut kept: Vec<&str> = Vec::new();
+        for symbol in &symbols {
+            let is_prefix = symbols.iter().any(|other| {
+                other.len() > symbol.len() && other.starts_with(symbol) && other[symbol.len()..].starts_with(" › ")
+            });
+            if !is_prefix && !kept.contains(symbol) {
+                kept.push(symbol);
+            }
+        }
+
+        let content: String = chars[start..end].iter().collect();
+        if !content.trim().is_empty() {
+            chunks.push(TextChunk {
+                content,
+                start,
+                end,
+                symbol: (!kept.is_empty()).then(|| kept.join(", ")),
+                ..Default::default()
+            });
+        }
+        group.clear();
+    }
+
+    /// 无法按成员拆分的超长条目按行切分，单行过长时按固定长度切分
+    fn split_oversized(&self, unit: &Unit, spans: &[Range<usize>], chars: &[char], chunks: &mut Vec<TextChunk>) {
+        let mut window: Option<Range<usize>> = None;
+        let push = |range: Range<usize>, chunks: &mut Vec<TextChunk>| {
+            let content: String = chars[range.clone()].iter().collect();
+            if !content.trim().is_empty() {
+                chunks.push(TextChunk {
+                    content,
+                    start: range.start,
+                    end: range.end,
+                    symbol: unit.symbol.clone(),
+                    ..Default::default()
+                });
+            }
+        };
+
+        for line in unit.lines.clone() {
+            let span = spans[line].clone();
+            if self.unit.measure_chars(&chars[span.clone()]) > self.chunk_size {
+                if let Some(range) = window.take() {
+                    push(range, chunks);
+                }
+                let line_text: String = chars[span.clone()].iter().collect();
+                let splitter = TextSplitter::new(self.chunk_size, 0).with_unit(self.unit);
+                chunks.extend(splitter.split_with_offsets(&line_text, span.start).into_iter().map(|mut chunk| {
+                    chunk.symbol = unit.symbol.clone();
+                    chunk
+                }));
+                continue;
+            }
+
+            window = match window.take() {
+                Some(range) if self.unit.measure_chars(&chars[range.start..span.end]) <= self.chunk_size => {
+                    Some(range.start..span.end)
+                }
+                Some(range) => {
+                    push(range, chunks);
+                    Some(span)
+                }
+                None => Some(span),
+            };
+        }
+        if let Some(range) = window {
+            push(range, chunks);
+        }
+    }
+
+    /// 将行范围划分为分块单位，过大的容器递归拆分为成员
+    fn units(
+        &self,
+        lines: &[&str],
+        infos: &[LineInfo],
+        range: Range<usize>,
+        base: usize,
+        parent: Option<&str>,
+    ) -> Vec<Unit> {
+        let items = if self.language.indented {
+            self.segment_indented(lines, infos, range, base)
+        } else {
+            self.segment_braces(lines, infos, range, base)
+        };
+
+        let mut units = Vec::new();
+        for item in items {
+            let symbol = match (parent, item.symbol) {
+                (Some(parent), Some(symbol)) => Some(format!("{} › {}", parent, symbol)),
+                (Some(parent), None) => Some(parent.to_string()),
+                (None, symbol) => symbol,
+            };
+            let len = self.unit.measure(&lines[item.lines.clone()].join("\n")) + 1;
+
+            match item.body {
+                Some((body, body_base)) if len > self.chunk_size && !body.is_empty() => {
+                    if item.lines.start < body.start {
+                        units.push(Unit { lines: item.lines.start..body.start, symbol: symbol.clone() });
+                    }
+                    let body_end = body.end;
+                    units.extend(self.units(lines, infos, body, body_base, symbol.as_deref()));
+                    if body_end < item.lines.end {
+                        units.push(Unit { lines: body_end..item.lines.end, symbol: None });
+                    }
+                }
+                _ => units.push(Unit { lines: item.lines, symbol }),
+            }
+        }
+        units
+    }
+
+    /// 花括号语言：在基准深度上划分条目
+    fn segment_braces(&self, lines: &[&str], infos: &[LineInfo], range: Range<usize>, base: usize) -> Vec<Item> {
+        let mut items = Vec::new();
+        // 当前条目：起始行、声明行
+        let mut current: Option<(usize, Option<usize>)> = None;
+
+        for i in range.clone() {
+            let info = infos[i];
+            if info.blank && current.is_none() {
+                continue;
+            }
+            let at_base = !info.continuation && info.depth == base && !info.blank;
+            let is_code = at_base && !info.leading;
+
+            // 没有结束符的零散语句（如 Go、Kotlin 的 import）之后遇到新的声明
+            if let Some((start, Some(_))) = current {
+                if is_code && self.declaration(lines[i]).is_some() {
+                    items.push(self.brace_item(lines, infos, start..i, base));
+                    current = None;
+                }
+            }
+
+            let entry = current.get_or_insert((i, None));
+            if is_code && entry.1.is_none() {
+                entry.1 = Some(i);
+            }
+
+            let preprocessor = !self.language.line_comments.contains(&"#")
+                && self.language.name != "rust"
+                && lines[i].trim_start().starts_with('#');
+            let terminated = info.depth_end == base
+                && !info.continuation
+                && (matches!(info.last, Some('}' | ';')) || preprocessor);
+            if entry.1.is_some() && terminated {
+                items.push(self.brace_item(lines, infos, entry.0..i + 1, base));
+                current = None;
+            }
+        }
+        if let Some((start, _)) = current {
+            items.push(self.brace_item(lines, infos, start..range.end, base));
+        }
+
+        items.retain(|item| !item.lines.is_empty());
+        items
+    }
+
+    fn brace_item(&self, lines: &[&str], infos: &[LineInfo], range: Range<usize>, base: usize) -> Item {
+        let range = trim_blank(infos, range);
+        let declaration = range.clone().find(|&i| !infos[i].blank && !infos[i].leading && !infos[i].continuation);
+        let opens_block = range.clone().any(|i| infos[i].depth_end > base);
+
+        let mut symbol = None;
+        let mut body = None;
+        if let Some(line) = declaration {
+            let found = self.declaration(lines[line]);
+            if let Some((keyword, _)) = &found {
+                // 成员从第一个以 `{` 结尾的行之后开始，到最后一行（右括号）之前结束
+                let open = range.clone()
+                    .skip_while(|&i| i < line)
+                    .find(|&i| infos[i].depth_end > base && lines[i].trim_end().ends_with('{'));
+                if self.language.containers.contains(keyword) && range.len() > 2 {
+                    if let Some(open) = open {
+                        body = Some((open + 1..range.end - 1, base + 1));
+                    }
+                }
+            }
+            symbol = found.map(|(_, symbol)| symbol).or_else(|| {
+                if opens_block { function_name(lines[line]) } else { None }
+            });
+        }
+
+        Item { lines: range, symbol, body }
+    }
+
+    /// Python：在基准缩进上划分条目
+    fn segment_indented(&self, lines: &[&str], infos: &[LineInfo], range: Range<usize>, base: usize) -> Vec<Item> {
+        let mut items = Vec::new();
+        let mut current: Option<(usize, Option<usize>)> = None;
+
+        for i in range.clone() {
+            let info = infos[i];
+            if info.blank {
+                continue;
+            }
+            let logical = !info.continuation && info.depth == 0;
+            if logical && info.indent <= base {
+                if let Some((start, Some(_))) = current {
+                    items.push(self.indented_item(lines, infos, start..i));
+                    current = None;
+                }
+            }
+
+            let entry = current.get_or_insert((i, None));
+            if logical && info.indent <= base && !info.leading && entry.1.is_none() {
+                entry.1 = Some(i);
+            }
+        }
+        if let Some((start, _)) = current {
+            items.push(self.indented_item(lines, infos, start..range.end));
+        }
+
+        items
+    }
+
+    fn indented_item(&self, lines: &[&str], infos: &[LineInfo], range: Range<usize>) -> Item {
+        let range = trim_blank(infos, range);
+        let declaration = range.clone().find(|&i| !infos[i].blank && !infos[i].leading && !infos[i].continuation);
+
+        let mut symbol = None;
+        let mut body = None;
+        if let Some(line) = declaration {
+            if let Some((keyword, name)) = self.declaration(lines[line]) {
+                if self.language.containers.contains(&keyword) {
+                    let indent = infos[line].indent;
+                    let first = (line + 1..range.end)
+                        .find(|&i| !infos[i].blank && !infos[i].continuation && infos[i].depth == 0 && infos[i].indent > indent);
+                    if let Some(first) = first {
+                        body = Some((first..range.end, infos[first].indent));
+                    }
+                }
+                symbol = Some(name);
+            }
+        }
+
+        Item { lines: range, symbol, body }
+    }
+
+    /// 识别声明行，返回关键字和符号名（例如 `fn parse`、`impl Display for Foo`）
+    fn declaration(&self, line: &str) -> Option<(&'static str, String)> {
+        let line = line.trim();
+        let mut rest = line;
+
+        loop {
+            let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let word = &rest[..word_end];
+            if word.is_empty() {
+                return None;
+            }
+
+            let keyword = self.language.declarations.iter().copied().find(|kw| {
+                word == *kw || word.strip_prefix(kw).is_some_and(|r| r.starts_with(['<', '(']))
+            });
+            if let Some(keyword) = keyword {
+                let after = rest[keyword.len()..].trim_start();
+                if matches!(keyword, "impl" | "extension") {
+                    let header = skip_generics(after);
+                    let header = header.split(['{']).next().unwrap_or(header);
+                    let header = header.split(" where ").next().unwrap_or(header);
+                    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+                    return Some((keyword, format!("{} {}", keyword, header).trim().to_string()));
+                }
+
+                // Go 方法的接收者：func (s *Server) Start()
+                let after = match after.strip_prefix('(') {
+                    Some(receiver) => receiver.split_once(')').map_or("", |(_, r)| r).trim_start(),
+                    None => after,
+                };
+                let name: String = after
+                    .trim_start_matches(['*', '&'])
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '!'))
+                    .collect();
+                let symbol = if name.is_empty() { keyword.to_string() } else { format!("{} {}", keyword, name) };
+                return Some((keyword, symbol));
+            }
+
+            let is_modifier = MODIFIERS.contains(&word)
+                || word.starts_with("pub(")
+                || word.starts_with("extern")
+                || (word.starts_with("template<") && word.ends_with('>'));
+            if !is_modifier {
+                return None;
+            }
+            rest = rest[word_end..].trim_start();
+        }
+    }
+
+    /// 逐行扫描括号深度、注释和字符串状态
+    fn scan_lines(&self, lines: &[&str]) -> Vec<LineInfo> {
+        let language = self.language;
+        let mut infos = Vec::with_capacity(lines.len());
+        let mut depth = 0usize;
+        let mut block_comment = false;
+        // 跨行字符串的结束标记
+        let mut open_string: Option<&'static str> = None;
+
+        for line in lines {
+            let trimmed = line.trim();
+            let continuation = block_comment || open_string.is_some();
+            let mut info = LineInfo {
+                depth,
+                indent: line.len() - line.trim_start().len(),
+                blank: trimmed.is_empty(),
+                continuation,
+                ..Default::default()
+            };
+            info.leading = !continuation
+                && (language.line_comments.iter().any(|c| trimmed.starts_with(c))
+                    || (!language.indented && trimmed.starts_with("/*"))
+                    || (language.name == "rust" && trimmed.starts_with("#["))
+                    || (trimmed.starts_with('@') && !trimmed.starts_with("@interface")));
+
+            let chars: Vec<char> = line.chars().collect();
+            let starts_at = |i: usize, pattern: &str| pattern.chars().enumerate().all(|(k, p)| chars.get(i + k) == Some(&p));
+            let mut i = 0;
+
+            while i < chars.len() {
+                let c = chars[i];
+                if block_comment {
+                    if starts_at(i, "*/") {
+                        block_comment = false;
+                        i += 2;
+                    } else {
+                        i += 1;
+                    }
+                    continue;
+                }
+                if let Some(end) = open_string {
+                    if starts_at(i, end) {
+                        open_string = None;
+                        info.last = end.chars().last();
+                        i += end.chars().count();
+                    } else {
+                        i += if c == '\\' && end != "\"#" { 2 } else { 1 };
+                    }
+                    continue;
+                }
+                if language.line_comments.iter().any(|p| starts_at(i, p)) {
+                    break;
+                }
+                if !language.indented && starts_at(i, "/*") {
+                    block_comment = true;
+                    i += 2;
+                    continue;
+                }
+
+                match c {
+                    '"' | '\'' if language.indented && (starts_at(i, "\"\"\"") || starts_at(i, "'''")) => {
+                        open_string = Some(if c == '"' { "\"\"\"" } else { "'''" });
+                        i += 3;
+                        continue;
+                    }
+                    'r' if language.name == "rust" && starts_at(i, "r#\"") => {
+                        open_string = Some("\"#");
+                        i += 3;
+                        continue;
+                    }
+                    // Rust 的生命周期标注不是字符字面量
+                    '\'' if language.name == "rust" && !(chars.get(i + 1) == Some(&'\\') || chars.get(i + 2) == Some(&'\'')) => {}
+                    '"' => open_string = Some("\""),
+                    '\'' => open_string = Some("'"),
+                    '`' => open_string = Some("`"),
+                    '{' | '(' | '[' => depth += 1,
+                    '}' | ')' | ']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                if !c.is_whitespace() {
+                    info.last = Some(c);
+                }
+                i += 1;
+            }
+
+            // 普通引号字符串不跨行（Rust 除外），未闭合时在行尾结束
+            if matches!(open_string, Some("'")) || (matches!(open_string, Some("\"")) && language.name != "rust") {
+                open_string = None;
+            }
+
+            info.depth_end = depth;
+            infos.push(info);
+        }
+
+        infos
+    }
+}
+
+/// 去掉范围首尾的空行
+fn trim_blank(infos: &[LineInfo], range: Range<usize>) -> Range<usize> {
+    let start = range.clone().find(|&i| !infos[i].blank).unwrap_or(range.end);
+    let end = range.clone().rev().find(|&i| !infos[i].blank).map_or(start, |i| i + 1);
+    start..end
+}
+
+/// 跳过开头的泛型参数，例如 `impl<'a, T>` 中的 `<'a, T>`
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// 没有声明关键字的函数定义（C、Java 方法等）：取 `(` 前的标识符
+fn function_name(line: &str) -> Option<String> {
+    let before = line[..line.find('(')?].trim_end();
+    let name: String = before
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '~'))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    (!name.is_empty() && !CONTROL_KEYWORDS.contains(&name.as_str())).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(chunks: &[TextChunk]) -> Vec<&str> {
+        chunks.iter().map(|c| c.symbol.as_deref().unwrap_or("")).collect()
+    }
+
+    #[test]
+    fn test_split_rust_by_items() {
+        let code = r#"use std::fmt;
+
+/// 解析配置
+#[inline]
+pub fn parse(input: &str) -> Result<Config, Error> {
+    let text = "{ not a brace";
+    input.lines().map(|l| { l.trim() }).collect()
+}
+
+impl<'a> fmt::Display for Config<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+
+    fn helper(&self) -> usize {
+        self.name.len() + self.name.len() + self.name.len()
+    }
+}
+"#;
+        let language = language_for_extension("rs").unwrap();
+        let chunks = CodeSplitter::new(200, language).split(code);
+
+        assert_eq!(symbols(&chunks), vec![
+            "fn parse",
+            "impl fmt::Display for Config<'a> › fn fmt",
+            "impl fmt::Display for Config<'a> › fn helper",
+        ]);
+        assert!(chunks[0].content.starts_with("use std::fmt;"));
+        assert!(chunks[0].content.ends_with("collect()\n}"));
+        assert!(chunks[2].content.ends_with("}\n}"));
+        for chunk in &chunks {
+            let expected: String = code.chars().skip(chunk.start).take(chunk.end - chunk.start).collect();
+            assert_eq!(chunk.content, expected);
+        }
+    }
+
+    #[test]
+    fn test_split_python_class_methods() {
+        let code = r#"import os
+
+
+class Store:
+    """存储。
+
+    包含 { 符号的文档字符串
+    """
+
+    def get(self, key):
+        return self.items.get(key, os.environ.get(key))
+
+    @property
+    def size(self):
+        return len(self.items) + len(self.items) + len(self.items)
+
+
+def main():
+    print(Store().size)
+"#;
+        let language = language_for_extension("py").unwrap();
+        let chunks = CodeSplitter::new(120, language).split(code);
+
+        assert_eq!(symbols(&chunks), vec![
+            "class Store",
+            "class Store › def get",
+            "class Store › def size",
+            "def main",
+        ]);
+        assert!(chunks[2].content.trim_start().starts_with("@property"));
+    }
+
+    #[test]
+    fn test_typescript_symbols() {
+        let language = language_for_extension("ts").unwrap();
+        let splitter = CodeSplitter::new(800, language);
+
+        assert_eq!(splitter.declaration("export default class App extends Vue {"), Some(("class", "class App".to_string())));
+        assert_eq!(splitter.declaration("export const useStore = () => {"), Some(("const", "const useStore".to_string())));
+        assert_eq!(splitter.declaration("return foo;"), None);
+        assert_eq!(function_name("  public int add(int a, int b) {"), Some("add".to_string()));
+        assert_eq!(function_name("if (x) {"), None);
+    }
+}
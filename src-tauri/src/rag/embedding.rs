@@ -1,150 +1,445 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{join_endpoint, EmbeddingProviderKind, RAGConfig};
+
+/// Embedding 提供方
+///
+/// 不同的向量化服务（DashScope、OpenAI 兼容接口、测试桩）实现该 trait，
+/// 由 `EmbeddingService` 统一负责分批和并发。
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// 提供方名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 使用的模型名称
+    fn model(&self) -> &str;
+
+    /// 单次请求允许的最大文本数
+    fn max_batch_size(&self) -> usize;
+
+    /// 批量生成向量，返回顺序与输入一致
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// 创建带超时的 HTTP 客户端
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+// ==================== DashScope ====================
 
 #[derive(Debug, Serialize)]
-struct EmbeddingRequest {
+struct DashScopeRequest {
     model: String,
-    input: InputData,
+    input: DashScopeInput,
 }
 
 #[derive(Debug, Serialize)]
-struct InputData {
+struct DashScopeInput {
     texts: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
-struct EmbeddingResponse {
-    output: Output,
+struct DashScopeResponse {
+    output: DashScopeOutput,
     #[allow(dead_code)]
-    usage: Option<Usage>,
+    usage: Option<DashScopeUsage>,
 }
 
 #[derive(Debug, Deserialize)]
-struct Output {
-    embeddings: Vec<EmbeddingData>,
+struct DashScopeOutput {
+    embeddings: Vec<DashScopeEmbeddingData>,
 }
 
 #[derive(Debug, Deserialize)]
-struct EmbeddingData {
+struct DashScopeEmbeddingData {
     embedding: Vec<f32>,
-    #[allow(dead_code)]
     text_index: usize,
 }
 
 #[derive(Debug, Deserialize)]
-struct Usage {
+struct DashScopeUsage {
     #[allow(dead_code)]
     total_tokens: usize,
 }
 
-/// Embedding 服务
-pub struct EmbeddingService {
+/// 阿里云 DashScope 通用文本向量
+pub struct DashScopeEmbedding {
     client: Client,
     api_key: String,
     model: String,
 }
 
-impl EmbeddingService {
+impl DashScopeEmbedding {
+    const URL: &'static str =
+        "https://dashscope.aliyuncs.com/api/v1/services/embeddings/text-embedding/text-embedding";
+
     pub fn new(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: http_client(),
             api_key,
             model,
         }
     }
-    
-    pub fn api_key(&self) -> &String {
-        &self.api_key
+}
+
+#[async_trait]
+impl EmbeddingProvider for DashScopeEmbedding {
+    fn name(&self) -> &str {
+        "dashscope"
     }
-    
-    pub fn model(&self) -> &String {
+
+    fn model(&self) -> &str {
         &self.model
     }
-    
-    /// 将文本转换为向量
-    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let request = EmbeddingRequest {
+
+    fn max_batch_size(&self) -> usize {
+        // 通义千问 API 限制每批最多 25 条
+        25
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let request = DashScopeRequest {
             model: self.model.clone(),
-            input: InputData {
-                texts: vec![text.to_string()],
+            input: DashScopeInput {
+                texts: texts.to_vec(),
             },
         };
-        
+
         let response = self.client
-            .post("https://dashscope.aliyuncs.com/api/v1/services/embeddings/text-embedding/text-embedding")
+            .post(Self::URL)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&request)
             .send()
             .await?;
-        
+
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await?;
             anyhow::bail!("Embedding API 请求失败: {} - {}", status, text);
         }
-        
-        let result: EmbeddingResponse = response.json().await?;
-        
-        if result.output.embeddings.is_empty() {
-            anyhow::bail!("Embedding API 返回空向量");
+
+        let result: DashScopeResponse = response.json().await?;
+        let mut embeddings = result.output.embeddings;
+        embeddings.sort_by_key(|e| e.text_index);
+
+        Ok(embeddings.into_iter().map(|e| e.embedding).collect())
+    }
+}
+
+// ==================== OpenAI 兼容接口 ====================
+
+#[derive(Debug, Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiResponse {
+    data: Vec<OpenAiEmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+/// OpenAI 兼容的 `/v1/embeddings` 接口（OpenAI、llama.cpp、Ollama、vLLM 等）
+pub struct OpenAiEmbedding {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiEmbedding {
+    /// `base_url` 形如 `http://localhost:11434/v1`，也可以直接填写完整的 `/embeddings` 地址
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: http_client(),
+            endpoint: join_endpoint(base_url, "/embeddings"),
+            api_key: api_key.filter(|k| !k.is_empty()),
+            model,
         }
-        
-        Ok(result.output.embeddings[0].embedding.clone())
     }
-    
-    /// 批量生成向量
-    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(Vec::new());
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbedding {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_batch_size(&self) -> usize {
+        32
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut request = self.client
+            .post(&self.endpoint)
+            .json(&OpenAiRequest {
+                model: &self.model,
+                input: texts,
+            });
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
         }
-        
-        let request = EmbeddingRequest {
-            model: self.model.clone(),
-            input: InputData {
-                texts: texts.to_vec(),
-            },
-        };
-        
-        let response = self.client
-            .post("https://dashscope.aliyuncs.com/api/v1/services/embeddings/text-embedding/text-embedding")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-        
+
+        let response = request.send().await?;
+
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await?;
-            anyhow::bail!("Embedding API 批量请求失败: {} - {}", status, text);
+            anyhow::bail!("Embedding API 请求失败: {} - {}", status, text);
+        }
+
+        let result: OpenAiResponse = response.json().await?;
+        let mut data = result.data;
+        data.sort_by_key(|d| d.index);
+
+        Ok(data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+// ==================== 测试桩 ====================
+
+/// 确定性的本地向量（字符 n-gram 哈希），不依赖网络，用于测试和离线调试
+pub struct StubEmbedding {
+    dimension: usize,
+}
+
+impl StubEmbedding {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+        }
+    }
+
+    /// 同步计算单条文本的向量
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+        let chars: Vec<char> = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+
+        // 单字与双字特征同时计入
+        for n in 1..=2 {
+            for gram in chars.windows(n) {
+                let bucket = fnv1a(gram) as usize % self.dimension;
+                vector[bucket] += 1.0;
+            }
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+
+        vector
+    }
+}
+
+/// FNV-1a 哈希（跨平台、跨版本稳定）
+fn fnv1a(chars: &[char]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for c in chars {
+        for byte in (*c as u32).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+#[async_trait]
+impl EmbeddingProvider for StubEmbedding {
+    fn name(&self) -> &str {
+        "stub"
+    }
+
+    fn model(&self) -> &str {
+        "stub"
+    }
+
+    fn max_batch_size(&self) -> usize {
+        256
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_text(t)).collect())
+    }
+}
+
+// ==================== Embedding 服务 ====================
+
+/// 同时进行的向量化请求数上限
+const MAX_CONCURRENT_BATCHES: usize = 10;
+
+/// Embedding 服务
+pub struct EmbeddingService {
+    provider: Arc<dyn EmbeddingProvider>,
+}
+
+impl EmbeddingService {
+    /// 使用 DashScope 创建服务
+    pub fn new(api_key: String, model: String) -> Self {
+        Self::with_provider(Arc::new(DashScopeEmbedding::new(api_key, model)))
+    }
+
+    pub fn with_provider(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// 根据 RAG 配置选择提供方
+    pub fn from_config(config: &RAGConfig, api_key: &str) -> Result<Self> {
+        let api_key = config
+            .embedding_api_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .unwrap_or_else(|| api_key.to_string());
+
+        let provider: Arc<dyn EmbeddingProvider> = match config.embedding_provider {
+            EmbeddingProviderKind::DashScope => {
+                Arc::new(DashScopeEmbedding::new(api_key, config.embedding_model.clone()))
+            }
+            EmbeddingProviderKind::OpenAi => {
+                let base_url = config
+                    .embedding_base_url
+                    .as_deref()
+                    .filter(|u| !u.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("请配置 Embedding 服务地址"))?;
+                Arc::new(OpenAiEmbedding::new(
+                    base_url,
+                    Some(api_key),
+                    config.embedding_model.clone(),
+                ))
+            }
+            EmbeddingProviderKind::Stub => Arc::new(StubEmbedding::new(256)),
+        };
+
+        Ok(Self::with_provider(provider))
+    }
+
+    pub fn provider(&self) -> &Arc<dyn EmbeddingProvider> {
+        &self.provider
+    }
+
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    /// 将文本转换为向量
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut embeddings = self.provider.embed_batch(&[text.to_string()]).await?;
+
+        match embeddings.pop() {
+            Some(embedding) if !embedding.is_empty() => Ok(embedding),
+            _ => anyhow::bail!("Embedding API 返回空向量"),
+        }
+    }
+
+    /// 批量生成向量（按提供方限制自动分批，并发请求）
+    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let batch_size = self.provider.max_batch_size().max(1);
+        let total_batches = texts.len().div_ceil(batch_size);
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_BATCHES));
+        let mut tasks = Vec::with_capacity(total_batches);
+
+        for (batch_idx, batch) in texts.chunks(batch_size).enumerate() {
+            let provider = self.provider.clone();
+            let semaphore = semaphore.clone();
+            let batch_texts = batch.to_vec();
+            let batch_num = batch_idx + 1;
+
+            tasks.push(tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                println!("🚀 正在处理批次 {}/{} ({} 个块)...", batch_num, total_batches, batch_texts.len());
+
+                let embeddings = provider
+                    .embed_batch(&batch_texts)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("批次 {} 向量化失败: {}", batch_num, e))?;
+
+                if embeddings.len() != batch_texts.len() || embeddings.iter().any(|e| e.is_empty()) {
+                    anyhow::bail!("批次 {} 返回的向量数量或内容不正确", batch_num);
+                }
+
+                println!("✅ 批次 {}/{} 完成", batch_num, total_batches);
+                Ok::<_, anyhow::Error>(embeddings)
+            }));
+        }
+
+        // 按提交顺序收集，保证与输入一一对应
+        let mut all_embeddings = Vec::with_capacity(texts.len());
+        for task in tasks {
+            all_embeddings.extend(task.await??);
         }
-        
-        let result: EmbeddingResponse = response.json().await?;
-        
-        Ok(result.output.embeddings
-            .into_iter()
-            .map(|e| e.embedding)
-            .collect())
+
+        Ok(all_embeddings)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::rag::vector_store::cosine_similarity;
+
     #[tokio::test]
     #[ignore] // 需要 API Key 才能运行
     async fn test_embed() {
         let api_key = std::env::var("QWEN_API_KEY").expect("需要设置 QWEN_API_KEY 环境变量");
         let service = EmbeddingService::new(api_key, "text-embedding-v2".to_string());
-        
+
         let embedding = service.embed("你好世界").await.unwrap();
-        
+
         assert!(!embedding.is_empty());
         println!("向量维度: {}", embedding.len());
         println!("前5个值: {:?}", &embedding[..5]);
     }
-}
 
+    #[tokio::test]
+    async fn test_stub_embedding() {
+        let service = EmbeddingService::with_provider(Arc::new(StubEmbedding::new(64)));
+
+        let texts: Vec<String> = vec!["苹果很好吃".into(), "苹果很好吃".into(), "今天天气很好".into()];
+        let embeddings = service.embed_batch(&texts).await.unwrap();
+
+        assert_eq!(embeddings.len(), 3);
+        assert_eq!(embeddings[0].len(), 64);
+        assert_eq!(embeddings[0], embeddings[1]);
+
+        let query = service.embed("苹果好吃吗").await.unwrap();
+        assert!(cosine_similarity(&query, &embeddings[0]) > cosine_similarity(&query, &embeddings[2]));
+    }
+
+    #[test]
+    fn test_openai_endpoint() {
+        let provider = OpenAiEmbedding::new("http://localhost:11434/v1/", None, "bge-m3".into());
+        assert_eq!(provider.endpoint, "http://localhost:11434/v1/embeddings");
+
+        let provider = OpenAiEmbedding::new("http://localhost:8080/v1/embeddings", None, "bge-m3".into());
+        assert_eq!(provider.endpoint, "http://localhost:8080/v1/embeddings");
+    }
+}
@@ -0,0 +1,452 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+/// 层数上限，防止极端随机值造成过高的层级
+const MAX_LEVEL: usize = 16;
+
+/// 提供节点向量的存储（向量需已归一化）
+pub trait VectorSource {
+    fn vector(&self, id: u32) -> &[f32];
+}
+
+/// HNSW 参数
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HnswParams {
+    /// 每层保留的邻居数（第 0 层为 2M）
+    pub m: usize,
+    /// 构建时的候选集大小
+    pub ef_construction: usize,
+    /// 查询时的候选集大小
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    /// neighbors[level] 为该节点在对应层的邻居
+    neighbors: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// 按距离排序的候选节点
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: u32,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+/// 归一化向量之间的余弦距离
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 1.0;
+    }
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+/// 分层可导航小世界图（HNSW）近似最近邻索引
+///
+/// 节点 ID 与向量存储中的槽位一一对应；删除只做标记，
+/// 被删除的节点仍参与导航，由上层在墓碑过多时重建索引。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    params: HnswParams,
+    nodes: Vec<Node>,
+    entry_point: Option<u32>,
+    max_level: usize,
+    deleted_count: usize,
+    rng_state: u64,
+}
+
+impl HnswIndex {
+    pub fn new(params: HnswParams) -> Self {
+        Self {
+            params,
+            nodes: Vec::new(),
+            entry_point: None,
+            max_level: 0,
+            deleted_count: 0,
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// 节点总数（含已删除）
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// 已删除（墓碑）节点数
+    pub fn deleted_count(&self) -> usize {
+        self.deleted_count
+    }
+
+    pub fn is_deleted(&self, id: u32) -> bool {
+        self.nodes.get(id as usize).is_none_or(|n| n.deleted)
+    }
+
+    /// 标记删除
+    pub fn delete(&mut self, id: u32) {
+        if let Some(node) = self.nodes.get_mut(id as usize) {
+            if !node.deleted {
+                node.deleted = true;
+                self.deleted_count += 1;
+            }
+        }
+    }
+
+    /// 插入节点，`id` 必须等于当前节点数
+    pub fn insert<S: VectorSource + ?Sized>(&mut self, id: u32, source: &S) {
+        assert_eq!(id as usize, self.nodes.len(), "HNSW 节点 ID 必须连续");
+
+        let level = self.random_level();
+        self.nodes.push(Node {
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+
+        let Some(mut entry) = self.entry_point else {
+            self.entry_point = Some(id);
+            self.max_level = level;
+            return;
+        };
+
+        let query = source.vector(id);
+        let mut entry_distance = distance(query, source.vector(entry));
+
+        // 在高于新节点层级的各层贪心下降
+        for layer in (level + 1..=self.max_level).rev() {
+            (entry, entry_distance) = self.greedy_closest(query, entry, entry_distance, layer, source);
+        }
+
+        let mut entry_points = vec![Candidate {
+            distance: entry_distance,
+            id: entry,
+        }];
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let candidates = self.search_layer(query, &entry_points, self.params.ef_construction, layer, source);
+            let neighbors = self.select_neighbors(&candidates, self.params.m, source);
+            let max_connections = self.max_connections(layer);
+
+            for &neighbor in &neighbors {
+                let list = &mut self.nodes[neighbor as usize].neighbors[layer];
+                list.push(id);
+                if list.len() > max_connections {
+                    self.shrink_neighbors(neighbor, layer, max_connections, source);
+                }
+            }
+            self.nodes[id as usize].neighbors[layer] = neighbors;
+
+            entry_points = candidates;
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(id);
+        }
+    }
+
+    /// 查询最近的 `k` 个未删除节点，返回 (节点 ID, 余弦相似度)，按相似度降序
+    pub fn search<S: VectorSource + ?Sized>(&self, query: &[f32], k: usize, source: &S) -> Vec<(u32, f32)> {
+        let Some(mut entry) = self.entry_point else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut entry_distance = distance(query, source.vector(entry));
+        for layer in (1..=self.max_level).rev() {
+            (entry, entry_distance) = self.greedy_closest(query, entry, entry_distance, layer, source);
+        }
+        let entry_points = [Candidate {
+            distance: entry_distance,
+            id: entry,
+        }];
+
+        // 墓碑会占用候选名额，结果不足时扩大候选集重试
+        let live = self.nodes.len() - self.deleted_count;
+        let wanted = k.min(live);
+        let mut ef = self.params.ef_search.max(k);
+        loop {
+            let results: Vec<(u32, f32)> = self
+                .search_layer(query, &entry_points, ef, 0, source)
+                .into_iter()
+                .filter(|c| !self.nodes[c.id as usize].deleted)
+                .take(k)
+                .map(|c| (c.id, 1.0 - c.distance))
+                .collect();
+
+            if results.len() >= wanted || ef >= self.nodes.len() {
+                return results;
+            }
+            ef = (ef * 2).min(self.nodes.len());
+        }
+    }
+
+    fn max_connections(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    /// 指数分布的随机层级（xorshift，保证可复现）
+    fn random_level(&mut self) -> usize {
+        let mut x = self.rng_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng_state = x;
+
+        let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        let uniform = (bits as f64 + 0.5) / (1u64 << 53) as f64;
+        let ml = 1.0 / (self.params.m.max(2) as f64).ln();
+
+        ((-uniform.ln() * ml).floor() as usize).min(MAX_LEVEL)
+    }
+
+    /// 单层贪心搜索最近节点
+    fn greedy_closest<S: VectorSource + ?Sized>(
+        &self,
+        query: &[f32],
+        mut entry: u32,
+        mut entry_distance: f32,
+        layer: usize,
+        source: &S,
+    ) -> (u32, f32) {
+        loop {
+            let mut improved = false;
+            for &neighbor in self.neighbors(entry, layer) {
+                let d = distance(query, source.vector(neighbor));
+                if d < entry_distance {
+                    entry = neighbor;
+                    entry_distance = d;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return (entry, entry_distance);
+            }
+        }
+    }
+
+    /// 单层束搜索，返回按距离升序排列的至多 `ef` 个候选
+    fn search_layer<S: VectorSource + ?Sized>(
+        &self,
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+        source: &S,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().map(|c| c.id).collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = entry_points.iter().copied().map(Reverse).collect();
+        let mut results: BinaryHeap<Candidate> = entry_points.iter().copied().collect();
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            let worst = results.peek().map_or(f32::INFINITY, |c| c.distance);
+            if current.distance > worst && results.len() >= ef {
+                break;
+            }
+
+            for &neighbor in self.neighbors(current.id, layer) {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let d = distance(query, source.vector(neighbor));
+                let worst = results.peek().map_or(f32::INFINITY, |c| c.distance);
+                if results.len() < ef || d < worst {
+                    let candidate = Candidate {
+                        distance: d,
+                        id: neighbor,
+                    };
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    fn neighbors(&self, id: u32, layer: usize) -> &[u32] {
+        self.nodes[id as usize]
+            .neighbors
+            .get(layer)
+            .map_or(&[], |n| n.as_slice())
+    }
+
+    /// 启发式选邻：优先保留彼此分散的邻居，不足时用被裁掉的近邻补齐
+    fn select_neighbors<S: VectorSource + ?Sized>(
+        &self,
+        candidates: &[Candidate],
+        m: usize,
+        source: &S,
+    ) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut pruned = Vec::new();
+
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = source.vector(candidate.id);
+            let diverse = selected
+                .iter()
+                .all(|&s| candidate.distance < distance(vector, source.vector(s)));
+
+            if diverse {
+                selected.push(candidate.id);
+            } else {
+                pruned.push(candidate.id);
+            }
+        }
+
+        for id in pruned {
+            if selected.len() >= m {
+                break;
+            }
+            selected.push(id);
+        }
+
+        selected
+    }
+
+    /// 邻居过多时按启发式裁剪
+    fn shrink_neighbors<S: VectorSource + ?Sized>(
+        &mut self,
+        id: u32,
+        layer: usize,
+        max_connections: usize,
+        source: &S,
+    ) {
+        let vector = source.vector(id);
+        let mut candidates: Vec<Candidate> = self.nodes[id as usize].neighbors[layer]
+            .iter()
+            .map(|&n| Candidate {
+                distance: distance(vector, source.vector(n)),
+                id: n,
+            })
+            .collect();
+        candidates.sort();
+
+        self.nodes[id as usize].neighbors[layer] = self.select_neighbors(&candidates, max_connections, source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Vectors(Vec<Vec<f32>>);
+
+    impl VectorSource for Vectors {
+        fn vector(&self, id: u32) -> &[f32] {
+            &self.0[id as usize]
+        }
+    }
+
+    /// 生成可复现的随机单位向量
+    fn random_vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                let mut v: Vec<f32> = (0..dimension)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / u32::MAX as f32) - 0.25
+                    })
+                    .collect();
+                let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+                v.iter_mut().for_each(|x| *x /= norm);
+                v
+            })
+            .collect()
+    }
+
+    fn brute_force(vectors: &[Vec<f32>], query: &[f32], k: usize, skip: &HashSet<u32>) -> Vec<u32> {
+        let mut scored: Vec<(u32, f32)> = vectors
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !skip.contains(&(*i as u32)))
+            .map(|(i, v)| (i as u32, distance(query, v)))
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.into_iter().take(k).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn test_hnsw_recall() {
+        let vectors = Vectors(random_vectors(2000, 32));
+        let mut index = HnswIndex::new(HnswParams::default());
+        for id in 0..vectors.0.len() as u32 {
+            index.insert(id, &vectors);
+        }
+
+        let queries = random_vectors(2050, 32).split_off(2000);
+        let mut hits = 0;
+        for query in &queries {
+            let expected = brute_force(&vectors.0, query, 10, &HashSet::new());
+            let found: HashSet<u32> = index.search(query, 10, &vectors).into_iter().map(|(id, _)| id).collect();
+            hits += expected.iter().filter(|id| found.contains(id)).count();
+        }
+
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall > 0.9, "召回率过低: {}", recall);
+    }
+
+    #[test]
+    fn test_hnsw_delete_and_serialize() {
+        let vectors = Vectors(random_vectors(300, 16));
+        let mut index = HnswIndex::new(HnswParams::default());
+        for id in 0..vectors.0.len() as u32 {
+            index.insert(id, &vectors);
+        }
+
+        // 删除后查询自身不会再命中
+        index.delete(7);
+        let results = index.search(&vectors.0[7], 5, &vectors);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(id, _)| *id != 7));
+        assert_eq!(index.deleted_count(), 1);
+
+        let bytes = bincode::serialize(&index).unwrap();
+        let restored: HnswIndex = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(restored.search(&vectors.0[3], 5, &vectors), index.search(&vectors.0[3], 5, &vectors));
+    }
+}
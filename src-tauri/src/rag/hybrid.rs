@@ -0,0 +1,63 @@
+//! 混合检索：倒数排名融合（Reciprocal Rank Fusion）
+//!
+//! 向量检索与关键词检索的分数量纲不同，RRF 只依赖各路结果中的名次，
+//! 文档得分为 Σ 1 / (k + rank)，同时出现在多路结果中的文档会排到前面。
+
+use std::collections::HashMap;
+
+/// RRF 平滑常数，取论文中的经验值
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// 融合多路排序结果（每路按相关度从高到低），返回按融合得分降序排列的 (ID, 得分)
+///
+/// 得分相同时保持首次出现的先后顺序。
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>], k: f32) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, (f32, usize)> = HashMap::new();
+    let mut order = 0;
+
+    for ranking in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            let entry = scores.entry(id.as_str()).or_insert_with(|| {
+                order += 1;
+                (0.0, order)
+            });
+            entry.0 += 1.0 / (k + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(&str, (f32, usize))> = scores.into_iter().collect();
+    fused.sort_by(|a, b| b.1 .0.total_cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+
+    fused.into_iter()
+        .map(|(id, (score, _))| (id.to_string(), score))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let vector = ids(&["a", "b", "c"]);
+        let keyword = ids(&["c", "d", "a"]);
+
+        let fused = reciprocal_rank_fusion(&[vector, keyword], DEFAULT_RRF_K);
+        let order: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+
+        // a、c 同时被两路命中，排在只被一路命中的 b、d 之前
+        assert_eq!(order, vec!["a", "c", "b", "d"]);
+        assert!((fused[0].1 - (1.0 / 61.0 + 1.0 / 63.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_single_list() {
+        let fused = reciprocal_rank_fusion(&[ids(&["x", "y"]), Vec::new()], DEFAULT_RRF_K);
+        assert_eq!(fused.len(), 2);
+        assert_eq!(fused[0].0, "x");
+    }
+}
@@ -0,0 +1,202 @@
+//! 关键词检索的分词与查询构建
+//!
+//! SQLite 自带的分词器不会切分连续的中文，这里预先在 Rust 中分词，
+//! 以空格连接后写入 FTS5：中文按单字和相邻双字切分，英文和数字按词切分，
+//! 并保留 `err_code`、`v2.3.1`、`gpt-4` 这类标识符的完整形式。
+
+use sqlx::SqliteConnection;
+use std::collections::HashSet;
+
+/// 是否为需要按字切分的中日韩字符
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{20000}'..='\u{2A6DF}')
+}
+
+/// 标识符内部允许出现的连接符
+fn is_joiner(c: char) -> bool {
+    matches!(c, '_' | '.' | '-')
+}
+
+#[derive(Default)]
+struct Tokenizer {
+    tokens: Vec<String>,
+    word: String,
+    cjk_run: Vec<char>,
+    for_query: bool,
+}
+
+impl Tokenizer {
+    fn flush_word(&mut self) {
+        let word = self.word.trim_end_matches(is_joiner).to_lowercase();
+        self.word.clear();
+        if word.is_empty() {
+            return;
+        }
+
+        // 带连接符的标识符同时保留各组成部分，方便只输入部分名称时命中
+        if word.contains(is_joiner) {
+            self.tokens.extend(
+                word.split(is_joiner)
+                    .filter(|part| !part.is_empty())
+                    .map(|part| part.to_string()),
+            );
+        }
+        self.tokens.push(word);
+    }
+
+    fn flush_cjk(&mut self) {
+        let run = std::mem::take(&mut self.cjk_run);
+
+        // 查询时只用双字，避免单字带来大量噪声；单个汉字时才退回单字
+        if !self.for_query || run.len() == 1 {
+            self.tokens.extend(run.iter().map(|c| c.to_string()));
+        }
+        self.tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+    }
+
+    fn run(mut self, text: &str) -> Vec<String> {
+        for c in text.chars() {
+            if is_cjk(c) {
+                self.flush_word();
+                self.cjk_run.push(c);
+            } else if c.is_alphanumeric() {
+                self.flush_cjk();
+                self.word.push(c);
+            } else if is_joiner(c) && !self.word.is_empty() {
+                self.word.push(c);
+            } else {
+                self.flush_word();
+                self.flush_cjk();
+            }
+        }
+        self.flush_word();
+        self.flush_cjk();
+
+        self.tokens
+    }
+}
+
+/// 文本分词（用于写入索引）
+pub fn tokenize(text: &str) -> Vec<String> {
+    Tokenizer::default().run(text)
+}
+
+/// 生成写入 FTS5 `terms` 列的文本
+pub fn index_terms(text: &str) -> String {
+    tokenize(text).join(" ")
+}
+
+/// 为文档块写入关键词索引：先在 `chunk_fts_map` 中分配整数键，再以该键作为 FTS5 的 rowid
+pub async fn index_chunk(conn: &mut SqliteConnection, chunk_id: &str, content: &str) -> sqlx::Result<()> {
+    let fts_rowid = sqlx::query("INSERT INTO chunk_fts_map (chunk_id) VALUES (?)")
+        .bind(chunk_id)
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+    sqlx::query("INSERT INTO chunks_fts (rowid, terms) VALUES (?, ?)")
+        .bind(fts_rowid)
+        .bind(index_terms(content))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// 将用户问题转换为 FTS5 MATCH 表达式（各词之间为 OR，交由 bm25 排序）
+///
+/// 问题中没有可检索的词时返回 None。
+pub fn match_query(text: &str) -> Option<String> {
+    let tokenizer = Tokenizer {
+        for_query: true,
+        ..Tokenizer::default()
+    };
+
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = tokenizer
+        .run(text)
+        .into_iter()
+        .filter(|t| seen.insert(t.clone()))
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[test]
+    fn test_tokenize_mixed_text() {
+        let tokens = tokenize("错误码 ERR_CONN_RESET 出现在 v2.3.1 版本。");
+
+        assert!(tokens.contains(&"错误".to_string()));
+        assert!(tokens.contains(&"误码".to_string()));
+        assert!(tokens.contains(&"码".to_string()));
+        assert!(tokens.contains(&"err_conn_reset".to_string()));
+        assert!(tokens.contains(&"conn".to_string()));
+        assert!(tokens.contains(&"v2.3.1".to_string()));
+        assert!(!tokens.iter().any(|t| t.ends_with('.')));
+    }
+
+    #[test]
+    fn test_match_query() {
+        assert_eq!(match_query("报销流程"), Some("\"报销\" OR \"销流\" OR \"流程\"".to_string()));
+        assert_eq!(match_query("？？"), None);
+        assert_eq!(match_query("gpt-4 \"quoted\""), Some("\"gpt\" OR \"4\" OR \"gpt-4\" OR \"quoted\"".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_fts5_search() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query("CREATE VIRTUAL TABLE chunks_fts USING fts5(terms, tokenize = \"unicode61 tokenchars '._-'\")")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE chunk_fts_map (fts_rowid INTEGER PRIMARY KEY, chunk_id TEXT NOT NULL UNIQUE)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let texts = [
+            "出差报销流程：先提交申请，再上传发票。",
+            "服务返回 ERR_CONN_RESET 时请检查代理设置。",
+            "年假规定：入职满一年可休五天。",
+        ];
+        let mut conn = pool.acquire().await.unwrap();
+        for (i, text) in texts.iter().enumerate() {
+            index_chunk(&mut conn, &format!("chunk-{}", i + 1), text).await.unwrap();
+        }
+        drop(conn);
+
+        let search = |question: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, String>(
+                    "SELECT m.chunk_id FROM chunks_fts
+                     JOIN chunk_fts_map m ON m.fts_rowid = chunks_fts.rowid
+                     WHERE chunks_fts MATCH ? ORDER BY bm25(chunks_fts) LIMIT 1",
+                )
+                .bind(match_query(question).unwrap())
+                .fetch_optional(&pool)
+                .await
+                .unwrap()
+            }
+        };
+
+        assert_eq!(search("怎么报销").await.as_deref(), Some("chunk-1"));
+        assert_eq!(search("ERR_CONN_RESET 是什么").await.as_deref(), Some("chunk-2"));
+        assert_eq!(search("年假几天").await.as_deref(), Some("chunk-3"));
+    }
+}
@@ -0,0 +1,98 @@
+//! 知识库级别的设置
+//!
+//! 每个知识库可以单独指定分块方式和 Embedding 模型，未指定的字段沿用全局 RAG 配置。
+//! 使用不同 Embedding 模型的知识库向量维度可能不同，因此每个知识库有独立的向量存储。
+
+use serde::{Deserialize, Serialize};
+
+use super::tokenizer::ChunkUnit;
+use super::{ChunkStrategy, EmbeddingProviderKind, RAGConfig};
+
+/// 默认知识库的 ID（升级前的文档都属于该知识库）
+pub const DEFAULT_KNOWLEDGE_BASE_ID: &str = "default";
+
+/// 覆盖全局 RAG 配置的知识库设置，为空的字段使用全局配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KnowledgeBaseSettings {
+    pub chunk_size: Option<usize>,
+    pub chunk_overlap: Option<usize>,
+    pub chunk_unit: Option<ChunkUnit>,
+    pub chunk_strategy: Option<ChunkStrategy>,
+    pub embedding_provider: Option<EmbeddingProviderKind>,
+    pub embedding_base_url: Option<String>,
+    pub embedding_api_key: Option<String>,
+    pub embedding_model: Option<String>,
+}
+
+impl KnowledgeBaseSettings {
+    /// 是否指定了单独的 Embedding 服务
+    pub fn has_embedding_override(&self) -> bool {
+        self.embedding_provider.is_some()
+            || self.embedding_base_url.is_some()
+            || self.embedding_api_key.is_some()
+            || self.embedding_model.is_some()
+    }
+
+    /// Embedding 相关的设置（修改后已有的向量不再可用）
+    pub fn embedding_settings(&self) -> (Option<EmbeddingProviderKind>, Option<&str>, Option<&str>) {
+        (
+            self.embedding_provider,
+            self.embedding_base_url.as_deref(),
+            self.embedding_model.as_deref(),
+        )
+    }
+
+    /// 在全局配置上应用知识库设置
+    pub fn apply(&self, config: &RAGConfig) -> RAGConfig {
+        let mut config = config.clone();
+        if let Some(chunk_size) = self.chunk_size {
+            config.chunk_size = chunk_size;
+        }
+        if let Some(chunk_overlap) = self.chunk_overlap {
+            config.chunk_overlap = chunk_overlap;
+        }
+        if let Some(chunk_unit) = self.chunk_unit {
+            config.chunk_unit = chunk_unit;
+        }
+        if let Some(chunk_strategy) = self.chunk_strategy {
+            config.chunk_strategy = chunk_strategy;
+        }
+        if let Some(provider) = self.embedding_provider {
+            config.embedding_provider = provider;
+        }
+        if let Some(base_url) = &self.embedding_base_url {
+            config.embedding_base_url = Some(base_url.clone());
+        }
+        if let Some(api_key) = &self.embedding_api_key {
+            config.embedding_api_key = Some(api_key.clone());
+        }
+        if let Some(model) = &self.embedding_model {
+            config.embedding_model = model.clone();
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_settings() {
+        let global = RAGConfig::default();
+        let settings: KnowledgeBaseSettings =
+            serde_json::from_str(r#"{"chunk_size": 300, "chunk_strategy": "semantic", "embedding_model": "bge-m3"}"#).unwrap();
+
+        let config = settings.apply(&global);
+        assert_eq!(config.chunk_size, 300);
+        assert_eq!(config.chunk_strategy, ChunkStrategy::Semantic);
+        assert_eq!(config.embedding_model, "bge-m3");
+        assert_eq!(config.chunk_overlap, global.chunk_overlap);
+        assert!(settings.has_embedding_override());
+
+        let empty = KnowledgeBaseSettings::default();
+        assert!(!empty.has_embedding_override());
+        assert_eq!(empty.apply(&global).chunk_size, global.chunk_size);
+    }
+}
@@ -1,18 +1,12 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Debug, Serialize)]
-struct ChatRequest {
-    model: String,
-    input: ChatInput,
-    parameters: Option<Parameters>,
-}
-
-#[derive(Debug, Serialize)]
-struct ChatInput {
-    messages: Vec<ChatMessage>,
-}
+use super::citation::{format_context, ContextPassage};
+use super::{join_endpoint, LlmProviderKind, RAGConfig};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatMessage {
@@ -20,160 +14,849 @@ pub struct ChatMessage {
     pub content: String,
 }
 
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+/// 生成参数
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        Self {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+        }
+    }
+}
+
+/// 流式生成时接收增量文本的回调
+pub type DeltaCallback<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+/// 对话模型提供方
+///
+/// DashScope、OpenAI 兼容网关和本地 Ollama 各自实现请求与响应格式，
+/// `LLMService` 在其上构建 RAG 提示词。
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// 提供方名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 使用的模型名称
+    fn model(&self) -> &str;
+
+    /// 生成完整回复
+    async fn chat(&self, messages: &[ChatMessage], options: &GenerationOptions) -> Result<String>;
+
+    /// 流式生成：每收到一段增量文本调用一次 `on_delta`，结束后返回完整回复
+    ///
+    /// 默认实现退化为一次性生成，不支持流式的提供方无需覆盖。
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        let text = self.chat(messages, options).await?;
+        on_delta(&text);
+        Ok(text)
+    }
+}
+
+/// 按行切分流式响应体（兼容 SSE 与 NDJSON），跨 chunk 的多字节字符会被完整保留
+#[derive(Default)]
+struct LineBuffer {
+    buffer: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// 追加数据并取出所有完整的行
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut lines = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    /// 取出剩余未以换行结尾的内容
+    fn finish(&mut self) -> Option<String> {
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).trim().to_string();
+        (!rest.is_empty()).then_some(rest)
+    }
+}
+
+/// 提取 SSE `data:` 行的内容，其他字段（event、id 等）返回 None
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|d| d.trim_start())
+}
+
+/// 逐行读取流式响应体，`parse_line` 从每一行中解析增量文本；返回完整文本
+async fn read_stream(
+    mut response: reqwest::Response,
+    on_delta: &DeltaCallback<'_>,
+    parse_line: impl Fn(&str) -> Result<Option<String>>,
+) -> Result<String> {
+    let mut lines = LineBuffer::default();
+    let mut full_text = String::new();
+
+    let mut handle_line = |line: &str| -> Result<()> {
+        if let Some(delta) = parse_line(line)? {
+            if !delta.is_empty() {
+                on_delta(&delta);
+                full_text.push_str(&delta);
+            }
+        }
+        Ok(())
+    };
+
+    while let Some(chunk) = response.chunk().await? {
+        for line in lines.push(&chunk) {
+            handle_line(&line)?;
+        }
+    }
+    if let Some(line) = lines.finish() {
+        handle_line(&line)?;
+    }
+
+    if full_text.is_empty() {
+        anyhow::bail!("LLM API 返回空内容");
+    }
+
+    Ok(full_text)
+}
+
+/// 非流式请求的总超时（流式回答可能持续更久，只限制两次数据之间的间隔）
+const CHAT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 创建 HTTP 客户端：限制建立连接的时间和两次读取之间的空闲时间
+fn http_client() -> Client {
+    Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .read_timeout(Duration::from_secs(120))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// 检查响应状态，失败时带上响应体
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await?;
+        anyhow::bail!("LLM API 请求失败: {} - {}", status, text);
+    }
+    Ok(response)
+}
+
+// ==================== DashScope ====================
+
 #[derive(Debug, Serialize)]
-struct Parameters {
+struct DashScopeRequest<'a> {
+    model: &'a str,
+    input: DashScopeInput<'a>,
+    parameters: DashScopeParameters,
+}
+
+#[derive(Debug, Serialize)]
+struct DashScopeInput<'a> {
+    messages: &'a [ChatMessage],
+}
+
+#[derive(Debug, Serialize)]
+struct DashScopeParameters {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result_format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    incremental_output: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ChatResponse {
-    output: ChatOutput,
+struct DashScopeResponse {
+    output: DashScopeOutput,
     #[allow(dead_code)]
-    usage: Option<Usage>,
+    usage: Option<DashScopeUsage>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ChatOutput {
+struct DashScopeOutput {
     text: Option<String>,
-    choices: Option<Vec<Choice>>,
+    choices: Option<Vec<DashScopeChoice>>,
 }
 
 #[derive(Debug, Deserialize)]
-struct Choice {
+struct DashScopeChoice {
     message: ChatMessage,
 }
 
 #[derive(Debug, Deserialize)]
-struct Usage {
+struct DashScopeUsage {
     #[allow(dead_code)]
     input_tokens: usize,
     #[allow(dead_code)]
     output_tokens: usize,
 }
 
-/// LLM 服务
-pub struct LLMService {
+/// 阿里云 DashScope 通义千问
+pub struct DashScopeLlm {
     client: Client,
     api_key: String,
     model: String,
 }
 
-impl LLMService {
+impl DashScopeLlm {
+    const URL: &'static str =
+        "https://dashscope.aliyuncs.com/api/v1/services/aigc/text-generation/generation";
+
     pub fn new(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: http_client(),
             api_key,
             model,
         }
     }
-    
-    pub fn api_key(&self) -> &String {
-        &self.api_key
+}
+
+#[async_trait]
+impl LlmProvider for DashScopeLlm {
+    fn name(&self) -> &str {
+        "dashscope"
     }
-    
-    pub fn model(&self) -> &String {
+
+    fn model(&self) -> &str {
         &self.model
     }
-    
-    /// 生成回答
-    pub async fn generate(&self, messages: Vec<ChatMessage>) -> Result<String> {
-        let request = ChatRequest {
-            model: self.model.clone(),
-            input: ChatInput { messages },
-            parameters: Some(Parameters {
-                temperature: Some(0.7),
-                top_p: Some(0.9),
-            }),
+
+    async fn chat(&self, messages: &[ChatMessage], options: &GenerationOptions) -> Result<String> {
+        let request = DashScopeRequest {
+            model: &self.model,
+            input: DashScopeInput { messages },
+            parameters: DashScopeParameters {
+                temperature: options.temperature,
+                top_p: options.top_p,
+                result_format: None,
+                incremental_output: None,
+            },
+        };
+
+        let response = self.client
+            .post(Self::URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .timeout(CHAT_TIMEOUT)
+            .json(&request)
+            .send()
+            .await?;
+
+        let result: DashScopeResponse = check_status(response).await?.json().await?;
+
+        result.output
+            .into_text()
+            .ok_or_else(|| anyhow::anyhow!("LLM API 返回空内容"))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        // incremental_output 让每个事件只携带新增的文本
+        let request = DashScopeRequest {
+            model: &self.model,
+            input: DashScopeInput { messages },
+            parameters: DashScopeParameters {
+                temperature: options.temperature,
+                top_p: options.top_p,
+                result_format: Some("message"),
+                incremental_output: Some(true),
+            },
         };
-        
+
         let response = self.client
-            .post("https://dashscope.aliyuncs.com/api/v1/services/aigc/text-generation/generation")
+            .post(Self::URL)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
+            .header("X-DashScope-SSE", "enable")
             .json(&request)
             .send()
             .await?;
-        
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await?;
-            anyhow::bail!("LLM API 请求失败: {} - {}", status, text);
-        }
-        
-        let result: ChatResponse = response.json().await?;
-        
-        // 处理不同的响应格式
-        if let Some(text) = result.output.text {
-            return Ok(text);
-        }
-        
-        if let Some(choices) = result.output.choices {
-            if let Some(first_choice) = choices.first() {
-                return Ok(first_choice.message.content.clone());
+
+        read_stream(check_status(response).await?, on_delta, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(None);
+            };
+            let event: DashScopeResponse = serde_json::from_str(data)?;
+            Ok(event.output.into_text())
+        })
+        .await
+    }
+}
+
+impl DashScopeOutput {
+    /// 处理不同的响应格式（text 或 choices）
+    fn into_text(self) -> Option<String> {
+        if let Some(text) = self.text {
+            return Some(text);
+        }
+
+        self.choices
+            .and_then(|c| c.into_iter().next())
+            .map(|c| c.message.content)
+    }
+}
+
+// ==================== OpenAI 兼容接口 ====================
+
+#[derive(Debug, Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiResponse {
+    choices: Vec<OpenAiChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiStreamChunk {
+    choices: Vec<OpenAiStreamChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiStreamChoice {
+    delta: OpenAiDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiDelta {
+    content: Option<String>,
+}
+
+/// OpenAI 兼容的 `/v1/chat/completions` 接口
+pub struct OpenAiLlm {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiLlm {
+    /// `base_url` 形如 `https://gateway.example.com/v1`，也可以直接填写完整的 `/chat/completions` 地址
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: http_client(),
+            endpoint: join_endpoint(base_url, "/chat/completions"),
+            api_key: api_key.filter(|k| !k.is_empty()),
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiLlm {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], options: &GenerationOptions) -> Result<String> {
+        let response = self.send(messages, options, false).await?;
+        let result: OpenAiResponse = response.json().await?;
+
+        result.choices
+            .into_iter()
+            .next()
+            .map(|c| c.message.content)
+            .ok_or_else(|| anyhow::anyhow!("LLM API 返回空内容"))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        let response = self.send(messages, options, true).await?;
+
+        read_stream(response, on_delta, |line| {
+            let Some(data) = sse_data(line) else {
+                return Ok(None);
+            };
+            if data == "[DONE]" {
+                return Ok(None);
             }
+            let chunk: OpenAiStreamChunk = serde_json::from_str(data)?;
+            Ok(chunk.choices.into_iter().next().and_then(|c| c.delta.content))
+        })
+        .await
+    }
+}
+
+impl OpenAiLlm {
+    async fn send(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let mut request = self.client
+            .post(&self.endpoint)
+            .json(&OpenAiRequest {
+                model: &self.model,
+                messages,
+                temperature: options.temperature,
+                top_p: options.top_p,
+                stream,
+            });
+
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
         }
-        
-        anyhow::bail!("LLM API 返回空内容");
+        if !stream {
+            request = request.timeout(CHAT_TIMEOUT);
+        }
+
+        check_status(request.send().await?).await
     }
-    
-    /// RAG 问答
-    pub async fn answer_with_context(&self, question: &str, context: &str) -> Result<String> {
-        let system_message = ChatMessage {
-            role: "system".to_string(),
-            content: "你是一个专业的知识库助手。请基于提供的文档内容回答用户问题。如果文档中没有相关信息，请诚实告知。".to_string(),
+}
+
+// ==================== Ollama ====================
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: ChatMessage,
+}
+
+/// 本地 Ollama 的 `/api/chat` 接口
+pub struct OllamaLlm {
+    client: Client,
+    endpoint: String,
+    model: String,
+}
+
+impl OllamaLlm {
+    /// 默认服务地址
+    pub const DEFAULT_BASE_URL: &'static str = "http://localhost:11434";
+
+    pub fn new(base_url: &str, model: String) -> Self {
+        Self {
+            client: http_client(),
+            endpoint: join_endpoint(base_url, "/api/chat"),
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaLlm {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], options: &GenerationOptions) -> Result<String> {
+        let response = self.send(messages, options, false).await?;
+        let result: OllamaResponse = response.json().await?;
+        Ok(result.message.content)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        // Ollama 的流式响应是逐行 JSON（NDJSON）
+        let response = self.send(messages, options, true).await?;
+
+        read_stream(response, on_delta, |line| {
+            let chunk: OllamaResponse = serde_json::from_str(line)?;
+            Ok(Some(chunk.message.content))
+        })
+        .await
+    }
+}
+
+impl OllamaLlm {
+    async fn send(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let body = OllamaRequest {
+            model: &self.model,
+            messages,
+            stream,
+            options: OllamaOptions {
+                temperature: options.temperature,
+                top_p: options.top_p,
+            },
         };
-        
-        let user_message = ChatMessage {
-            role: "user".to_string(),
-            content: format!(
-                "参考文档：\n\n{}\n\n问题：{}",
-                context, question
-            ),
+
+        let mut request = self.client
+            .post(&self.endpoint)
+            .json(&body);
+        if !stream {
+            request = request.timeout(CHAT_TIMEOUT);
+        }
+
+        check_status(request.send().await?).await
+    }
+}
+
+// ==================== LLM 服务 ====================
+
+/// RAG 问答的系统提示词
+const RAG_SYSTEM_PROMPT: &str =
+    "你是一个专业的知识库助手。请基于提供的文档内容回答用户问题。如果文档中没有相关信息，请诚实告知。\n\n\
+     参考文档中的每个片段都以 [编号] 开头。回答中每一处依据文档的陈述之后，请用对应的编号标注出处，\
+     例如「年假为 5 天[1]」，依据多个片段时写作 [1][2]。只能使用参考文档中出现的编号，不要编造编号。";
+
+/// 追问改写的系统提示词
+const CONDENSE_SYSTEM_PROMPT: &str =
+    "你负责改写用户的追问。根据对话历史，把最后的问题改写成一个无需上下文也能理解的独立问题，\
+     补全其中的指代和省略。只输出改写后的问题，不要回答问题，不要添加任何解释。";
+
+/// LLM 服务
+pub struct LLMService {
+    provider: Arc<dyn LlmProvider>,
+    options: GenerationOptions,
+}
+
+impl LLMService {
+    /// 使用 DashScope 创建服务
+    pub fn new(api_key: String, model: String) -> Self {
+        Self::with_provider(Arc::new(DashScopeLlm::new(api_key, model)))
+    }
+
+    pub fn with_provider(provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            provider,
+            options: GenerationOptions::default(),
+        }
+    }
+
+    /// 根据 RAG 配置选择提供方
+    pub fn from_config(config: &RAGConfig, api_key: &str) -> Result<Self> {
+        let api_key = config
+            .llm_api_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .unwrap_or_else(|| api_key.to_string());
+        let base_url = config.llm_base_url.as_deref().filter(|u| !u.is_empty());
+
+        let provider: Arc<dyn LlmProvider> = match config.llm_provider {
+            LlmProviderKind::DashScope => {
+                Arc::new(DashScopeLlm::new(api_key, config.llm_model.clone()))
+            }
+            LlmProviderKind::OpenAi => {
+                let base_url = base_url.ok_or_else(|| anyhow::anyhow!("请配置 LLM 服务地址"))?;
+                Arc::new(OpenAiLlm::new(base_url, Some(api_key), config.llm_model.clone()))
+            }
+            LlmProviderKind::Ollama => Arc::new(OllamaLlm::new(
+                base_url.unwrap_or(OllamaLlm::DEFAULT_BASE_URL),
+                config.llm_model.clone(),
+            )),
         };
-        
-        self.generate(vec![system_message, user_message]).await
+
+        Ok(Self::with_provider(provider))
+    }
+
+    pub fn provider(&self) -> &Arc<dyn LlmProvider> {
+        &self.provider
+    }
+
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    /// 生成回答
+    pub async fn generate(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        self.provider.chat(&messages, &self.options).await
+    }
+
+    /// 使用指定参数生成回答
+    pub async fn generate_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: GenerationOptions,
+    ) -> Result<String> {
+        self.provider.chat(&messages, &options).await
+    }
+
+    /// 流式生成回答
+    pub async fn generate_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        self.provider.chat_stream(&messages, &self.options, on_delta).await
+    }
+
+    /// RAG 问答
+    pub async fn answer_with_context(&self, question: &str, passages: &[ContextPassage]) -> Result<String> {
+        self.answer_with_history(question, passages, &[]).await
+    }
+
+    /// 多轮 RAG 问答：历史消息按原顺序放在参考文档之前
+    pub async fn answer_with_history(
+        &self,
+        question: &str,
+        passages: &[ContextPassage],
+        history: &[ChatMessage],
+    ) -> Result<String> {
+        self.generate(Self::rag_messages(question, passages, history)).await
+    }
+
+    /// 流式多轮 RAG 问答
+    pub async fn answer_with_history_stream(
+        &self,
+        question: &str,
+        passages: &[ContextPassage],
+        history: &[ChatMessage],
+        on_delta: &DeltaCallback<'_>,
+    ) -> Result<String> {
+        self.generate_stream(Self::rag_messages(question, passages, history), on_delta).await
+    }
+
+    /// 将依赖上下文的追问改写为独立问题，用于向量检索
+    ///
+    /// 没有历史时直接返回原问题；模型输出为空时同样回退到原问题。
+    pub async fn condense_question(&self, history: &[ChatMessage], question: &str) -> Result<String> {
+        if history.is_empty() {
+            return Ok(question.to_string());
+        }
+
+        let transcript = history
+            .iter()
+            .map(|m| {
+                let speaker = if m.role == "user" { "用户" } else { "助手" };
+                format!("{}：{}", speaker, m.content)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let messages = vec![
+            ChatMessage::system(CONDENSE_SYSTEM_PROMPT),
+            ChatMessage::user(format!("对话历史：\n{}\n\n追问：{}", transcript, question)),
+        ];
+
+        // 改写需要稳定输出，使用较低的温度
+        let options = GenerationOptions {
+            temperature: Some(0.1),
+            top_p: None,
+        };
+        let condensed = self.generate_with_options(messages, options).await?;
+
+        Ok(clean_condensed_question(&condensed).unwrap_or_else(|| question.to_string()))
+    }
+
+    /// 构建 RAG 提示词（参考片段按 [1]、[2] 编号，供模型内联引用）
+    fn rag_messages(question: &str, passages: &[ContextPassage], history: &[ChatMessage]) -> Vec<ChatMessage> {
+        let context = format_context(passages);
+        let mut messages = Vec::with_capacity(history.len() + 2);
+        messages.push(ChatMessage::system(RAG_SYSTEM_PROMPT));
+        messages.extend(history.iter().cloned());
+        messages.push(ChatMessage::user(format!("参考文档：\n\n{}\n\n问题：{}", context, question)));
+        messages
     }
 }
 
+/// 清理改写结果：去掉前缀和包裹的引号，只保留第一行
+fn clean_condensed_question(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .trim_start_matches("改写后的问题：")
+        .trim_start_matches("独立问题：")
+        .trim_matches(|c| matches!(c, '"' | '“' | '”' | '「' | '」'))
+        .trim();
+
+    (!line.is_empty()).then(|| line.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[tokio::test]
     #[ignore] // 需要 API Key 才能运行
     async fn test_generate() {
         let api_key = std::env::var("QWEN_API_KEY").expect("需要设置 QWEN_API_KEY 环境变量");
         let service = LLMService::new(api_key, "qwen-turbo".to_string());
-        
+
         let messages = vec![
             ChatMessage {
                 role: "user".to_string(),
                 content: "你好，请介绍一下你自己".to_string(),
             }
         ];
-        
+
         let response = service.generate(messages).await.unwrap();
-        
+
         assert!(!response.is_empty());
         println!("LLM 回复: {}", response);
     }
-    
+
     #[tokio::test]
     #[ignore]
     async fn test_answer_with_context() {
         let api_key = std::env::var("QWEN_API_KEY").expect("需要设置 QWEN_API_KEY 环境变量");
         let service = LLMService::new(api_key, "qwen-turbo".to_string());
-        
-        let context = "苹果富含维生素C，对人体健康非常有益。每天吃一个苹果可以增强免疫力。";
+
+        let passages = vec![ContextPassage {
+            title: "水果百科".to_string(),
+            content: "苹果富含维生素C，对人体健康非常有益。每天吃一个苹果可以增强免疫力。".to_string(),
+        }];
         let question = "吃苹果有什么好处？";
-        
-        let answer = service.answer_with_context(question, context).await.unwrap();
-        
+
+        let answer = service.answer_with_context(question, &passages).await.unwrap();
+
         assert!(!answer.is_empty());
         println!("答案: {}", answer);
     }
-}
 
+    #[test]
+    fn test_line_buffer_keeps_split_utf8() {
+        let mut buffer = LineBuffer::default();
+        let bytes = "data: 你好\n\ndata: [DONE]".as_bytes();
+
+        // 在“你”的字节中间切开
+        let (first, second) = bytes.split_at(8);
+        assert!(buffer.push(first).is_empty());
+
+        let lines = buffer.push(second);
+        assert_eq!(lines, vec!["data: 你好".to_string()]);
+        assert_eq!(sse_data(&lines[0]), Some("你好"));
+        assert_eq!(buffer.finish(), Some("data: [DONE]".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stream_chunks() {
+        let chunk: OpenAiStreamChunk =
+            serde_json::from_str(r#"{"choices":[{"delta":{"content":"Hel"},"index":0}]}"#).unwrap();
+        assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("Hel"));
+
+        let event: DashScopeResponse = serde_json::from_str(
+            r#"{"output":{"choices":[{"message":{"role":"assistant","content":"你好"},"finish_reason":"null"}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(event.output.into_text().as_deref(), Some("你好"));
+    }
+
+    #[test]
+    fn test_rag_messages_with_history() {
+        let history = vec![
+            ChatMessage::user("年假有几天？"),
+            ChatMessage::assistant("入职满一年有 5 天年假。"),
+        ];
+
+        let passages = vec![ContextPassage {
+            title: "员工手册".to_string(),
+            content: "病假规定……".to_string(),
+        }];
+        let messages = LLMService::rag_messages("那病假呢？", &passages, &history);
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(messages[1].content, "年假有几天？");
+        assert_eq!(messages[2].role, "assistant");
+        assert!(messages[3].content.contains("[1] 来源：员工手册\n病假规定……"));
+        assert!(messages[3].content.ends_with("问题：那病假呢？"));
+    }
+
+    #[test]
+    fn test_clean_condensed_question() {
+        assert_eq!(
+            clean_condensed_question("改写后的问题：“病假有几天？”\n").as_deref(),
+            Some("病假有几天？")
+        );
+        assert_eq!(clean_condensed_question("  \n"), None);
+    }
+
+    #[test]
+    fn test_from_config_selects_provider() {
+        let mut config = RAGConfig {
+            llm_provider: LlmProviderKind::Ollama,
+            llm_model: "qwen2.5:7b".to_string(),
+            ..RAGConfig::default()
+        };
+        let service = LLMService::from_config(&config, "").unwrap();
+        assert_eq!(service.provider().name(), "ollama");
+        assert_eq!(service.model(), "qwen2.5:7b");
+
+        // OpenAI 兼容接口必须配置地址
+        config.llm_provider = LlmProviderKind::OpenAi;
+        assert!(LLMService::from_config(&config, "sk-test").is_err());
+
+        config.llm_base_url = Some("https://gateway.example.com/v1".to_string());
+        let service = LLMService::from_config(&config, "sk-test").unwrap();
+        assert_eq!(service.provider().name(), "openai");
+    }
+}
@@ -0,0 +1,373 @@
+//! Markdown 结构化分块
+//!
+//! 按标题划分章节，围栏代码块和表格作为整体不被截断，每个块以标题路径
+//! （例如 `Install › Linux`）开头，路径同时记录在块的 `section` 中。
+//! 超长的代码块按行拆分并重新补上围栏，超长的表格按行拆分并重复表头。
+
+use std::ops::Range;
+
+use super::text_splitter::{TextChunk, TextSplitter};
+use super::tokenizer::ChunkUnit;
+
+/// 标题路径各级之间的分隔符
+const BREADCRUMB_SEPARATOR: &str = " › ";
+
+#[derive(Debug, Clone, PartialEq)]
+enum BlockKind {
+    Heading { level: usize, text: String },
+    Code,
+    Table,
+    Text,
+}
+
+/// 按行解析得到的块
+struct Block {
+    kind: BlockKind,
+    lines: Range<usize>,
+}
+
+/// 参与分块的片段：块或超长块拆分后的一部分
+struct Piece {
+    text: String,
+    /// 在原文中的字符范围
+    span: Range<usize>,
+    heading: bool,
+}
+
+/// Markdown 分块器
+pub struct MarkdownSplitter {
+    chunk_size: usize,
+    unit: ChunkUnit,
+}
+
+impl MarkdownSplitter {
+    pub fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            unit: ChunkUnit::Chars,
+        }
+    }
+
+    /// 设置块大小的计量单位（默认按字符）
+    pub fn with_unit(mut self, unit: ChunkUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// 按标题和块结构分块
+    pub fn split(&self, text: &str) -> Vec<TextChunk> {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let mut line_spans = Vec::with_capacity(lines.len());
+        let mut offset = 0;
+        for line in &lines {
+            let len = line.chars().count();
+            line_spans.push(offset..offset + len);
+            offset += len + 1;
+        }
+
+        let mut chunks = Vec::new();
+        let mut headings: Vec<(usize, String)> = Vec::new();
+        let mut current: Vec<Piece> = Vec::new();
+
+        for block in parse_blocks(&lines) {
+            if let BlockKind::Heading { level, text } = &block.kind {
+                // 新标题开始新的块（紧邻的多级标题合并到同一块）
+                if current.iter().any(|p| !p.heading) {
+                    self.flush(&mut current, &headings, &mut chunks);
+                }
+                while headings.last().is_some_and(|(l, _)| *l >= *level) {
+                    headings.pop();
+                }
+                headings.push((*level, text.clone()));
+            }
+
+            for piece in self.pieces(&block, &lines, &line_spans) {
+                let current_len: usize = current.iter().map(|p| self.unit.measure(&p.text) + 2).sum();
+                if current_len + self.unit.measure(&piece.text) > self.chunk_size && current.iter().any(|p| !p.heading) {
+                    self.flush(&mut current, &headings, &mut chunks);
+                }
+                current.push(piece);
+            }
+        }
+        self.flush(&mut current, &headings, &mut chunks);
+
+        chunks
+    }
+
+    fn flush(&self, current: &mut Vec<Piece>, headings: &[(usize, String)], chunks: &mut Vec<TextChunk>) {
+        let (Some(first), Some(last)) = (current.first(), current.last()) else {
+            return;
+        };
+
+        let body = current.iter().map(|p| p.text.as_str()).collect::<Vec<_>>().join("\n\n");
+        let breadcrumb = (!headings.is_empty()).then(|| {
+            headings.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join(BREADCRUMB_SEPARATOR)
+        });
+        let content = match &breadcrumb {
+            Some(breadcrumb) => format!("{}\n\n{}", breadcrumb, body),
+            None => body,
+        };
+
+        chunks.push(TextChunk {
+            content,
+            start: first.span.start,
+            end: last.span.end,
+            section: breadcrumb,
+            ..Default::default()
+        });
+        current.clear();
+    }
+
+    /// 将块转换为片段，超长的块按类型拆分
+    fn pieces(&self, block: &Block, lines: &[&str], spans: &[Range<usize>]) -> Vec<Piece> {
+        let span = spans[block.lines.start].start..spans[block.lines.end - 1].end;
+        let text = lines[block.lines.clone()].join("\n");
+        let heading = matches!(block.kind, BlockKind::Heading { .. });
+
+        if heading || self.unit.measure(&text) <= self.chunk_size {
+            return vec![Piece { text, span, heading }];
+        }
+
+        let pieces = match block.kind {
+            BlockKind::Code => {
+                // 去掉首尾的围栏行，每一段重新包上围栏
+                let open = lines[block.lines.start].trim();
+                let close = lines[block.lines.end - 1].trim();
+                let has_close = block.lines.len() > 1 && close.starts_with(&open[..1]);
+                let body_end = if has_close { block.lines.end - 1 } else { block.lines.end };
+                let fence: String = open.chars().take_while(|c| *c == '`' || *c == '~').collect();
+                let budget = self.chunk_size.saturating_sub(self.unit.measure(open) + self.unit.measure(&fence) + 2);
+                self.group_lines(block.lines.start + 1..body_end, lines, spans, budget, |body| {
+                    format!("{}\n{}\n{}", open, body, fence)
+                })
+            }
+            BlockKind::Table => {
+                // 每一段都重复表头和分隔行
+                let header = lines[block.lines.start..block.lines.start + 2].join("\n");
+                let budget = self.chunk_size.saturating_sub(self.unit.measure(&header) + 1);
+                self.group_lines(block.lines.start + 2..block.lines.end, lines, spans, budget, |rows| {
+                    format!("{}\n{}", header, rows)
+                })
+            }
+            _ => TextSplitter::new(self.chunk_size, 0)
+                .with_unit(self.unit)
+                .split_recursive_from(&text, span.start)
+                .into_iter()
+                .map(|chunk| Piece {
+                    text: chunk.content,
+                    span: chunk.start..chunk.end,
+                    heading: false,
+                })
+                .collect(),
+        };
+
+        if pieces.is_empty() {
+            return vec![Piece { text, span, heading }];
+        }
+        pieces
+    }
+
+    /// 按行累积到预算长度，每组用 `wrap` 补上围栏或表头
+    fn group_lines(
+        &self,
+        range: Range<usize>,
+        lines: &[&str],
+        spans: &[Range<usize>],
+        budget: usize,
+        wrap: impl Fn(&str) -> String,
+    ) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+        let mut group: Option<Range<usize>> = None;
+        let push = |group: Range<usize>, pieces: &mut Vec<Piece>| {
+            pieces.push(Piece {
+                text: wrap(&lines[group.clone()].join("\n")),
+                span: spans[group.start].start..spans[group.end - 1].end,
+                heading: false,
+            });
+        };
+
+        for i in range {
+            group = match group.take() {
+                Some(g) if self.unit.measure(&lines[g.start..i + 1].join("\n")) <= budget => Some(g.start..i + 1),
+                Some(g) => {
+                    push(g, &mut pieces);
+                    Some(i..i + 1)
+                }
+                None => Some(i..i + 1),
+            };
+        }
+        if let Some(g) = group {
+            push(g, &mut pieces);
+        }
+        pieces
+    }
+}
+
+/// 围栏代码块的起始标记（``` 或 ~~~，最多缩进 3 个空格）
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let count = trimmed.chars().take_while(|c| *c == marker).count();
+    (indent <= 3 && count >= 3).then_some((marker, count))
+}
+
+/// ATX 标题：`## 标题`
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, text.to_string()))
+}
+
+/// Setext 标题的下划线：`===` 为一级，`---` 为二级
+fn setext_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// 表格分隔行：`| --- | :-: |`
+fn is_table_separator(line: &str) -> bool {
+    let trimmed = line.trim().trim_matches('|');
+    !trimmed.is_empty()
+        && trimmed.contains('-')
+        && trimmed.split('|').all(|cell| {
+            let cell = cell.trim();
+            !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':'))
+        })
+}
+
+fn starts_block(lines: &[&str], i: usize) -> bool {
+    fence_marker(lines[i]).is_some()
+        || atx_heading(lines[i]).is_some()
+        || (lines[i].contains('|') && lines.get(i + 1).is_some_and(|next| is_table_separator(next)))
+}
+
+/// 按行解析出标题、代码块、表格和普通文本块
+fn parse_blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let kind = if let Some((marker, count)) = fence_marker(line) {
+            // 找到匹配的结束围栏，未闭合时延续到文末
+            i += 1;
+            while i < lines.len() {
+                let closing = lines[i].trim();
+                i += 1;
+                if closing.chars().take_while(|c| *c == marker).count() >= count
+                    && closing.trim_start_matches(marker).trim().is_empty()
+                {
+                    break;
+                }
+            }
+            BlockKind::Code
+        } else if let Some((level, text)) = atx_heading(line) {
+            i += 1;
+            BlockKind::Heading { level, text }
+        } else if line.contains('|') && lines.get(i + 1).is_some_and(|next| is_table_separator(next)) {
+            i += 2;
+            while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+                i += 1;
+            }
+            BlockKind::Table
+        } else if let Some(level) = lines.get(i + 1).and_then(|next| setext_level(next)) {
+            i += 2;
+            BlockKind::Heading { level, text: line.trim().to_string() }
+        } else {
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines, i) {
+                i += 1;
+            }
+            BlockKind::Text
+        };
+
+        blocks.push(Block { kind, lines: start..i });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const README: &str = "# Install
+
+Requirements below.
+
+## Linux
+
+Run the script:
+
+```bash
+# not a heading
+
+./install.sh
+```
+
+| Distro | Status |
+| --- | --- |
+| Ubuntu | ok |
+
+## macOS
+Use Homebrew.
+";
+
+    #[test]
+    fn test_split_by_headings_with_breadcrumb() {
+        let chunks = MarkdownSplitter::new(500).split(README);
+        let sections: Vec<Option<&str>> = chunks.iter().map(|c| c.section.as_deref()).collect();
+
+        assert_eq!(sections, vec![Some("Install"), Some("Install › Linux"), Some("Install › macOS")]);
+        assert!(chunks[1].content.starts_with("Install › Linux\n\n## Linux"));
+        assert!(chunks[1].content.contains("```bash\n# not a heading\n\n./install.sh\n```"));
+        assert!(chunks[1].content.ends_with("| Ubuntu | ok |"));
+        let original: String = README.chars().skip(chunks[2].start).take(chunks[2].end - chunks[2].start).collect();
+        assert_eq!(original, "## macOS\nUse Homebrew.");
+    }
+
+    #[test]
+    fn test_oversized_code_and_table_are_rewrapped() {
+        let rows: Vec<String> = (0..6).map(|i| format!("| row{} | value{} |", i, i)).collect();
+        let code: Vec<String> = (0..8).map(|i| format!("let v{} = {};", i, i)).collect();
+        let text = format!(
+            "## Data\n\n| Key | Value |\n| --- | --- |\n{}\n\n```rust\n{}\n```",
+            rows.join("\n"),
+            code.join("\n")
+        );
+        let chunks = MarkdownSplitter::new(70).split(&text);
+
+        let tables: Vec<&TextChunk> = chunks.iter().filter(|c| c.content.contains("| row")).collect();
+        assert!(tables.len() > 1);
+        assert!(tables.iter().all(|c| c.content.contains("| Key | Value |\n| --- | --- |\n| row")));
+
+        let code: Vec<&TextChunk> = chunks.iter().filter(|c| c.content.contains("let ")).collect();
+        assert!(code.len() > 1);
+        assert!(code.iter().all(|c| c.content.contains("```rust\nlet") && c.content.trim_end().ends_with("```")));
+        assert!(chunks.iter().all(|c| c.section.as_deref() == Some("Data")));
+    }
+}
@@ -1,29 +1,184 @@
 pub mod embedding;
+pub mod citation;
+pub mod code_splitter;
 pub mod vector_store;
+pub mod hnsw;
+pub mod hybrid;
+pub mod keyword;
+pub mod knowledge_base;
 pub mod llm;
+pub mod markdown_splitter;
+pub mod rerank;
+pub mod semantic_splitter;
 pub mod text_splitter;
+pub mod tokenizer;
 
 use serde::{Deserialize, Serialize};
 
+use tokenizer::ChunkUnit;
+
+/// 拼接 OpenAI 风格的接口地址：`base_url` 已包含完整路径时原样使用
+pub(crate) fn join_endpoint(base_url: &str, path: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    if base_url.ends_with(path) {
+        base_url.to_string()
+    } else {
+        format!("{}{}", base_url, path)
+    }
+}
+
+/// Embedding 服务提供方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmbeddingProviderKind {
+    /// 阿里云 DashScope
+    #[default]
+    #[serde(rename = "dashscope")]
+    DashScope,
+    /// OpenAI 兼容的 /v1/embeddings 接口（含本地 llama.cpp、Ollama）
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// 确定性测试桩，不访问网络
+    #[serde(rename = "stub")]
+    Stub,
+}
+
+/// 对话模型提供方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LlmProviderKind {
+    /// 阿里云 DashScope
+    #[default]
+    #[serde(rename = "dashscope")]
+    DashScope,
+    /// OpenAI 兼容的 /v1/chat/completions 接口
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// 本地 Ollama（/api/chat）
+    #[serde(rename = "ollama")]
+    Ollama,
+}
+
+/// 重排序提供方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RerankProviderKind {
+    /// 不重排序，直接使用检索结果
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// 阿里云 DashScope gte-rerank
+    #[serde(rename = "dashscope")]
+    DashScope,
+    /// Jina / Cohere 风格的 /v1/rerank 接口
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// 由对话模型为候选片段打分
+    #[serde(rename = "llm")]
+    Llm,
+}
+
+/// 普通文本的分块策略（代码、Markdown 和分章节的文档有各自的分块方式）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChunkStrategy {
+    /// 按段落，过长的段落按固定长度截断
+    #[serde(rename = "fixed")]
+    Fixed,
+    /// 依次在段落、句子、词的边界处切分
+    #[default]
+    #[serde(rename = "recursive")]
+    Recursive,
+    /// 按相邻句子的向量相似度分组
+    #[serde(rename = "semantic")]
+    Semantic,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RAGConfig {
     pub qwen_api_key: String,
+    pub embedding_provider: EmbeddingProviderKind,
+    /// OpenAI 兼容接口地址，例如 http://localhost:11434/v1
+    pub embedding_base_url: Option<String>,
+    /// 单独的 Embedding API Key，为空时使用通义千问 API Key
+    pub embedding_api_key: Option<String>,
     pub embedding_model: String,
+    pub llm_provider: LlmProviderKind,
+    /// OpenAI 兼容接口或 Ollama 地址，例如 http://localhost:11434
+    pub llm_base_url: Option<String>,
+    /// 单独的 LLM API Key，为空时使用通义千问 API Key
+    pub llm_api_key: Option<String>,
     pub llm_model: String,
     pub chunk_size: usize,
     pub chunk_overlap: usize,
+    /// `chunk_size` / `chunk_overlap` 的计量单位：字符或 token
+    pub chunk_unit: ChunkUnit,
+    /// 默认分块策略，上传时可按文档单独指定
+    pub chunk_strategy: ChunkStrategy,
+    /// 语义分块的断点百分位（0-100），越大块越长
+    pub semantic_breakpoint_percentile: f32,
+    /// 提供给模型的参考片段总 token 数上限，0 表示不限制
+    pub max_context_tokens: usize,
     pub top_k: usize,
+    /// 多轮对话时带入提示词的历史轮数（一问一答为一轮），0 表示不使用历史
+    pub history_turns: usize,
+    /// 是否将追问改写为独立问题后再检索
+    pub condense_question: bool,
+    /// 是否将关键词检索（FTS5）与向量检索结果融合
+    pub hybrid_search: bool,
+    pub rerank_provider: RerankProviderKind,
+    /// Rerank 接口地址，例如 http://localhost:8080/v1
+    pub rerank_base_url: Option<String>,
+    /// 单独的 Rerank API Key，为空时使用通义千问 API Key
+    pub rerank_api_key: Option<String>,
+    pub rerank_model: String,
+    /// 重排序前召回的候选数量
+    pub candidate_k: usize,
+}
+
+impl RAGConfig {
+    /// 是否需要通义千问 API Key（仅本地/自建服务时可以不配置）
+    pub fn requires_api_key(&self) -> bool {
+        self.embedding_provider == EmbeddingProviderKind::DashScope
+            || self.llm_provider == LlmProviderKind::DashScope
+            || self.rerank_provider == RerankProviderKind::DashScope
+    }
+
+    /// 生成向量的 Embedding 服务标识，标识不同的服务生成的向量不能混用
+    pub fn embedding_signature(&self) -> String {
+        format!(
+            "{:?}|{}|{}",
+            self.embedding_provider,
+            self.embedding_base_url.as_deref().unwrap_or_default(),
+            self.embedding_model
+        )
+    }
 }
 
 impl Default for RAGConfig {
     fn default() -> Self {
         Self {
             qwen_api_key: String::new(),
+            embedding_provider: EmbeddingProviderKind::default(),
+            embedding_base_url: None,
+            embedding_api_key: None,
             embedding_model: "text-embedding-v2".to_string(),
+            llm_provider: LlmProviderKind::default(),
+            llm_base_url: None,
+            llm_api_key: None,
             llm_model: "qwen-turbo".to_string(),
             chunk_size: 800,
             chunk_overlap: 80,
+            chunk_unit: ChunkUnit::default(),
+            chunk_strategy: ChunkStrategy::default(),
+            semantic_breakpoint_percentile: 90.0,
+            max_context_tokens: 6000,
             top_k: 3,
+            history_turns: 3,
+            condense_question: true,
+            hybrid_search: true,
+            rerank_provider: RerankProviderKind::default(),
+            rerank_base_url: None,
+            rerank_api_key: None,
+            rerank_model: "gte-rerank".to_string(),
+            candidate_k: 20,
         }
     }
 }
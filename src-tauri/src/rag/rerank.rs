@@ -0,0 +1,408 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::llm::{ChatMessage, GenerationOptions, LLMService};
+use super::{join_endpoint, RAGConfig, RerankProviderKind};
+
+/// 单条重排序结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RerankResult {
+    /// 对应输入文档的下标
+    pub index: usize,
+    /// 相关度得分，越高越相关（不同提供方的量纲不同，只用于排序和展示）
+    pub score: f32,
+}
+
+/// 重排序提供方
+///
+/// 对检索得到的候选文档逐一与问题打分（交叉编码器或 LLM 评分），
+/// 由 `RerankService` 统一负责排序和截断。
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// 提供方名称（用于日志）
+    fn name(&self) -> &str;
+
+    /// 使用的模型名称
+    fn model(&self) -> &str;
+
+    /// 为每个文档打分，返回结果无需有序
+    async fn score(&self, query: &str, documents: &[String]) -> Result<Vec<RerankResult>>;
+}
+
+/// 创建带超时的 HTTP 客户端
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// 检查响应状态码，失败时带上响应内容
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await?;
+        anyhow::bail!("Rerank API 请求失败: {} - {}", status, text);
+    }
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankItem {
+    index: usize,
+    relevance_score: f32,
+}
+
+impl From<RerankItem> for RerankResult {
+    fn from(item: RerankItem) -> Self {
+        Self {
+            index: item.index,
+            score: item.relevance_score,
+        }
+    }
+}
+
+// ==================== DashScope ====================
+
+#[derive(Debug, Serialize)]
+struct DashScopeRequest<'a> {
+    model: &'a str,
+    input: DashScopeInput<'a>,
+    parameters: DashScopeParameters,
+}
+
+#[derive(Debug, Serialize)]
+struct DashScopeInput<'a> {
+    query: &'a str,
+    documents: &'a [String],
+}
+
+#[derive(Debug, Serialize)]
+struct DashScopeParameters {
+    return_documents: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DashScopeResponse {
+    output: DashScopeOutput,
+}
+
+#[derive(Debug, Deserialize)]
+struct DashScopeOutput {
+    results: Vec<RerankItem>,
+}
+
+/// 阿里云 DashScope 文本排序（gte-rerank）
+pub struct DashScopeReranker {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl DashScopeReranker {
+    const URL: &'static str =
+        "https://dashscope.aliyuncs.com/api/v1/services/rerank/text-rerank/text-rerank";
+
+    pub fn new(api_key: String, model: String) -> Self {
+        Self {
+            client: http_client(),
+            api_key,
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for DashScopeReranker {
+    fn name(&self) -> &str {
+        "dashscope"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn score(&self, query: &str, documents: &[String]) -> Result<Vec<RerankResult>> {
+        let request = DashScopeRequest {
+            model: &self.model,
+            input: DashScopeInput { query, documents },
+            parameters: DashScopeParameters {
+                return_documents: false,
+            },
+        };
+
+        let response = self.client
+            .post(Self::URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        let result: DashScopeResponse = check_status(response).await?.json().await?;
+        Ok(result.output.results.into_iter().map(Into::into).collect())
+    }
+}
+
+// ==================== OpenAI 兼容接口 ====================
+
+#[derive(Debug, Serialize)]
+struct OpenAiRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiResponse {
+    results: Vec<RerankItem>,
+}
+
+/// Jina / Cohere 风格的 /v1/rerank 接口（vLLM、TEI、Xinference 等本地服务均兼容）
+pub struct OpenAiReranker {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiReranker {
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            client: http_client(),
+            url: join_endpoint(base_url, "/rerank"),
+            api_key: api_key.filter(|k| !k.is_empty()),
+            model,
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for OpenAiReranker {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn score(&self, query: &str, documents: &[String]) -> Result<Vec<RerankResult>> {
+        let request = OpenAiRequest {
+            model: &self.model,
+            query,
+            documents,
+        };
+
+        let mut builder = self.client.post(&self.url).json(&request);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let result: OpenAiResponse = check_status(builder.send().await?).await?.json().await?;
+        Ok(result.results.into_iter().map(Into::into).collect())
+    }
+}
+
+// ==================== LLM 评分 ====================
+
+/// LLM 评分时每个候选片段的最大字符数
+const LLM_PASSAGE_MAX_CHARS: usize = 500;
+
+const LLM_RERANK_SYSTEM_PROMPT: &str = "你是一个检索结果评估助手。请判断每个编号片段对回答用户问题的帮助程度，给出 0 到 10 的分数（10 表示直接包含答案，0 表示完全无关）。\n\n只输出评分结果，每行一个，格式为「编号: 分数」，不要输出任何解释。";
+
+/// 没有专用重排序模型时，使用对话模型为候选片段打分
+pub struct LlmReranker {
+    llm: Arc<LLMService>,
+}
+
+impl LlmReranker {
+    pub fn new(llm: Arc<LLMService>) -> Self {
+        Self { llm }
+    }
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    fn model(&self) -> &str {
+        self.llm.model()
+    }
+
+    async fn score(&self, query: &str, documents: &[String]) -> Result<Vec<RerankResult>> {
+        let passages: Vec<String> = documents
+            .iter()
+            .enumerate()
+            .map(|(i, doc)| {
+                let passage: String = doc.chars().take(LLM_PASSAGE_MAX_CHARS).collect();
+                format!("[{}] {}", i + 1, passage)
+            })
+            .collect();
+
+        let messages = vec![
+            ChatMessage::system(LLM_RERANK_SYSTEM_PROMPT),
+            ChatMessage::user(format!("问题：{}\n\n片段：\n{}", query, passages.join("\n\n"))),
+        ];
+        let options = GenerationOptions {
+            temperature: Some(0.0),
+            top_p: None,
+        };
+
+        let output = self.llm.generate_with_options(messages, options).await?;
+        Ok(parse_llm_scores(&output, documents.len()))
+    }
+}
+
+/// 解析「编号: 分数」格式的评分，未给出评分的片段记为 0 分
+fn parse_llm_scores(output: &str, count: usize) -> Vec<RerankResult> {
+    let mut scores = vec![0.0; count];
+
+    for line in output.lines() {
+        let mut numbers = line
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter_map(|part| part.trim_matches('.').parse::<f32>().ok());
+
+        if let (Some(number), Some(score)) = (numbers.next(), numbers.next()) {
+            let index = number as usize;
+            if index >= 1 && index <= count {
+                scores[index - 1] = score.clamp(0.0, 10.0);
+            }
+        }
+    }
+
+    scores
+        .into_iter()
+        .enumerate()
+        .map(|(index, score)| RerankResult { index, score })
+        .collect()
+}
+
+// ==================== 服务 ====================
+
+/// 重排序服务
+pub struct RerankService {
+    provider: Arc<dyn Reranker>,
+}
+
+impl RerankService {
+    pub fn with_provider(provider: Arc<dyn Reranker>) -> Self {
+        Self { provider }
+    }
+
+    /// 根据配置创建重排序服务，未启用重排序时返回 None
+    ///
+    /// LLM 评分复用已创建的对话模型服务。
+    pub fn from_config(config: &RAGConfig, api_key: &str, llm: Arc<LLMService>) -> Result<Option<Self>> {
+        let api_key = config
+            .rerank_api_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .unwrap_or_else(|| api_key.to_string());
+
+        let provider: Arc<dyn Reranker> = match config.rerank_provider {
+            RerankProviderKind::None => return Ok(None),
+            RerankProviderKind::DashScope => {
+                Arc::new(DashScopeReranker::new(api_key, config.rerank_model.clone()))
+            }
+            RerankProviderKind::OpenAi => {
+                let base_url = config
+                    .rerank_base_url
+                    .as_deref()
+                    .filter(|u| !u.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("请配置 Rerank 服务地址"))?;
+                Arc::new(OpenAiReranker::new(
+                    base_url,
+                    Some(api_key),
+                    config.rerank_model.clone(),
+                ))
+            }
+            RerankProviderKind::Llm => Arc::new(LlmReranker::new(llm)),
+        };
+
+        Ok(Some(Self::with_provider(provider)))
+    }
+
+    pub fn provider(&self) -> &Arc<dyn Reranker> {
+        &self.provider
+    }
+
+    /// 对候选文档重排序，返回得分最高的 `top_n` 条（按得分降序）
+    pub async fn rerank(&self, query: &str, documents: &[String], top_n: usize) -> Result<Vec<RerankResult>> {
+        if documents.is_empty() || top_n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut results: Vec<RerankResult> = self.provider
+            .score(query, documents)
+            .await?
+            .into_iter()
+            .filter(|r| r.index < documents.len())
+            .collect();
+
+        // 得分相同时保持原检索顺序
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+        results.truncate(top_n);
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按文档中包含查询字符的个数打分
+    struct OverlapReranker;
+
+    #[async_trait]
+    impl Reranker for OverlapReranker {
+        fn name(&self) -> &str {
+            "overlap"
+        }
+
+        fn model(&self) -> &str {
+            "overlap"
+        }
+
+        async fn score(&self, query: &str, documents: &[String]) -> Result<Vec<RerankResult>> {
+            Ok(documents
+                .iter()
+                .enumerate()
+                .map(|(index, doc)| RerankResult {
+                    index,
+                    score: query.chars().filter(|c| doc.contains(*c)).count() as f32,
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_sorts_and_truncates() {
+        let service = RerankService::with_provider(Arc::new(OverlapReranker));
+        let documents = vec![
+            "年假规定".to_string(),
+            "报销流程说明".to_string(),
+            "差旅报销".to_string(),
+        ];
+
+        let results = service.rerank("报销流程", &documents, 2).await.unwrap();
+
+        assert_eq!(results.iter().map(|r| r.index).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(results[0].score, 4.0);
+    }
+
+    #[test]
+    fn test_parse_llm_scores() {
+        let output = "1: 8\n[2] 3.5\n3：10 分\n9: 7\n";
+        let scores: Vec<f32> = parse_llm_scores(output, 4).into_iter().map(|r| r.score).collect();
+
+        assert_eq!(scores, vec![8.0, 3.5, 10.0, 0.0]);
+    }
+}
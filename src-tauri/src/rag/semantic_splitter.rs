@@ -0,0 +1,135 @@
+//! 语义分块
+//!
+//! 先将文本切分为句子并逐句向量化，再比较相邻句子的向量距离：距离超过所有
+//! 相邻距离中指定百分位的位置视为话题转换，在此断开；同时保证块不超过块大小。
+//! 每个句子都需要调用一次 Embedding，耗时和费用高于其他分块方式。
+
+use anyhow::Result;
+
+use super::embedding::EmbeddingService;
+use super::text_splitter::{TextChunk, TextSplitter};
+use super::tokenizer::ChunkUnit;
+use super::vector_store::cosine_similarity;
+
+/// 语义分块器
+pub struct SemanticSplitter {
+    chunk_size: usize,
+    /// 断点百分位（0-100），越大断点越少、块越长
+    breakpoint_percentile: f32,
+    unit: ChunkUnit,
+}
+
+impl SemanticSplitter {
+    pub fn new(chunk_size: usize, breakpoint_percentile: f32) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            breakpoint_percentile: breakpoint_percentile.clamp(0.0, 100.0),
+            unit: ChunkUnit::Chars,
+        }
+    }
+
+    /// 设置块大小的计量单位（默认按字符）
+    pub fn with_unit(mut self, unit: ChunkUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// 按相邻句子的语义相似度分块
+    pub async fn split(&self, text: &str, embedding: &EmbeddingService) -> Result<Vec<TextChunk>> {
+        let sentences = TextSplitter::new(self.chunk_size, 0)
+            .with_unit(self.unit)
+            .split_sentences(text);
+        if sentences.len() < 2 {
+            return Ok(sentences);
+        }
+
+        let texts: Vec<String> = sentences.iter().map(|s| s.content.clone()).collect();
+        let embeddings = embedding.embed_batch(&texts).await?;
+        if embeddings.len() != sentences.len() {
+            anyhow::bail!("句子向量数量不匹配: {} 个句子，{} 个向量", sentences.len(), embeddings.len());
+        }
+
+        Ok(self.group(text, &sentences, &embeddings))
+    }
+
+    /// 根据句子向量将相邻句子合并为块
+    fn group(&self, text: &str, sentences: &[TextChunk], embeddings: &[Vec<f32>]) -> Vec<TextChunk> {
+        let chars: Vec<char> = text.chars().collect();
+        let distances: Vec<f32> = embeddings
+            .windows(2)
+            .map(|pair| 1.0 - cosine_similarity(&pair[0], &pair[1]))
+            .collect();
+        let threshold = percentile(&distances, self.breakpoint_percentile);
+
+        let mut chunks = Vec::new();
+        let mut push = |first: &TextChunk, last: &TextChunk| {
+            chunks.push(TextChunk {
+                content: chars[first.start..last.end].iter().collect(),
+                start: first.start,
+                end: last.end,
+                ..Default::default()
+            });
+        };
+
+        let mut first = 0;
+        let mut total = 0;
+        for (i, sentence) in sentences.iter().enumerate() {
+            let len = self.unit.measure(&sentence.content);
+            let is_breakpoint = i > 0 && distances[i - 1] > threshold;
+            if i > first && (is_breakpoint || total + len > self.chunk_size) {
+                push(&sentences[first], &sentences[i - 1]);
+                first = i;
+                total = 0;
+            }
+            total += len;
+        }
+        if let Some(last) = sentences.last() {
+            push(&sentences[first], last);
+        }
+
+        chunks
+    }
+}
+
+/// 线性插值的百分位数，没有数据时返回正无穷（不产生断点）
+fn percentile(values: &[f32], percentile: f32) -> f32 {
+    if values.is_empty() {
+        return f32::INFINITY;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let position = percentile / 100.0 * (sorted.len() - 1) as f32;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_similarity() {
+        let text = "猫喜欢吃鱼。猫每天睡很久。\n\n股市今天上涨。股市明天可能下跌。";
+        let sentences = TextSplitter::new(100, 0).split_sentences(text);
+        assert_eq!(sentences.len(), 4);
+        let embeddings = vec![vec![1.0, 0.1], vec![0.9, 0.2], vec![0.1, 1.0], vec![0.2, 0.9]];
+
+        let chunks = SemanticSplitter::new(100, 90.0).group(text, &sentences, &embeddings);
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(contents, vec!["猫喜欢吃鱼。猫每天睡很久。", "股市今天上涨。股市明天可能下跌。"]);
+        assert_eq!(chunks[1].start, text.chars().position(|c| c == '股').unwrap());
+
+        // 超过块大小时即使语义相近也会断开
+        let chunks = SemanticSplitter::new(8, 90.0).group(text, &sentences, &embeddings);
+        assert_eq!(chunks.len(), 4);
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 90.0), f32::INFINITY);
+        assert_eq!(percentile(&[3.0, 1.0, 2.0], 50.0), 2.0);
+        assert!((percentile(&[0.0, 0.0, 1.0], 90.0) - 0.8).abs() < 1e-6);
+    }
+}
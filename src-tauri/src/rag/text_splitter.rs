@@ -1,7 +1,175 @@
+use std::ops::Range;
+
+use super::tokenizer::ChunkUnit;
+
+/// 带位置信息的文本块
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextChunk {
+    pub content: String,
+    /// 在原文中的起始字符偏移（按 Unicode 字符计）
+    pub start: usize,
+    /// 在原文中的结束字符偏移（不含）
+    pub end: usize,
+    /// 所属章节（例如电子书的章标题）
+    pub section: Option<String>,
+    /// 块所在的起止页码（从 1 开始，仅分页文档如 PDF 有值）
+    pub page_start: Option<usize>,
+    pub page_end: Option<usize>,
+    /// 代码块所属的符号（函数、类型、impl 等）
+    pub symbol: Option<String>,
+}
+
+/// 文档中的章节范围，分块时不会跨越章节边界
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: String,
+    /// 起始字符偏移
+    pub start: usize,
+    /// 结束字符偏移（不含）
+    pub end: usize,
+}
+
+/// 根据每页的起始偏移为文本块标注页码
+///
+/// `page_starts[i]` 为第 `i + 1` 页在原文中的起始字符偏移（升序）；
+/// 跨页的块记录起止两页。
+pub fn assign_pages(chunks: &mut [TextChunk], page_starts: &[usize]) {
+    if page_starts.is_empty() {
+        return;
+    }
+    
+    // 起始偏移不大于 offset 的最后一页（空白页与下一页起点相同，会被跳过）
+    let page_at = |offset: usize| page_starts.partition_point(|&start| start <= offset).max(1);
+    
+    for chunk in chunks {
+        chunk.page_start = Some(page_at(chunk.start));
+        chunk.page_end = Some(page_at(chunk.end.saturating_sub(1).max(chunk.start)));
+    }
+}
+
+/// 递归分割依次尝试的边界，从粗到细；都无法满足长度时按固定长度切分
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Boundary {
+    Paragraph,
+    Line,
+    Sentence,
+    /// 逗号、分号等分句标点
+    Clause,
+    Word,
+}
+
+const BOUNDARIES: [Boundary; 5] = [
+    Boundary::Paragraph,
+    Boundary::Line,
+    Boundary::Sentence,
+    Boundary::Clause,
+    Boundary::Word,
+];
+
+/// 句末标点
+fn is_sentence_terminator(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '!' | '?' | '.' | '…')
+}
+
+/// 紧跟在句末标点后、仍属于本句的右引号和右括号
+fn is_closing(c: char) -> bool {
+    matches!(c, '”' | '’' | '」' | '』' | '）' | ')' | '"' | '\'' | ']' | '】' | '》')
+}
+
+/// 在 `i` 处的边界结束位置（不含），不是边界时返回 None
+fn boundary_end(chars: &[char], i: usize, end: usize, boundary: Boundary) -> Option<usize> {
+    let c = chars[i];
+    let mut j = i + 1;
+    match boundary {
+        Boundary::Paragraph => {
+            if c != '\n' || chars.get(j) != Some(&'\n') || j >= end {
+                return None;
+            }
+            while j < end && chars[j].is_whitespace() {
+                j += 1;
+            }
+        }
+        Boundary::Line => {
+            if c != '\n' {
+                return None;
+            }
+        }
+        Boundary::Sentence => {
+            if !is_sentence_terminator(c) {
+                return None;
+            }
+            while j < end && (is_sentence_terminator(chars[j]) || is_closing(chars[j])) {
+                j += 1;
+            }
+            // 英文句点后须为空白或文本结尾，避免切开 3.14、e.g 之类
+            if c == '.' && j < end && !chars[j].is_whitespace() {
+                return None;
+            }
+            while j < end && chars[j].is_whitespace() && chars[j] != '\n' {
+                j += 1;
+            }
+        }
+        Boundary::Clause => {
+            if !matches!(c, '，' | '、' | '；' | '：' | ',' | ';' | ':') {
+                return None;
+            }
+            while j < end && chars[j].is_whitespace() && chars[j] != '\n' {
+                j += 1;
+            }
+        }
+        Boundary::Word => {
+            if !c.is_whitespace() {
+                return None;
+            }
+            while j < end && chars[j].is_whitespace() {
+                j += 1;
+            }
+        }
+    }
+    Some(j)
+}
+
+/// 将范围按边界切成连续的片段，分隔符归入前一个片段
+fn segment(chars: &[char], range: Range<usize>, boundary: Boundary) -> Vec<Range<usize>> {
+    let mut pieces = Vec::new();
+    let mut start = range.start;
+    let mut i = range.start;
+
+    while i < range.end {
+        match boundary_end(chars, i, range.end, boundary) {
+            Some(end) => {
+                pieces.push(start..end);
+                start = end;
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    if start < range.end {
+        pieces.push(start..range.end);
+    }
+
+    pieces
+}
+
+/// 去掉范围首尾的空白字符
+fn trim_range(chars: &[char], range: Range<usize>) -> Range<usize> {
+    let mut start = range.start;
+    let mut end = range.end;
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    start..end
+}
+
 /// 文本分块器
 pub struct TextSplitter {
     chunk_size: usize,
     chunk_overlap: usize,
+    unit: ChunkUnit,
 }
 
 impl TextSplitter {
@@ -9,11 +177,26 @@ impl TextSplitter {
         Self {
             chunk_size,
             chunk_overlap,
+            unit: ChunkUnit::Chars,
         }
     }
     
+    /// 设置块大小和重叠长度的计量单位（默认按字符）
+    pub fn with_unit(mut self, unit: ChunkUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+    
     /// 将文本分割成多个块
     pub fn split(&self, text: &str) -> Vec<String> {
+        self.split_with_offsets(text, 0)
+            .into_iter()
+            .map(|chunk| chunk.content)
+            .collect()
+    }
+    
+    /// 按固定长度分割，偏移量从 `base` 开始计算
+    pub(crate) fn split_with_offsets(&self, text: &str, base: usize) -> Vec<TextChunk> {
         if text.is_empty() {
             return Vec::new();
         }
@@ -21,24 +204,34 @@ impl TextSplitter {
         let chars: Vec<char> = text.chars().collect();
         let total_len = chars.len();
         
-        if total_len <= self.chunk_size {
-            return vec![text.to_string()];
+        if self.unit.measure(text) <= self.chunk_size {
+            return vec![TextChunk {
+                content: text.to_string(),
+                start: base,
+                end: base + total_len,
+                ..Default::default()
+            }];
         }
         
         let mut chunks = Vec::new();
         let mut start = 0;
         
         while start < total_len {
-            let end = (start + self.chunk_size).min(total_len);
-            let chunk: String = chars[start..end].iter().collect();
-            chunks.push(chunk);
+            let end = start + self.unit.fit_prefix(&chars[start..], self.chunk_size);
+            chunks.push(TextChunk {
+                content: chars[start..end].iter().collect(),
+                start: base + start,
+                end: base + end,
+                ..Default::default()
+            });
             
             if end >= total_len {
                 break;
             }
             
-            // 下一个块的起始位置，考虑重叠
-            start = end - self.chunk_overlap;
+            // 下一个块的起始位置，考虑重叠（至少前进一个字符）
+            let overlap = self.unit.fit_suffix(&chars[start..end], self.chunk_overlap);
+            start = (end - overlap).max(start + 1);
         }
         
         chunks
@@ -54,17 +247,185 @@ impl TextSplitter {
     
     /// 智能分割：先按段落，如果段落太长再按固定长度
     pub fn split_smart(&self, text: &str) -> Vec<String> {
-        let paragraphs = self.split_by_paragraphs(text);
+        self.split_smart_chunks(text)
+            .into_iter()
+            .map(|chunk| chunk.content)
+            .collect()
+    }
+    
+    /// 智能分割，并记录每个块在原文中的字符范围
+    pub fn split_smart_chunks(&self, text: &str) -> Vec<TextChunk> {
+        self.split_smart_from(text, 0)
+    }
+    
+    /// 递归分割：优先在段落、换行、句子（`。！？`、`.!?`）、分句标点和词的边界处切分
+    ///
+    /// 只有连单词都超过块大小时才按固定长度截断；切分后的片段再合并到接近块大小，
+    /// 相邻块之间保留不超过 `chunk_overlap` 的完整片段作为重叠。
+    pub fn split_recursive(&self, text: &str) -> Vec<TextChunk> {
+        self.split_recursive_from(text, 0)
+    }
+    
+    /// 递归分割，偏移量从 `base` 开始计算
+    pub(crate) fn split_recursive_from(&self, text: &str, base: usize) -> Vec<TextChunk> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut atoms = Vec::new();
+        self.collect_atoms(&chars, 0..chars.len(), 0, &mut atoms);
+        
+        let lengths: Vec<usize> = atoms.iter()
+            .map(|range| self.unit.measure_chars(&chars[range.clone()]))
+            .collect();
+        let mut result = Vec::new();
+        let mut push = |range: Range<usize>| {
+            let range = trim_range(&chars, range);
+            if !range.is_empty() {
+                result.push(TextChunk {
+                    content: chars[range.clone()].iter().collect(),
+                    start: base + range.start,
+                    end: base + range.end,
+                    ..Default::default()
+                });
+            }
+        };
+        
+        let mut first = 0;
+        let mut total = 0;
+        for i in 0..atoms.len() {
+            if i > first && total + lengths[i] > self.chunk_size {
+                push(atoms[first].start..atoms[i - 1].end);
+                // 保留末尾的片段作为重叠，同时保证能放下当前片段
+                while first < i && (total > self.chunk_overlap || total + lengths[i] > self.chunk_size) {
+                    total -= lengths[first];
+                    first += 1;
+                }
+            }
+            total += lengths[i];
+        }
+        if let Some(last) = atoms.last() {
+            push(atoms[first].start..last.end);
+        }
+        
+        result
+    }
+    
+    /// 切分为句子，过长的句子继续按分句标点、词或固定长度切分
+    pub(crate) fn split_sentences(&self, text: &str) -> Vec<TextChunk> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut ranges = segment(&chars, 0..chars.len(), Boundary::Paragraph);
+        for boundary in [Boundary::Line, Boundary::Sentence] {
+            ranges = ranges.into_iter()
+                .flat_map(|range| segment(&chars, range, boundary))
+                .collect();
+        }
+        
+        let clause_level = BOUNDARIES.iter().position(|b| *b == Boundary::Clause).unwrap_or(0);
+        let mut atoms = Vec::new();
+        for range in ranges {
+            self.collect_atoms(&chars, range, clause_level, &mut atoms);
+        }
+        
+        atoms.into_iter()
+            .map(|range| trim_range(&chars, range))
+            .filter(|range| !range.is_empty())
+            .map(|range| TextChunk {
+                content: chars[range.clone()].iter().collect(),
+                start: range.start,
+                end: range.end,
+                ..Default::default()
+            })
+            .collect()
+    }
+    
+    /// 将范围切成都不超过块大小的片段，依次尝试更细的边界
+    fn collect_atoms(&self, chars: &[char], range: Range<usize>, level: usize, atoms: &mut Vec<Range<usize>>) {
+        if range.is_empty() {
+            return;
+        }
+        if self.unit.measure_chars(&chars[range.clone()]) <= self.chunk_size {
+            atoms.push(range);
+            return;
+        }
+        
+        match BOUNDARIES.get(level) {
+            Some(&boundary) => {
+                for piece in segment(chars, range, boundary) {
+                    self.collect_atoms(chars, piece, level + 1, atoms);
+                }
+            }
+            None => {
+                let mut start = range.start;
+                while start < range.end {
+                    let len = self.unit.fit_prefix(&chars[start..range.end], self.chunk_size);
+                    atoms.push(start..start + len);
+                    start += len;
+                }
+            }
+        }
+    }
+    
+    /// 按章节分别分块，块不会跨越章节，并带上章节标题
+    ///
+    /// 章节之间或之外的文本作为无标题的部分单独分块。
+    pub fn split_sections(&self, text: &str, sections: &[Section]) -> Vec<TextChunk> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut sections: Vec<&Section> = sections.iter()
+            .filter(|s| s.start < s.end && s.end <= chars.len())
+            .collect();
+        sections.sort_by_key(|s| s.start);
+        
         let mut result = Vec::new();
+        let mut offset = 0;
+        let mut push_range = |start: usize, end: usize, title: Option<&str>| {
+            let part: String = chars[start..end].iter().collect();
+            result.extend(self.split_recursive_from(&part, start).into_iter().map(|mut chunk| {
+                chunk.section = title.map(str::to_string);
+                chunk
+            }));
+        };
         
-        for para in paragraphs {
-            if para.chars().count() <= self.chunk_size {
-                result.push(para);
-            } else {
-                // 段落太长，按固定长度分割
-                let sub_chunks = self.split(&para);
-                result.extend(sub_chunks);
+        for section in sections {
+            // 与上一章节重叠的部分只归属上一章节
+            let start = section.start.max(offset);
+            if start >= section.end {
+                continue;
+            }
+            if start > offset {
+                push_range(offset, start, None);
             }
+            push_range(start, section.end, Some(&section.title));
+            offset = section.end;
+        }
+        if offset < chars.len() {
+            push_range(offset, chars.len(), None);
+        }
+        
+        result
+    }
+    
+    /// 智能分割，偏移量从 `base` 开始计算
+    fn split_smart_from(&self, text: &str, base: usize) -> Vec<TextChunk> {
+        let mut result = Vec::new();
+        let mut offset = base;
+        
+        for para in text.split("\n\n") {
+            let para_len = para.chars().count();
+            
+            if !para.trim().is_empty() {
+                if self.unit.measure(para) <= self.chunk_size {
+                    result.push(TextChunk {
+                        content: para.to_string(),
+                        start: offset,
+                        end: offset + para_len,
+                        ..Default::default()
+                    });
+                } else {
+                    // 段落太长，按固定长度分割
+                    result.extend(self.split_with_offsets(para, offset));
+                }
+            }
+            
+            // 跳过段落本身和分隔用的两个换行
+            offset += para_len + 2;
         }
         
         result
@@ -117,5 +478,114 @@ mod tests {
             println!("智能分块 {}: {}", i + 1, chunk);
         }
     }
+    
+    #[test]
+    fn test_split_smart_chunks_offsets() {
+        let splitter = TextSplitter::new(20, 5);
+        let text = "短段落。\n\n这是一个非常非常非常非常非常非常非常非常长的段落需要分割。\n\n\n\n结尾。";
+        let chars: Vec<char> = text.chars().collect();
+        let chunks = splitter.split_smart_chunks(text);
+        
+        assert_eq!(chunks.len(), splitter.split_smart(text).len());
+        for chunk in &chunks {
+            let slice: String = chars[chunk.start..chunk.end].iter().collect();
+            assert_eq!(slice, chunk.content);
+        }
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().content, "结尾。");
+    }
+    
+    #[test]
+    fn test_split_sections() {
+        let splitter = TextSplitter::new(30, 0);
+        let text = "前言。\n\n第一章正文第一段。第一章正文第一段。\n\n第二章正文。";
+        let chapter1_start = text.chars().position(|c| c == '第').unwrap();
+        let chapter2_start = text.chars().count() - "第二章正文。".chars().count();
+        let sections = vec![
+            Section { title: "第一章".to_string(), start: chapter1_start, end: chapter2_start },
+            Section { title: "第二章".to_string(), start: chapter2_start, end: text.chars().count() },
+        ];
+        
+        let chunks = splitter.split_sections(text, &sections);
+        let summary: Vec<(&str, Option<&str>)> = chunks.iter()
+            .map(|c| (c.content.as_str(), c.section.as_deref()))
+            .collect();
+        
+        assert_eq!(summary, vec![
+            ("前言。", None),
+            ("第一章正文第一段。第一章正文第一段。", Some("第一章")),
+            ("第二章正文。", Some("第二章")),
+        ]);
+        assert_eq!(chunks[2].start, chapter2_start);
+    }
+    
+    #[test]
+    fn test_assign_pages() {
+        let splitter = TextSplitter::new(10, 0);
+        // 第 1 页 "第一页内容。"，第 2 页为空白页，第 3 页 "第三页内容较长一些。"
+        let text = "第一页内容。\n\n第三页内容较长一些。";
+        let mut chunks = splitter.split_smart_chunks(text);
+        assign_pages(&mut chunks, &[0, 8, 8]);
+        
+        let pages: Vec<(Option<usize>, Option<usize>)> = chunks.iter()
+            .map(|c| (c.page_start, c.page_end))
+            .collect();
+        assert_eq!(pages, vec![(Some(1), Some(1)), (Some(3), Some(3))]);
+        
+        let mut whole = vec![TextChunk { content: text.to_string(), start: 0, end: 18, ..Default::default() }];
+        assign_pages(&mut whole, &[0, 8, 8]);
+        assert_eq!((whole[0].page_start, whole[0].page_end), (Some(1), Some(3)));
+    }
+    
+    #[test]
+    fn test_split_recursive() {
+        let splitter = TextSplitter::new(24, 10);
+        let text = "第一句话很短。第二句话稍微长一点！第三句？\n\nEnglish sentence one. Another one here.";
+        let chars: Vec<char> = text.chars().collect();
+        let chunks = splitter.split_recursive(text);
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        
+        assert_eq!(contents, vec![
+            "第一句话很短。第二句话稍微长一点！第三句？",
+            "English sentence one.",
+            "Another one here.",
+        ]);
+        for chunk in &chunks {
+            assert_eq!(chunk.content, chars[chunk.start..chunk.end].iter().collect::<String>());
+        }
+        
+        // 相邻块以完整的句子重叠
+        let chunks = TextSplitter::new(8, 4).split_recursive("一二三。四五六。七八九。十一二。");
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(contents, vec!["一二三。四五六。", "四五六。七八九。", "七八九。十一二。"]);
+    }
+    
+    #[test]
+    fn test_split_sentences() {
+        let splitter = TextSplitter::new(100, 0);
+        let text = "版本号为 3.14。这是“引用的话！”之后的内容 ends here. Next one? 最后一句";
+        let sentences: Vec<String> = splitter.split_sentences(text).into_iter().map(|c| c.content).collect();
+        
+        assert_eq!(sentences, vec!["版本号为 3.14。", "这是“引用的话！”", "之后的内容 ends here.", "Next one?", "最后一句"]);
+    }
+    
+    #[test]
+    fn test_split_by_tokens() {
+        let splitter = TextSplitter::new(8, 2).with_unit(ChunkUnit::Tokens);
+        let text = "Retrieval augmented generation combines search with language models to answer questions.";
+        let chars: Vec<char> = text.chars().collect();
+        let chunks = splitter.split_with_offsets(text, 0);
+        
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, chars.len());
+        for pair in chunks.windows(2) {
+            assert!(pair[1].start < pair[0].end, "相邻块应有重叠");
+        }
+        for chunk in &chunks {
+            assert!(ChunkUnit::Tokens.measure(&chunk.content) <= 8);
+            assert_eq!(chunk.content, chars[chunk.start..chunk.end].iter().collect::<String>());
+        }
+    }
 }
 
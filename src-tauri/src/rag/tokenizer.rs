@@ -0,0 +1,126 @@
+//! 文本长度计量：按字符或按 token
+//!
+//! token 计数使用随程序打包的 cl100k BPE 词表（通义千问的分词器在其基础上扩充了
+//! 中文词汇），不需要联网，得到的是与模型计费口径接近的估算值。分块大小和提供给
+//! 模型的上下文上限共用这里的计数方式。
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+/// 分块大小的计量单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChunkUnit {
+    /// 按 Unicode 字符计数
+    #[default]
+    #[serde(rename = "chars")]
+    Chars,
+    /// 按 BPE token 计数
+    #[serde(rename = "tokens")]
+    Tokens,
+}
+
+/// 内置的 BPE 分词器，首次使用时加载
+fn bpe() -> &'static CoreBPE {
+    static BPE: OnceLock<CoreBPE> = OnceLock::new();
+    BPE.get_or_init(|| tiktoken_rs::cl100k_base().expect("内置 BPE 词表加载失败"))
+}
+
+impl ChunkUnit {
+    /// 计算文本长度
+    pub fn measure(self, text: &str) -> usize {
+        match self {
+            ChunkUnit::Chars => text.chars().count(),
+            ChunkUnit::Tokens => bpe().encode_ordinary(text).len(),
+        }
+    }
+
+    /// 计算字符序列的长度
+    pub fn measure_chars(self, chars: &[char]) -> usize {
+        match self {
+            ChunkUnit::Chars => chars.len(),
+            ChunkUnit::Tokens => self.measure(&chars.iter().collect::<String>()),
+        }
+    }
+
+    /// 从开头起不超过 `budget` 的最长前缀的字符数（非空文本至少返回 1，保证分块能前进）
+    pub fn fit_prefix(self, chars: &[char], budget: usize) -> usize {
+        let fitted = match self {
+            ChunkUnit::Chars => budget.min(chars.len()),
+            ChunkUnit::Tokens => longest_within(chars.len(), budget, |n| self.measure_chars(&chars[..n])),
+        };
+        fitted.max(1).min(chars.len())
+    }
+
+    /// 从末尾起不超过 `budget` 的最长后缀的字符数
+    pub fn fit_suffix(self, chars: &[char], budget: usize) -> usize {
+        match self {
+            ChunkUnit::Chars => budget.min(chars.len()),
+            ChunkUnit::Tokens => {
+                longest_within(chars.len(), budget, |n| self.measure_chars(&chars[chars.len() - n..]))
+            }
+        }
+    }
+}
+
+/// 在 `0..=len` 中查找 `measure(n) <= budget` 的最大 n
+///
+/// 先从 `budget` 个字符开始倍增找到上界，再二分；token 数随字符数近似单调递增。
+fn longest_within(len: usize, budget: usize, measure: impl Fn(usize) -> usize) -> usize {
+    let mut low = 0;
+    let mut probe = budget.max(1);
+    let high = loop {
+        if probe >= len {
+            if measure(len) <= budget {
+                return len;
+            }
+            break len;
+        }
+        if measure(probe) > budget {
+            break probe;
+        }
+        low = probe;
+        probe *= 2;
+    };
+
+    // 不变式：measure(low) <= budget < measure(high)
+    let mut high = high;
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if measure(mid) <= budget {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        assert_eq!(ChunkUnit::Chars.measure("hello 世界"), 8);
+        assert_eq!(ChunkUnit::Tokens.measure("hello world"), 2);
+        assert!(ChunkUnit::Tokens.measure("知识库问答") >= 3);
+    }
+
+    #[test]
+    fn test_fit_prefix_and_suffix() {
+        let chars: Vec<char> = "The quick brown fox jumps over the lazy dog. 敏捷的棕色狐狸跳过了懒狗。".chars().collect();
+        assert_eq!(ChunkUnit::Chars.fit_prefix(&chars, 5), 5);
+        assert_eq!(ChunkUnit::Chars.fit_suffix(&chars, 500), chars.len());
+
+        for budget in [1, 4, 10, 25] {
+            let prefix = ChunkUnit::Tokens.fit_prefix(&chars, budget);
+            assert!(ChunkUnit::Tokens.measure_chars(&chars[..prefix]) <= budget);
+            assert!(ChunkUnit::Tokens.measure_chars(&chars[..prefix + 1]) > budget);
+
+            let suffix = ChunkUnit::Tokens.fit_suffix(&chars, budget);
+            assert!(ChunkUnit::Tokens.measure_chars(&chars[chars.len() - suffix..]) <= budget);
+        }
+        assert_eq!(ChunkUnit::Tokens.fit_prefix(&chars, 1000), chars.len());
+    }
+}
@@ -1,4 +1,8 @@
+use super::hnsw::{HnswIndex, HnswParams, VectorSource};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,97 +19,385 @@ pub struct SearchResult {
     pub similarity: f32,
 }
 
-/// 内存向量存储
+/// 文档数量不超过该值时直接精确扫描，超过后使用 HNSW 近似检索
+const BRUTE_FORCE_THRESHOLD: usize = 2000;
+
+/// 持久化索引文件的格式版本
+const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// 槽位存储，与 HNSW 节点一一对应；已删除的文档保留向量供图导航使用
+struct Slots(Vec<VectorDocument>);
+
+impl VectorSource for Slots {
+    fn vector(&self, id: u32) -> &[f32] {
+        &self.0[id as usize].embedding
+    }
+}
+
+struct StoreInner {
+    slots: Slots,
+    index: HnswIndex,
+    /// 文档块 ID -> 槽位
+    id_to_slot: HashMap<String, u32>,
+    /// 文档 ID -> 槽位列表
+    document_slots: HashMap<String, Vec<u32>>,
+}
+
+/// 写入索引文件的结构（借用，避免复制整张图）
+#[derive(Serialize)]
+struct PersistedIndexRef<'a> {
+    version: u32,
+    ids: Vec<&'a str>,
+    tombstones: Vec<(u32, &'a [f32])>,
+    index: &'a HnswIndex,
+}
+
+/// 从索引文件读取的结构
+#[derive(Deserialize)]
+struct PersistedIndex {
+    version: u32,
+    /// 每个槽位对应的文档块 ID
+    ids: Vec<String>,
+    /// 已删除槽位的向量（数据库中已不存在，恢复导航时需要）
+    tombstones: Vec<(u32, Vec<f32>)>,
+    index: HnswIndex,
+}
+
+impl StoreInner {
+    fn new() -> Self {
+        Self {
+            slots: Slots(Vec::new()),
+            index: HnswIndex::new(HnswParams::default()),
+            id_to_slot: HashMap::new(),
+            document_slots: HashMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.id_to_slot.len()
+    }
+
+    /// 文档当前有效的槽位数（被同 ID 新文档替换的旧槽位不计入）
+    fn live_slots(&self, slots: &[u32]) -> usize {
+        slots.iter()
+            .filter(|&&slot| self.id_to_slot.get(&self.slots.0[slot as usize].id) == Some(&slot))
+            .count()
+    }
+
+    /// 追加文档（向量归一化后存储，余弦相似度即点积）；同 ID 的旧文档会被替换
+    fn insert(&mut self, mut doc: VectorDocument) {
+        normalize(&mut doc.embedding);
+
+        if let Some(old_slot) = self.id_to_slot.get(&doc.id).copied() {
+            self.delete_slot(old_slot);
+        }
+
+        let slot = self.slots.0.len() as u32;
+        self.id_to_slot.insert(doc.id.clone(), slot);
+        if let Some(document_id) = document_id_of(&doc) {
+            self.document_slots.entry(document_id).or_default().push(slot);
+        }
+        self.slots.0.push(doc);
+        self.index.insert(slot, &self.slots);
+    }
+
+    fn delete_slot(&mut self, slot: u32) {
+        let doc = &self.slots.0[slot as usize];
+        if self.id_to_slot.get(&doc.id) == Some(&slot) {
+            self.id_to_slot.remove(&doc.id);
+        }
+        self.index.delete(slot);
+    }
+
+    /// 墓碑超过三分之一时重建索引，释放已删除文档占用的内存
+    fn maybe_compact(&mut self) {
+        if self.index.deleted_count() * 3 <= self.index.len() {
+            return;
+        }
+
+        let old = std::mem::replace(self, Self::new());
+        for (slot, doc) in old.slots.0.into_iter().enumerate() {
+            if !old.index.is_deleted(slot as u32) {
+                self.insert(doc);
+            }
+        }
+    }
+
+    /// 精确扫描所有未删除的文档
+    fn brute_force_search(&self, query: &[f32], top_k: usize) -> Vec<(u32, f32)> {
+        let mut scored: Vec<(u32, f32)> = self.id_to_slot
+            .values()
+            .map(|&slot| (slot, dot(query, &self.slots.0[slot as usize].embedding)))
+            .collect();
+
+        let by_score_desc = |a: &(u32, f32), b: &(u32, f32)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+        if scored.len() > top_k {
+            scored.select_nth_unstable_by(top_k, by_score_desc);
+            scored.truncate(top_k);
+        }
+        scored.sort_by(by_score_desc);
+        scored
+    }
+
+    /// 复用已保存的 HNSW 图；图中缺失的文档增量插入。图与数据不一致时返回 false
+    fn attach(&mut self, persisted: PersistedIndex, docs: &mut Vec<Option<VectorDocument>>) -> bool {
+        if persisted.version != INDEX_FORMAT_VERSION || persisted.ids.len() != persisted.index.len() {
+            return false;
+        }
+
+        let positions: HashMap<&str, usize> = docs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.as_ref().map(|d| (d.id.as_str(), i)))
+            .collect();
+        let mut tombstones: HashMap<u32, Vec<f32>> = persisted.tombstones.into_iter().collect();
+
+        // 先校验：未删除的槽位必须能在数据库中找到，已删除的槽位必须有保存的向量
+        let valid = persisted.ids.iter().enumerate().all(|(slot, id)| {
+            if persisted.index.is_deleted(slot as u32) {
+                tombstones.contains_key(&(slot as u32))
+            } else {
+                positions.contains_key(id.as_str())
+            }
+        });
+        if !valid {
+            return false;
+        }
+
+        let slot_positions: Vec<Option<usize>> = persisted.ids
+            .iter()
+            .map(|id| positions.get(id.as_str()).copied())
+            .collect();
+
+        self.index = persisted.index;
+        for (slot, id) in persisted.ids.into_iter().enumerate() {
+            let slot_id = slot as u32;
+            if self.index.is_deleted(slot_id) {
+                self.slots.0.push(VectorDocument {
+                    id,
+                    content: String::new(),
+                    embedding: tombstones.remove(&slot_id).unwrap_or_default(),
+                    metadata: serde_json::Value::Null,
+                });
+                continue;
+            }
+
+            let mut doc = slot_positions[slot]
+                .and_then(|i| docs[i].take())
+                .expect("已校验的文档块必须存在");
+            normalize(&mut doc.embedding);
+
+            self.id_to_slot.insert(doc.id.clone(), slot_id);
+            if let Some(document_id) = document_id_of(&doc) {
+                self.document_slots.entry(document_id).or_default().push(slot_id);
+            }
+            self.slots.0.push(doc);
+        }
+
+        for doc in docs.drain(..).flatten() {
+            self.insert(doc);
+        }
+
+        true
+    }
+}
+
+/// 向量存储
+///
+/// 文档较少时精确扫描，较多时使用 HNSW 近似最近邻索引；
+/// 索引图可以持久化到磁盘，重启后无需重新构建。
 pub struct VectorStore {
-    documents: Arc<Mutex<Vec<VectorDocument>>>,
+    inner: Arc<Mutex<StoreInner>>,
 }
 
 impl VectorStore {
     pub fn new() -> Self {
         Self {
-            documents: Arc::new(Mutex::new(Vec::new())),
+            inner: Arc::new(Mutex::new(StoreInner::new())),
         }
     }
     
     /// 添加文档
     pub fn add_document(&self, doc: VectorDocument) {
-        let mut docs = self.documents.lock().unwrap();
-        docs.push(doc);
+        self.inner.lock().unwrap().insert(doc);
     }
     
     /// 批量添加文档
     pub fn add_documents(&self, new_docs: Vec<VectorDocument>) {
-        let mut docs = self.documents.lock().unwrap();
-        docs.extend(new_docs);
+        let mut inner = self.inner.lock().unwrap();
+        for doc in new_docs {
+            inner.insert(doc);
+        }
     }
     
     /// 相似度搜索
     pub fn search(&self, query_embedding: &[f32], top_k: usize) -> Vec<SearchResult> {
-        let docs = self.documents.lock().unwrap();
+        let inner = self.inner.lock().unwrap();
         
-        if docs.is_empty() {
+        if inner.len() == 0 || top_k == 0 {
             return Vec::new();
         }
         
-        let mut results: Vec<SearchResult> = docs
-            .iter()
-            .map(|doc| {
-                let similarity = cosine_similarity(&doc.embedding, query_embedding);
-                SearchResult {
-                    document: doc.clone(),
-                    similarity,
-                }
+        let mut query = query_embedding.to_vec();
+        normalize(&mut query);
+        
+        let hits = if inner.len() <= BRUTE_FORCE_THRESHOLD {
+            inner.brute_force_search(&query, top_k)
+        } else {
+            inner.index.search(&query, top_k, &inner.slots)
+        };
+        
+        // 只克隆最终返回的 Top-K 文档
+        hits.into_iter()
+            .map(|(slot, similarity)| SearchResult {
+                document: inner.slots.0[slot as usize].clone(),
+                similarity,
             })
-            .collect();
+            .collect()
+    }
+    
+    /// 按 ID 取出文档并计算与查询向量的相似度（用于关键词检索命中的文档块）
+    ///
+    /// 返回顺序与 `ids` 一致，不存在的 ID 会被跳过。
+    pub fn score_documents(&self, ids: &[String], query_embedding: &[f32]) -> Vec<SearchResult> {
+        let inner = self.inner.lock().unwrap();
         
-        // 按相似度降序排序
-        results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
+        let mut query = query_embedding.to_vec();
+        normalize(&mut query);
         
-        // 返回 Top-K
-        results.into_iter().take(top_k).collect()
+        ids.iter()
+            .filter_map(|id| inner.id_to_slot.get(id))
+            .map(|&slot| {
+                let document = &inner.slots.0[slot as usize];
+                SearchResult {
+                    similarity: dot(&document.embedding, &query),
+                    document: document.clone(),
+                }
+            })
+            .collect()
     }
     
     /// 根据文档 ID 删除
     pub fn remove_by_document_id(&self, document_id: &str) {
-        let mut docs = self.documents.lock().unwrap();
-        docs.retain(|doc| {
-            if let Some(id) = doc.metadata.get("document_id") {
-                id.as_str() != Some(document_id)
-            } else {
-                true
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(slots) = inner.document_slots.remove(document_id) {
+            for slot in slots {
+                inner.delete_slot(slot);
             }
-        });
+            inner.maybe_compact();
+        }
+    }
+    
+    /// 某个文档在向量存储中的块数
+    pub fn document_len(&self, document_id: &str) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.document_slots.get(document_id)
+            .map_or(0, |slots| inner.live_slots(slots))
+    }
+    
+    /// 向量存储中所有文档的 ID
+    pub fn document_ids(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.document_slots.iter()
+            .filter(|(_, slots)| inner.live_slots(slots) > 0)
+            .map(|(document_id, _)| document_id.clone())
+            .collect()
     }
     
     /// 清空所有文档
     pub fn clear(&self) {
-        let mut docs = self.documents.lock().unwrap();
-        docs.clear();
+        *self.inner.lock().unwrap() = StoreInner::new();
     }
     
     /// 获取文档数量
     pub fn len(&self) -> usize {
-        self.documents.lock().unwrap().len()
+        self.inner.lock().unwrap().len()
     }
     
     /// 是否为空
     pub fn is_empty(&self) -> bool {
-        self.documents.lock().unwrap().is_empty()
+        self.len() == 0
     }
     
     /// 序列化保存
     pub fn serialize(&self) -> Result<String, serde_json::Error> {
-        let docs = self.documents.lock().unwrap();
-        serde_json::to_string(&*docs)
+        let inner = self.inner.lock().unwrap();
+        let docs: Vec<&VectorDocument> = inner.slots.0
+            .iter()
+            .enumerate()
+            .filter(|(slot, _)| !inner.index.is_deleted(*slot as u32))
+            .map(|(_, doc)| doc)
+            .collect();
+        serde_json::to_string(&docs)
     }
     
     /// 从序列化数据加载
     pub fn deserialize(&self, data: &str) -> Result<(), serde_json::Error> {
         let docs: Vec<VectorDocument> = serde_json::from_str(data)?;
-        let mut store_docs = self.documents.lock().unwrap();
-        *store_docs = docs;
+        self.clear();
+        self.add_documents(docs);
         Ok(())
     }
+    
+    /// 导出 HNSW 图（bincode），包含槽位与文档块 ID 的对应关系
+    pub fn snapshot_index(&self) -> Result<Vec<u8>> {
+        let inner = self.inner.lock().unwrap();
+        
+        let tombstones = inner.slots.0
+            .iter()
+            .enumerate()
+            .filter(|(slot, _)| inner.index.is_deleted(*slot as u32))
+            .map(|(slot, doc)| (slot as u32, doc.embedding.as_slice()))
+            .collect();
+        
+        let persisted = PersistedIndexRef {
+            version: INDEX_FORMAT_VERSION,
+            ids: inner.slots.0.iter().map(|d| d.id.as_str()).collect(),
+            tombstones,
+            index: &inner.index,
+        };
+        
+        Ok(bincode::serialize(&persisted)?)
+    }
+    
+    /// 将 HNSW 图保存到文件（先写临时文件再重命名，避免写到一半的文件）
+    pub fn save_index(&self, path: &Path) -> Result<()> {
+        write_index_file(path, &self.snapshot_index()?)
+    }
+    
+    /// 用数据库中的向量重建存储，优先复用保存过的 HNSW 图
+    ///
+    /// 返回是否复用了已保存的图；图文件缺失或与数据不一致时重新构建。
+    pub fn restore(&self, docs: Vec<VectorDocument>, index_path: Option<&Path>) -> bool {
+        let persisted = index_path
+            .filter(|p| p.exists())
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| bincode::deserialize::<PersistedIndex>(&bytes).ok());
+        
+        let mut docs: Vec<Option<VectorDocument>> = docs.into_iter().map(Some).collect();
+        let mut inner = StoreInner::new();
+        
+        let reused = match persisted {
+            Some(persisted) => {
+                let attached = inner.attach(persisted, &mut docs);
+                if !attached {
+                    println!("⚠️ 向量索引文件与数据库不一致，重新构建索引");
+                    inner = StoreInner::new();
+                }
+                attached
+            }
+            None => false,
+        };
+        
+        if !reused {
+            for doc in docs.into_iter().flatten() {
+                inner.insert(doc);
+            }
+        }
+        
+        *self.inner.lock().unwrap() = inner;
+        reused
+    }
 }
 
 impl Default for VectorStore {
@@ -114,8 +406,54 @@ impl Default for VectorStore {
     }
 }
 
+/// 写入索引文件（先写临时文件再重命名）
+pub fn write_index_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn document_id_of(doc: &VectorDocument) -> Option<String> {
+    doc.metadata
+        .get("document_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// L2 归一化（零向量保持不变）
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// 将向量编码为 BLOB（小端序 f32）
+pub fn encode_embedding(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// 从 BLOB 解码向量
+pub fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
 /// 计算余弦相似度
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         return 0.0;
     }
@@ -150,6 +488,15 @@ mod tests {
         assert!(sim > 0.7 && sim < 0.8);
     }
     
+    #[test]
+    fn test_embedding_roundtrip() {
+        let embedding = vec![0.25, -1.5, 3.0e-7, f32::MAX];
+        let bytes = encode_embedding(&embedding);
+        
+        assert_eq!(bytes.len(), embedding.len() * 4);
+        assert_eq!(decode_embedding(&bytes), embedding);
+    }
+    
     #[test]
     fn test_vector_store() {
         let store = VectorStore::new();
@@ -177,5 +524,83 @@ mod tests {
         assert_eq!(results[0].document.id, "1");
         assert!(results[0].similarity > results[1].similarity);
     }
+    
+    fn chunk(id: &str, document_id: &str, embedding: Vec<f32>) -> VectorDocument {
+        VectorDocument {
+            id: id.to_string(),
+            content: id.to_string(),
+            embedding,
+            metadata: json!({"document_id": document_id}),
+        }
+    }
+    
+    #[test]
+    fn test_remove_by_document_id() {
+        let store = VectorStore::new();
+        store.add_documents(vec![
+            chunk("a1", "a", vec![1.0, 0.0]),
+            chunk("a2", "a", vec![0.9, 0.1]),
+            chunk("b1", "b", vec![0.0, 1.0]),
+        ]);
+        
+        store.remove_by_document_id("a");
+        
+        assert_eq!(store.len(), 1);
+        let results = store.search(&[1.0, 0.0], 3);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.id, "b1");
+    }
+    
+    #[test]
+    fn test_document_len() {
+        let store = VectorStore::new();
+        store.add_documents(vec![
+            chunk("a1", "a", vec![1.0, 0.0]),
+            chunk("a2", "a", vec![0.9, 0.1]),
+            chunk("b1", "b", vec![0.0, 1.0]),
+        ]);
+        // 同 ID 替换不重复计数
+        store.add_document(chunk("a1", "a", vec![0.8, 0.2]));
+        
+        assert_eq!(store.document_len("a"), 2);
+        assert_eq!(store.document_len("c"), 0);
+        
+        store.remove_by_document_id("b");
+        assert_eq!(store.document_ids(), vec!["a".to_string()]);
+    }
+    
+    #[test]
+    fn test_restore_reuses_saved_index() {
+        let docs: Vec<VectorDocument> = (0..50)
+            .map(|i| {
+                let angle = i as f32 * 0.1;
+                chunk(&format!("c{}", i), &format!("d{}", i % 5), vec![angle.cos(), angle.sin(), 0.5])
+            })
+            .collect();
+        
+        let store = VectorStore::new();
+        store.add_documents(docs.clone());
+        store.remove_by_document_id("d0");
+        
+        let path = std::env::temp_dir().join(format!("wali-index-{}.bin", uuid::Uuid::new_v4()));
+        store.save_index(&path).unwrap();
+        
+        // 数据库中只剩未删除的块，并新增一个未进入索引的块
+        let mut remaining: Vec<VectorDocument> = docs.into_iter()
+            .filter(|d| d.metadata["document_id"] != "d0")
+            .collect();
+        remaining.push(chunk("new", "d9", vec![0.0, 0.0, 1.0]));
+        
+        let restored = VectorStore::new();
+        assert!(restored.restore(remaining, Some(&path)));
+        assert_eq!(restored.len(), 41);
+        assert_eq!(restored.search(&[0.0, 0.0, 1.0], 1)[0].document.id, "new");
+        
+        // 索引中的块在数据库里缺失时放弃复用，重新构建
+        let rebuilt = VectorStore::new();
+        assert!(!rebuilt.restore(vec![chunk("c1", "d1", vec![1.0, 0.0, 0.0])], Some(&path)));
+        assert_eq!(rebuilt.len(), 1);
+        
+        let _ = std::fs::remove_file(&path);
+    }
 }
-
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
 // ==================== 类型定义 ====================
 
@@ -7,7 +8,31 @@ export interface Message {
   role: 'user' | 'assistant'
   content: string
   timestamp?: number
-  sources?: string[]
+  sources?: MessageSource[]
+}
+
+export interface MessageSource {
+  document_id: string
+  document_name: string
+  chunk_id: string
+  chunk_index: number
+  start_offset?: number | null
+  end_offset?: number | null
+  section?: string | null
+  page_start?: number | null
+  page_end?: number | null
+  symbol?: string | null
+  language?: string | null
+  label?: string
+  similarity: number
+  rerank_score?: number | null
+  snippet: string
+  citation: number
+  cited: boolean
+}
+
+export interface CitedChunk extends MessageSource {
+  content?: string | null
 }
 
 export interface Conversation {
@@ -19,36 +44,238 @@ export interface Conversation {
 
 export interface Document {
   id: string
+  knowledge_base_id: string
   name: string
   content: string
   file_type?: string
   file_size: number
   created_at: number
   updated_at: number
+  metadata?: string | null // JSON：网页标题、canonical 链接等
+  content_hash?: string | null // 正文的 SHA-256
+  embedding_model?: string | null // 生成向量的 Embedding 服务标识
 }
 
 export interface AskQuestionRequest {
   question: string
   conversation_id?: string
+  knowledge_base_ids?: string[] // 检索的知识库，为空时检索全部知识库
 }
 
 export interface AskQuestionResponse {
   success: boolean
   answer: string
-  sources: string[]
+  sources: MessageSource[]
+  conversation_id: string
+}
+
+export interface AskQuestionStreamStarted {
+  conversation_id: string
+  message_id: string
+}
+
+export interface ChatDeltaEvent {
   conversation_id: string
+  message_id: string
+  delta: string
+}
+
+export interface ChatDoneEvent {
+  conversation_id: string
+  message_id: string
+  answer: string
+  sources: MessageSource[]
+}
+
+export interface ChatErrorEvent {
+  conversation_id: string
+  message_id: string
+  error: string
+}
+
+export interface AskQuestionStreamHandlers {
+  onDelta: (delta: string) => void
+  onDone: (event: ChatDoneEvent) => void
+  onError: (error: string) => void
 }
 
 export interface UploadDocumentRequest {
   name: string
   content: string
   file_type?: string
+  chunk_strategy?: ChunkStrategy | null // 为空时使用配置中的默认分块策略
+  knowledge_base_id?: string | null // 为空时导入默认知识库
 }
 
 export interface UploadDocumentResponse {
   success: boolean
   message: string
   document_id?: string
+  job_id?: string // 后台导入任务 ID
+}
+
+export interface RepairReport {
+  checked: number
+  removed: string[] // 没有任何文档块、已删除的文档名称
+  reembedded_chunks: number
+  reloaded: number
+  orphaned: number
+}
+
+export type IngestionJobStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled'
+
+// duplicate：与已有文档内容相同；unchanged：重新导入时内容未变化（两者均跳过导入）
+export type IngestionStage = 'extracting' | 'embedding' | 'indexing' | 'duplicate' | 'unchanged'
+
+export interface IngestionJob {
+  id: string
+  document_id: string
+  knowledge_base_id: string
+  name: string
+  chunk_strategy?: ChunkStrategy | null
+  status: IngestionJobStatus
+  stage: IngestionStage
+  progress_done: number // 已完成向量化的块数
+  progress_total: number
+  error?: string | null
+  created_at: number
+  updated_at: number
+}
+
+export interface ImportFolderRequest {
+  path: string
+  include?: string[] // 只导入匹配的文件，例如 docs/**/*.md
+  exclude?: string[] // 排除的文件或目录，例如 node_modules
+  chunk_strategy?: ChunkStrategy | null
+  knowledge_base_id?: string | null
+  import_id?: string
+}
+
+export type ImportStatus = 'imported' | 'duplicate' | 'unsupported' | 'failed'
+
+export interface ImportFileResult {
+  path: string
+  status: ImportStatus
+  message?: string | null
+  document_id?: string | null
+}
+
+export interface ImportProgressEvent {
+  import_id: string
+  processed: number
+  total: number
+  file: ImportFileResult
+}
+
+export interface ImportSummary {
+  import_id: string
+  total: number
+  imported: number
+  duplicates: number
+  unsupported: number
+  failed: number
+  elapsed_ms: number
+  files: ImportFileResult[]
+}
+
+export interface WatchedFolder {
+  id: string
+  knowledge_base_id: string
+  path: string
+  include: string // JSON 数组
+  exclude: string // JSON 数组
+  chunk_strategy?: ChunkStrategy | null
+  created_at: number
+  last_synced_at?: number | null
+}
+
+export interface AddWatchedFolderRequest {
+  path: string
+  include?: string[]
+  exclude?: string[]
+  chunk_strategy?: ChunkStrategy | null
+  knowledge_base_id?: string | null
+}
+
+export interface FolderSyncSummary {
+  queued: number // 新增或修改、已加入导入队列的文件数
+  removed: number // 原文件已删除、随之删除的文档数
+  errors: string[] // 同步失败的文件及原因
+}
+
+// 知识库设置：为空的字段使用全局 RAG 配置
+export interface KnowledgeBaseSettings {
+  chunk_size?: number | null
+  chunk_overlap?: number | null
+  chunk_unit?: ChunkUnit | null
+  chunk_strategy?: ChunkStrategy | null
+  embedding_provider?: EmbeddingProviderKind | null
+  embedding_base_url?: string | null
+  embedding_api_key?: string | null
+  embedding_model?: string | null
+}
+
+export interface KnowledgeBase {
+  id: string
+  name: string
+  description?: string | null
+  settings: string // JSON：KnowledgeBaseSettings
+  created_at: number
+  updated_at: number
+}
+
+export interface KnowledgeBaseSummary extends KnowledgeBase {
+  document_count: number
+}
+
+export interface SaveKnowledgeBaseRequest {
+  name: string
+  description?: string | null
+  settings?: KnowledgeBaseSettings
+}
+
+export const DEFAULT_KNOWLEDGE_BASE_ID = 'default'
+
+export interface ConfigResponse {
+  success: boolean
+  message: string
+}
+
+export type EmbeddingProviderKind = 'dashscope' | 'openai' | 'stub'
+
+export type LlmProviderKind = 'dashscope' | 'openai' | 'ollama'
+
+export type RerankProviderKind = 'none' | 'dashscope' | 'openai' | 'llm'
+
+export type ChunkUnit = 'chars' | 'tokens'
+
+export type ChunkStrategy = 'fixed' | 'recursive' | 'semantic'
+
+export interface RAGConfig {
+  qwen_api_key: string
+  embedding_provider: EmbeddingProviderKind
+  embedding_base_url?: string | null
+  embedding_api_key?: string | null
+  embedding_model: string
+  llm_provider: LlmProviderKind
+  llm_base_url?: string | null
+  llm_api_key?: string | null
+  llm_model: string
+  chunk_size: number
+  chunk_overlap: number
+  chunk_unit: ChunkUnit
+  chunk_strategy: ChunkStrategy
+  semantic_breakpoint_percentile: number
+  max_context_tokens: number
+  top_k: number
+  history_turns: number
+  condense_question: boolean
+  hybrid_search: boolean
+  rerank_provider: RerankProviderKind
+  rerank_base_url?: string | null
+  rerank_api_key?: string | null
+  rerank_model: string
+  candidate_k: number
 }
 
 // ==================== API 函数 ====================
@@ -67,6 +294,20 @@ export async function getApiKeyStatus(): Promise<boolean> {
   return await invoke('get_api_key_status')
 }
 
+/**
+ * 获取 RAG 配置
+ */
+export async function getRagConfig(): Promise<RAGConfig> {
+  return await invoke('get_rag_config')
+}
+
+/**
+ * 更新 RAG 配置（Embedding / LLM 提供方、模型等）
+ */
+export async function setRagConfig(config: RAGConfig): Promise<ConfigResponse> {
+  return await invoke('set_rag_config', { config })
+}
+
 /**
  * 提问（RAG 问答）
  */
@@ -74,6 +315,56 @@ export async function askQuestion(request: AskQuestionRequest): Promise<AskQuest
   return await invoke('ask_question', { request })
 }
 
+/**
+ * 流式提问：回答通过 chat://delta 事件逐段推送，结束或出错后自动取消监听
+ */
+export async function askQuestionStream(
+  request: AskQuestionRequest,
+  handlers: AskQuestionStreamHandlers
+): Promise<AskQuestionStreamStarted> {
+  // 事件可能早于 invoke 返回到达，先缓存，拿到 message_id 后再分发
+  let messageId: string | undefined
+  const pending: Array<{ id: string; run: () => void }> = []
+  const dispatch = (id: string, run: () => void) => {
+    if (messageId === undefined) {
+      pending.push({ id, run })
+    } else if (id === messageId) {
+      run()
+    }
+  }
+
+  const unlisteners: UnlistenFn[] = []
+  const cleanup = () => unlisteners.forEach((unlisten) => unlisten())
+
+  unlisteners.push(
+    await listen<ChatDeltaEvent>('chat://delta', ({ payload }) =>
+      dispatch(payload.message_id, () => handlers.onDelta(payload.delta))
+    ),
+    await listen<ChatDoneEvent>('chat://done', ({ payload }) =>
+      dispatch(payload.message_id, () => {
+        cleanup()
+        handlers.onDone(payload)
+      })
+    ),
+    await listen<ChatErrorEvent>('chat://error', ({ payload }) =>
+      dispatch(payload.message_id, () => {
+        cleanup()
+        handlers.onError(payload.error)
+      })
+    )
+  )
+
+  try {
+    const started = await invoke<AskQuestionStreamStarted>('ask_question_stream', { request })
+    messageId = started.message_id
+    pending.filter((event) => event.id === messageId).forEach((event) => event.run())
+    return started
+  } catch (error) {
+    cleanup()
+    throw error
+  }
+}
+
 /**
  * 获取所有对话列表
  */
@@ -88,6 +379,13 @@ export async function getMessages(conversationId: string): Promise<Message[]> {
   return await invoke('get_messages', { conversationId })
 }
 
+/**
+ * 获取消息引用的文档块内容
+ */
+export async function getMessageSources(messageId: string): Promise<CitedChunk[]> {
+  return await invoke('get_message_sources', { messageId })
+}
+
 /**
  * 删除对话
  */
@@ -103,17 +401,129 @@ export async function uploadDocument(request: UploadDocumentRequest): Promise<Up
 }
 
 /**
- * 从文件路径上传文档（支持 PDF）
+ * 从文件路径上传文档（支持 PDF），可指定该文档的分块策略
+ */
+export async function uploadDocumentFromPath(
+  filePath: string,
+  chunkStrategy?: ChunkStrategy,
+  knowledgeBaseId?: string
+): Promise<UploadDocumentResponse> {
+  return await invoke('upload_document_from_path', { filePath, chunkStrategy, knowledgeBaseId })
+}
+
+/**
+ * 导入整个文件夹：每处理完一个文件通过 import://progress 事件回调进度，完成后返回汇总
+ */
+export async function importFolder(
+  request: ImportFolderRequest,
+  onProgress?: (event: ImportProgressEvent) => void
+): Promise<ImportSummary> {
+  const importId = request.import_id ?? crypto.randomUUID()
+  const unlisten = await listen<ImportProgressEvent>('import://progress', ({ payload }) => {
+    if (payload.import_id === importId) {
+      onProgress?.(payload)
+    }
+  })
+
+  try {
+    return await invoke<ImportSummary>('import_folder', { request: { ...request, import_id: importId } })
+  } finally {
+    unlisten()
+  }
+}
+
+/**
+ * 获取最近的导入任务
+ */
+export async function getIngestionJobs(): Promise<IngestionJob[]> {
+  return await invoke('get_ingestion_jobs')
+}
+
+/**
+ * 取消导入任务（已写入的文档会被删除）
  */
-export async function uploadDocumentFromPath(filePath: string): Promise<UploadDocumentResponse> {
-  return await invoke('upload_document_from_path', { filePath })
+export async function cancelIngestionJob(jobId: string): Promise<boolean> {
+  return await invoke('cancel_ingestion_job', { jobId })
 }
 
 /**
- * 获取所有文档列表
+ * 重试失败的导入任务
  */
-export async function getDocuments(): Promise<Document[]> {
-  return await invoke('get_documents')
+export async function retryIngestionJob(jobId: string): Promise<boolean> {
+  return await invoke('retry_ingestion_job', { jobId })
+}
+
+/**
+ * 监听导入任务的状态和进度变化
+ */
+export async function onIngestionProgress(listener: (job: IngestionJob) => void): Promise<UnlistenFn> {
+  return await listen<IngestionJob>('ingestion://progress', ({ payload }) => listener(payload))
+}
+
+/**
+ * 添加监听文件夹：文件变化后自动重新导入，删除的文件同步删除对应文档
+ */
+export async function addWatchedFolder(request: AddWatchedFolderRequest): Promise<WatchedFolder> {
+  return await invoke('add_watched_folder', { request })
+}
+
+/**
+ * 获取所有监听文件夹
+ */
+export async function getWatchedFolders(): Promise<WatchedFolder[]> {
+  return await invoke('get_watched_folders')
+}
+
+/**
+ * 移除监听文件夹，可同时删除从该文件夹导入的文档
+ */
+export async function removeWatchedFolder(folderId: string, removeDocuments: boolean): Promise<boolean> {
+  return await invoke('remove_watched_folder', { folderId, removeDocuments })
+}
+
+/**
+ * 立即同步监听文件夹
+ */
+export async function syncWatchedFolder(folderId: string): Promise<FolderSyncSummary> {
+  return await invoke('sync_watched_folder', { folderId })
+}
+
+/**
+ * 获取文档列表，指定知识库时只返回该知识库的文档
+ */
+export async function getDocuments(knowledgeBaseId?: string): Promise<Document[]> {
+  return await invoke('get_documents', { knowledgeBaseId })
+}
+
+/**
+ * 获取所有知识库及其文档数
+ */
+export async function getKnowledgeBases(): Promise<KnowledgeBaseSummary[]> {
+  return await invoke('get_knowledge_bases')
+}
+
+/**
+ * 创建知识库
+ */
+export async function createKnowledgeBase(request: SaveKnowledgeBaseRequest): Promise<KnowledgeBase> {
+  return await invoke('create_knowledge_base', { request })
+}
+
+/**
+ * 修改知识库（已有文档时不能修改 Embedding 设置）
+ */
+export async function updateKnowledgeBase(
+  knowledgeBaseId: string,
+  request: SaveKnowledgeBaseRequest
+): Promise<KnowledgeBase> {
+  return await invoke('update_knowledge_base', { knowledgeBaseId, request })
+}
+
+/**
+ * 删除知识库及其全部文档（默认知识库不能删除）
+ */
+export async function deleteKnowledgeBase(knowledgeBaseId: string): Promise<boolean> {
+  return await invoke('delete_knowledge_base', { knowledgeBaseId })
 }
 
 /**
@@ -123,6 +533,20 @@ export async function deleteDocument(documentId: string): Promise<boolean> {
   return await invoke('delete_document', { documentId })
 }
 
+/**
+ * 从原文件重新导入文档，只为内容变化的块重新生成向量
+ */
+export async function reimportDocument(documentId: string): Promise<UploadDocumentResponse> {
+  return await invoke('reimport_document', { documentId })
+}
+
+/**
+ * 修复知识库：删除没有文档块的文档，补全缺失的向量，重新同步向量存储
+ */
+export async function repairDocuments(): Promise<RepairReport> {
+  return await invoke('repair_documents')
+}
+
 /**
  * 读取文件内容
  */